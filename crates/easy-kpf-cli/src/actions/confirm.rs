@@ -2,7 +2,9 @@ use crate::app::{App, ConfirmAction, Mode};
 use crossterm::event::{KeyCode, KeyEvent};
 use easy_kpf_core::Result;
 
-use super::port_forward::{start_port_forward, stop_port_forward};
+use super::port_forward::{
+  adopt_port_owner, kill_port_owners, pin_host_keys, start_port_forwards, stop_port_forward,
+};
 
 pub async fn handle_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
   match key.code {
@@ -15,6 +17,18 @@ pub async fn handle_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         app.mode = Mode::Normal;
       }
     }
+    KeyCode::Char('a') if matches!(app.confirm_action, Some(ConfirmAction::PortOwners(..))) => {
+      if let Some(ConfirmAction::PortOwners(config, conflicts)) = app.confirm_action.take() {
+        adopt_port_owner(app, &config, &conflicts);
+      }
+      app.mode = Mode::Normal;
+    }
+    KeyCode::Char('k') if matches!(app.confirm_action, Some(ConfirmAction::PortOwners(..))) => {
+      if let Some(ConfirmAction::PortOwners(_, conflicts)) = app.confirm_action.take() {
+        kill_port_owners(app, &conflicts);
+      }
+      app.mode = Mode::Normal;
+    }
     KeyCode::Char('y') => {
       if let Some(action) = app.confirm_action.take() {
         match action {
@@ -27,14 +41,17 @@ pub async fn handle_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.configs.retain(|c| c.name != name);
             app.save_configs()?;
             app.update_visual_order();
-            app.set_status(format!("Deleted {}", name));
+            app.set_status(format!("Deleted {} (u to undo)", name));
           }
           ConfirmAction::StartAll => {
-            let configs: Vec<_> = app.configs.clone();
-            for config in configs {
-              start_port_forward(app, config).await?;
-            }
-            app.set_status("Started all port forwards");
+            let configs: Vec<_> = app
+              .configs
+              .iter()
+              .filter(|config| !app.running_services.contains_key(&config.name))
+              .cloned()
+              .collect();
+            app.set_status("Starting all port forwards...");
+            start_port_forwards(app, configs, "all port forwards");
           }
           ConfirmAction::StopAll => {
             let names: Vec<String> = app.running_services.keys().cloned().collect();
@@ -43,6 +60,12 @@ pub async fn handle_confirm_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             }
             app.set_status("Stopped all port forwards");
           }
+          ConfirmAction::PortOwners(_, conflicts) => {
+            kill_port_owners(app, &conflicts);
+          }
+          ConfirmAction::PinHostKeys(keys) => {
+            pin_host_keys(app, &keys);
+          }
           ConfirmAction::CancelEdit(_) => {
             // User confirmed they want to discard changes
             app.cancel_edit();
@@ -2,9 +2,15 @@ use crate::app::{App, ConfirmAction, Mode};
 use crate::vim::{VimMode, VimTransition};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use easy_kpf_core::Result;
+use std::path::Path;
 use tui_textarea::Input;
 
 pub fn handle_edit_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+  // Included entries can be browsed but not changed
+  if let Some(path) = app.edit_included_from().cloned() {
+    return handle_read_only(app, key, &path);
+  }
+
   // Check if in command mode (: commands like :w, :q)
   if app.command_mode {
     return handle_command_mode(app, key);
@@ -67,7 +73,6 @@ pub fn handle_edit_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     // Ctrl+s to save
     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
       app.save_edit()?;
-      app.set_status("Configuration saved");
     }
     KeyCode::Tab => {
       // Accept current suggestion if any, then move to next field
@@ -97,7 +102,6 @@ fn handle_typing_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     // Ctrl+s to save
     KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
       app.save_edit()?;
-      app.set_status("Configuration saved");
     }
     KeyCode::Tab => {
       app.exit_typing_mode();
@@ -237,7 +241,6 @@ fn handle_command_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         "w" | "write" => {
           // Save and stay in edit mode
           app.save_edit()?;
-          app.set_status("Configuration saved");
         }
         "q" | "quit" => {
           // Quit without saving - show confirmation only if there are changes
@@ -253,7 +256,6 @@ fn handle_command_mode(app: &mut App, key: KeyEvent) -> Result<()> {
         "wq" | "x" => {
           // Save and quit
           app.save_edit()?;
-          app.set_status("Configuration saved");
         }
         "q!" => {
           // Force quit without confirmation
@@ -281,3 +283,16 @@ fn handle_command_mode(app: &mut App, key: KeyEvent) -> Result<()> {
   }
   Ok(())
 }
+
+fn handle_read_only(app: &mut App, key: KeyEvent, included_from: &Path) -> Result<()> {
+  match key.code {
+    KeyCode::Esc | KeyCode::Char('q') => app.cancel_edit(),
+    KeyCode::Tab | KeyCode::Down | KeyCode::Char('j') => app.next_edit_field(),
+    KeyCode::BackTab | KeyCode::Up | KeyCode::Char('k') => app.prev_edit_field(),
+    _ => app.set_status(format!(
+      "Read-only: included from {}",
+      included_from.display()
+    )),
+  }
+  Ok(())
+}
@@ -3,6 +3,7 @@ mod edit;
 mod help;
 mod normal;
 mod port_forward;
+mod profiles;
 mod search;
 mod visual;
 
@@ -12,7 +13,7 @@ use easy_kpf_core::Result;
 
 // Re-export for external use (e.g., from main.rs if needed)
 #[allow(unused_imports)]
-pub use port_forward::{start_port_forward, stop_port_forward};
+pub use port_forward::{poll_starts, start_port_forward, stop_port_forward};
 
 pub async fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
   match app.mode {
@@ -22,5 +23,6 @@ pub async fn handle_key(app: &mut App, key: KeyEvent) -> Result<()> {
     Mode::Help => help::handle_help_mode(app, key),
     Mode::Edit | Mode::Create => edit::handle_edit_mode(app, key),
     Mode::Confirm => confirm::handle_confirm_mode(app, key).await,
+    Mode::Profiles => profiles::handle_profiles_mode(app, key).await,
   }
 }
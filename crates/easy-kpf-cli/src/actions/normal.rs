@@ -1,9 +1,11 @@
 use crate::app::{App, ConfirmAction, Mode, Panel};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use easy_kpf_core::Result;
+use easy_kpf_core::{services::dependencies, ConfigSource, Result};
 use std::process::Command;
 
-use super::port_forward::{start_port_forward, stop_port_forward};
+use super::port_forward::{
+  show_port_owners, start_port_forward, stop_port_forward, verify_host_key,
+};
 
 pub async fn handle_normal_mode(app: &mut App, key: KeyEvent) -> Result<()> {
   match key.code {
@@ -74,9 +76,37 @@ pub async fn handle_normal_mode(app: &mut App, key: KeyEvent) -> Result<()> {
 
     // Delete config
     KeyCode::Char('d') | KeyCode::Delete => {
-      if let Some(name) = app.selected_name() {
-        app.confirm_action = Some(ConfirmAction::Delete(name));
-        app.mode = Mode::Confirm;
+      if let Some(config) = app.selected_config().cloned() {
+        if let ConfigSource::Include(path) = config.source {
+          app.set_status(format!("Read-only: included from {}", path.display()));
+        } else {
+          app.confirm_action = Some(ConfirmAction::Delete(config.name));
+          app.mode = Mode::Confirm;
+        }
+      }
+    }
+
+    // Show who holds the selected config's local ports
+    KeyCode::Char('o') => {
+      if let Some(config) = app.selected_config().cloned() {
+        show_port_owners(app, &config);
+      }
+    }
+
+    // Fetch the SSH host key and offer to pin it
+    KeyCode::Char('K') => {
+      if let Some(config) = app.selected_config().cloned() {
+        verify_host_key(app, &config).await;
+      }
+    }
+
+    // Pick a profile to start or stop
+    KeyCode::Char('p') => {
+      if app.profiles.is_empty() {
+        app.set_status("No profiles defined; add a `profiles:` map to the config file");
+      } else {
+        app.profile_index = app.profile_index.min(app.profiles.len() - 1);
+        app.mode = Mode::Profiles;
       }
     }
 
@@ -91,6 +121,10 @@ pub async fn handle_normal_mode(app: &mut App, key: KeyEvent) -> Result<()> {
       app.mode = Mode::Help;
     }
 
+    // Undo/redo config changes
+    KeyCode::Char('u') => app.undo(),
+    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+
     // Refresh
     KeyCode::Char('r') => {
       app.sync_running_services();
@@ -117,9 +151,19 @@ async fn toggle_selected(app: &mut App) -> Result<()> {
 
   if app.running_services.contains_key(&name) {
     stop_port_forward(app, &name)?;
-    app.set_status(format!("Stopped {}", name));
+    let running: Vec<String> = app.running_services.keys().cloned().collect();
+    let dependents = dependencies::running_dependents(&name, &app.configs, &running);
+    if dependents.is_empty() {
+      app.set_status(format!("Stopped {}", name));
+    } else {
+      app.set_status(format!(
+        "Stopped {}; still running and depending on it: {}",
+        name,
+        dependents.join(", ")
+      ));
+    }
   } else {
-    start_port_forward(app, config).await?;
+    start_port_forward(app, config);
   }
 
   Ok(())
@@ -140,7 +184,8 @@ fn open_in_editor(app: &mut App) -> Result<()> {
   if let Ok(status) = status {
     if status.success() {
       app.load_configs()?;
-      app.set_status("Reloaded configuration");
+      app.record_change();
+      app.set_status("Reloaded configuration (u to undo)");
     }
   }
 
@@ -1,11 +1,18 @@
-use crate::app::{App, LogEntry};
+use crate::app::{App, ConfirmAction, LogEntry, Mode};
 use crate::executor::TokioCommandExecutor;
+use crate::state::StartUpdate;
+use async_trait::async_trait;
 use easy_kpf_core::{
-  services::{KubectlCommandBuilder, SshCommandBuilder},
+  services::{
+    custom_command,
+    dependencies::{self, ForwardStarter},
+    host_keys, kubectl_proxy, native_forward, port_check, port_owner, ssh_master, tcp_relay,
+    template, ConfigService, HostKey, KubectlCommandBuilder, PortConflict, Variables,
+  },
   traits::ProcessEvent,
-  CommandExecutor, ForwardType, PortForwardConfig, Result,
+  AppError, CommandExecutor, ForwardType, PortForwardConfig, ProcessManager, Result,
 };
-use std::process::Command;
+use std::sync::mpsc as std_mpsc;
 use tokio::sync::mpsc;
 
 /// Extract local port from a port mapping string (e.g., "8080:80" -> 8080, "8080" -> 8080)
@@ -41,11 +48,11 @@ fn find_privileged_ports(config: &PortForwardConfig) -> Vec<u16> {
     .collect()
 }
 
-/// Check for privileged ports and warn if not running as root
-async fn check_privileged_ports(app: &mut App, config: &PortForwardConfig, name: &str) -> bool {
+/// Refuse ports below 1024 when not running as root
+fn check_privileged_ports(config: &PortForwardConfig) -> Result<()> {
   let privileged_ports = find_privileged_ports(config);
   if privileged_ports.is_empty() || is_running_as_root() {
-    return true; // OK to proceed
+    return Ok(());
   }
 
   let ports_str = privileged_ports
@@ -53,47 +60,44 @@ async fn check_privileged_ports(app: &mut App, config: &PortForwardConfig, name:
     .map(std::string::ToString::to_string)
     .collect::<Vec<_>>()
     .join(", ");
-  let warning = format!(
-    "Warning: Port(s) {} require root privileges. Run with sudo or use ports >= 1024.",
+  Err(AppError::PortForward(format!(
+    "Port(s) {} require root privileges. Run with sudo or use ports >= 1024.",
     ports_str
-  );
+  )))
+}
 
-  app.set_status(warning.clone());
-  let _ = app
-    .log_sender
-    .send((
-      name.to_string(),
-      LogEntry {
-        line: warning,
-        is_stderr: true,
-      },
-    ))
-    .await;
+fn kubectl_builder(config_service: &ConfigService) -> KubectlCommandBuilder {
+  let kubectl_path = config_service
+    .load_kubectl_path()
+    .unwrap_or_else(|_| "kubectl".to_string());
+  let kubeconfig = config_service.load_kubeconfig_path().ok().flatten();
 
-  false // Cannot proceed
+  KubectlCommandBuilder::new(kubectl_path, kubeconfig)
 }
 
 /// Build the command for the port forward based on forward type
-fn build_command(
-  app: &App,
+async fn build_command(
+  config_service: &ConfigService,
+  executor: &TokioCommandExecutor,
   config: &PortForwardConfig,
-) -> (String, Vec<String>, Vec<(String, String)>) {
+) -> Result<(String, Vec<String>, Vec<(String, String)>)> {
   match config.forward_type {
     ForwardType::Ssh => {
-      let builder = SshCommandBuilder::new();
+      let builder = config_service.ssh_command_builder();
       let cmd = builder.build_port_forward_command(config);
-      (cmd.0, cmd.1, Vec::new())
+      Ok((cmd.0, cmd.1, Vec::new()))
     }
     ForwardType::Kubectl => {
-      let kubectl_path = app
-        .config_service
-        .load_kubectl_path()
-        .unwrap_or_else(|_| "kubectl".to_string());
-      let kubeconfig = app.config_service.load_kubeconfig_path().ok().flatten();
-
-      let builder = KubectlCommandBuilder::new(kubectl_path, kubeconfig);
-      builder.build_port_forward_command(config)
+      let builder = kubectl_builder(config_service);
+      // Label selector targets point at whichever pod is ready right now
+      let resolved = builder.resolve_target(executor, config).await?;
+      Ok(builder.build_port_forward_command(&resolved))
     }
+    ForwardType::Command => Ok(custom_command::build_command(config)),
+    ForwardType::Native | ForwardType::Tcp => Err(AppError::PortForward(format!(
+      "{} runs in-process, not as a command",
+      config.name
+    ))),
   }
 }
 
@@ -135,6 +139,7 @@ async fn handle_process_event(
     }
     ProcessEvent::Stderr(data) => {
       let line = String::from_utf8_lossy(&data).to_string();
+      let line = host_keys::classify_ssh_error(&line).map_or(line, |e| e.to_string());
       let _ = log_sender
         .send((
           service_name.to_string(),
@@ -174,39 +179,344 @@ async fn handle_process_event(
   }
 }
 
-pub async fn start_port_forward(app: &mut App, config: PortForwardConfig) -> Result<()> {
-  let name = config.name.clone();
+/// Start `config` after its `depends_on`, in the background
+pub fn start_port_forward(app: &mut App, config: PortForwardConfig) {
+  // When started on its own, offer to deal with whatever holds a busy port
+  let offer_port_owners = app.mode == Mode::Normal;
+  spawn_starts(app, vec![config], None, offer_port_owners);
+}
 
-  // Check for privileged ports when not running as root
-  if !check_privileged_ports(app, &config, &name).await {
-    return Ok(());
+/// Start `configs` one after another in the background. `group` names them in the
+/// summary shown once all have been tried.
+pub fn start_port_forwards(app: &mut App, configs: Vec<PortForwardConfig>, group: &str) {
+  spawn_starts(app, configs, Some(group.to_string()), false);
+}
+
+/// Starts run in a task so waiting for a forward or its dependencies to be ready
+/// doesn't freeze the TUI; they report back through `poll_starts`
+fn spawn_starts(
+  app: &mut App,
+  configs: Vec<PortForwardConfig>,
+  group: Option<String>,
+  offer_port_owners: bool,
+) {
+  let starter = Starter {
+    config_service: app.config_service.clone(),
+    process_manager: app.process_manager.clone(),
+    configs: app.configs.clone(),
+    variables: app.variables.clone(),
+    log_sender: app.log_sender.clone(),
+    updates: app.start_tx.clone(),
+    offer_port_owners,
+  };
+  if let [config] = configs.as_slice() {
+    app.set_status(format!("Starting {}...", config.name));
+  }
+
+  tokio::spawn(async move {
+    let mut started = 0;
+    let mut failed = Vec::new();
+    for config in configs {
+      // An earlier one may have started it as a dependency
+      if starter
+        .process_manager
+        .contains_process(&config.name)
+        .unwrap_or(false)
+      {
+        continue;
+      }
+      let name = config.name.clone();
+      if starter.start(config).await {
+        started += 1;
+      } else {
+        failed.push(name);
+      }
+    }
+
+    let Some(group) = group else {
+      return;
+    };
+    if failed.is_empty() {
+      starter.status(format!("Started {} services of {}", started, group));
+    } else {
+      starter.status(format!(
+        "Started {} services of {}; failed: {} (see logs)",
+        started,
+        group,
+        failed.join(", ")
+      ));
+    }
+  });
+}
+
+/// Apply what background starts reported (call this in the main loop)
+pub fn poll_starts(app: &mut App) {
+  while let Ok(update) = app.start_rx.try_recv() {
+    match update {
+      StartUpdate::Status(message) => app.set_status(message),
+      StartUpdate::Started(name, pid) => {
+        app.running_services.insert(name, pid);
+      }
+      StartUpdate::PortBusy(config) => {
+        if app.mode == Mode::Normal {
+          show_port_owners(app, &config);
+        }
+      }
+    }
   }
+}
 
-  // Build and spawn the command
-  let (program, args, env) = build_command(app, &config);
-  let executor = TokioCommandExecutor::new();
-  let (handle, rx) = executor.spawn(&program, &args, &env).await?;
+/// What starting a forward needs from the `App`, owned so it can run in a task
+struct Starter {
+  config_service: ConfigService,
+  process_manager: ProcessManager,
+  configs: Vec<PortForwardConfig>,
+  variables: Variables,
+  log_sender: mpsc::Sender<(String, LogEntry)>,
+  updates: std_mpsc::Sender<StartUpdate>,
+  offer_port_owners: bool,
+}
 
-  // Register the process
-  let pid = handle.pid;
-  app.running_services.insert(name.clone(), pid);
-  let _ = app.process_manager.add_process(name.clone(), pid, config);
-  app.set_status(format!("Started {} (pid {})", name, pid));
+impl Starter {
+  /// Start `config` after its `depends_on`. Returns whether it started; failures
+  /// are reported.
+  async fn start(&self, config: PortForwardConfig) -> bool {
+    let name = config.name.clone();
+    let started =
+      dependencies::start_with_dependencies(self, &self.process_manager, config, &self.configs)
+        .await;
+    if let Err(e) = &started {
+      self.report_error(&name, e).await;
+    }
+    started.is_ok()
+  }
 
-  // Spawn output reader
-  spawn_output_reader(name, app.log_sender.clone(), rx);
+  /// Start one forward, ignoring `depends_on`. Returns the status message.
+  async fn start_single(&self, config: PortForwardConfig) -> Result<String> {
+    let name = config.name.clone();
+    check_privileged_ports(&config)?;
 
-  Ok(())
+    // Held until the process is registered so another frontend can't start it too
+    let _claim = self.process_manager.claim_start(&name)?;
+
+    // Resolve placeholders and check local ports are free
+    let prepared = template::expand_config(&config, &self.variables)
+      .map_err(AppError::Validation)
+      .and_then(|expanded| port_check::prepare_local_ports(&expanded))
+      .inspect_err(|e| {
+        if self.offer_port_owners && matches!(e, AppError::PortForward(_)) {
+          let _ = self
+            .updates
+            .send(StartUpdate::PortBusy(Box::new(config.clone())));
+        }
+      })?;
+
+    let (pid, rx) = self.spawn_forward(&prepared.config).await?;
+
+    // Register the process
+    let _ = self.updates.send(StartUpdate::Started(name.clone(), pid));
+    let reassigned = prepared.summary();
+    let started = if prepared.config.is_multiplexed() {
+      format!(
+        "Started {} on the shared connection to {} (pid {})",
+        name,
+        ssh_master::master_key(&prepared.config),
+        pid
+      )
+    } else {
+      format!("Started {} (pid {})", name, pid)
+    };
+    let _ = self
+      .process_manager
+      .add_process(name.clone(), pid, prepared.config);
+
+    // Spawn output reader
+    if let Some(rx) = rx {
+      spawn_output_reader(name, self.log_sender.clone(), rx);
+    }
+
+    if reassigned.is_empty() {
+      Ok(started)
+    } else {
+      Ok(format!("{} on {}", started, reassigned))
+    }
+  }
+
+  /// Start the forward's process, run it as a task when it runs in-process, or
+  /// open it on the shared SSH master when multiplexed, which has no output of
+  /// its own
+  async fn spawn_forward(
+    &self,
+    config: &PortForwardConfig,
+  ) -> Result<(u32, Option<mpsc::Receiver<ProcessEvent>>)> {
+    if config.runs_in_process() {
+      let forward = match config.forward_type {
+        ForwardType::Tcp => tcp_relay::start(config).await?,
+        ForwardType::Kubectl => {
+          let builder = kubectl_builder(&self.config_service);
+          kubectl_proxy::start(TokioCommandExecutor::new(), builder, config).await?
+        }
+        _ => {
+          let kubeconfig = self.config_service.load_kubeconfig_path().ok().flatten();
+          let client = native_forward::client(kubeconfig.as_deref(), &config.context).await?;
+          native_forward::start(client, config).await?
+        }
+      };
+      let pid = self.process_manager.attach_task(&config.name, &forward)?;
+      return Ok((pid, Some(forward.events)));
+    }
+    if config.is_multiplexed() {
+      let builder = self.config_service.ssh_command_builder();
+      let process_manager = self.process_manager.clone();
+      let config = config.clone();
+      let pid = tokio::task::spawn_blocking(move || {
+        ssh_master::attach(&builder, &process_manager, &config)
+      })
+      .await
+      .unwrap_or_else(|e| Err(AppError::System(e.to_string())))?;
+      return Ok((pid, None));
+    }
+
+    let executor = TokioCommandExecutor::new();
+    if config.forward_type == ForwardType::Command {
+      let (handle, rx) = custom_command::spawn(&executor, config).await?;
+      return Ok((handle.pid, Some(rx)));
+    }
+    let (program, args, env) = build_command(&self.config_service, &executor, config).await?;
+    let (handle, rx) = executor.spawn(&program, &args, &env).await?;
+    Ok((handle.pid, Some(rx)))
+  }
+
+  fn status(&self, message: String) {
+    let _ = self.updates.send(StartUpdate::Status(message));
+  }
+
+  async fn report_error(&self, name: &str, e: &AppError) {
+    self.status(format!("Failed to start {}: {}", name, e));
+    let _ = self
+      .log_sender
+      .send((
+        name.to_string(),
+        LogEntry {
+          line: e.to_string(),
+          is_stderr: true,
+        },
+      ))
+      .await;
+  }
+}
+
+/// Open the adopt/kill dialog for processes holding `config`'s local ports
+pub fn show_port_owners(app: &mut App, config: &PortForwardConfig) {
+  let configs = template::expand_configs_lenient(&app.configs, &app.variables);
+  let config = template::expand_config(config, &app.variables).unwrap_or_else(|_| config.clone());
+  match port_owner::find_conflicts(&config, &configs, &app.process_manager) {
+    Ok(conflicts) if conflicts.is_empty() => {
+      app.set_status(format!(
+        "Nothing else is using the ports of {}",
+        config.name
+      ));
+    }
+    Ok(conflicts) => {
+      app.confirm_action = Some(ConfirmAction::PortOwners(
+        Box::new(config.clone()),
+        conflicts,
+      ));
+      app.mode = Mode::Confirm;
+    }
+    Err(e) => app.set_status(format!("Failed to look up port owners: {}", e)),
+  }
+}
+
+/// Fetch the host keys of an SSH forward and ask whether to pin them
+pub async fn verify_host_key(app: &mut App, config: &PortForwardConfig) {
+  if config.forward_type != ForwardType::Ssh {
+    app.set_status("Host keys only apply to SSH forwards");
+    return;
+  }
+  app.set_status(format!("Fetching host keys of {}...", config.service));
+  let keys = match app.expand_config(config) {
+    Ok(expanded) => tokio::task::spawn_blocking(move || host_keys::fetch(&expanded))
+      .await
+      .unwrap_or_else(|e| Err(AppError::System(e.to_string()))),
+    Err(e) => Err(e),
+  };
+  match keys {
+    Ok(keys) => {
+      app.confirm_action = Some(ConfirmAction::PinHostKeys(keys));
+      app.mode = Mode::Confirm;
+    }
+    Err(e) => app.set_status(format!("Failed to fetch host keys: {}", e)),
+  }
+}
+
+pub fn pin_host_keys(app: &mut App, keys: &[HostKey]) {
+  match app.config_service.host_keys().pin(keys) {
+    Ok(()) => {
+      let host = keys.first().map_or("", |k| k.host.as_str());
+      app.set_status(format!("Pinned host key of {}", host));
+    }
+    Err(e) => app.set_status(format!("Failed to pin host key: {}", e)),
+  }
+}
+
+pub fn adopt_port_owner(app: &mut App, config: &PortForwardConfig, conflicts: &[PortConflict]) {
+  match port_owner::adopt(config, conflicts, &app.process_manager) {
+    Ok(pid) => {
+      app.sync_running_services();
+      app.set_status(format!("Adopted {} (pid {})", config.name, pid));
+    }
+    Err(e) => app.set_status(e.to_string()),
+  }
+}
+
+pub fn kill_port_owners(app: &mut App, conflicts: &[PortConflict]) {
+  match port_owner::kill_owners(conflicts, &app.process_manager) {
+    Ok(pids) => {
+      app.sync_running_services();
+      let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+      app.set_status(format!("Killed pid {}", pids.join(", ")));
+    }
+    Err(e) => app.set_status(format!("Failed to kill: {}", e)),
+  }
 }
 
 pub fn stop_port_forward(app: &mut App, name: &str) -> Result<()> {
   if let Some(pid) = app.running_services.remove(name) {
-    // Kill the process
-    let _ = Command::new("kill").arg(pid.to_string()).output();
-
-    // Update process manager state
-    let _ = app.process_manager.remove_process(name);
+    // Kills the process, cancels a multiplexed forward on its master, or aborts
+    // an in-process forward's task
+    match app.process_manager.stop_process(name) {
+      Ok(None) => {
+        let _ = ProcessManager::kill_process(pid);
+      }
+      Ok(Some(_)) => {}
+      Err(e) => {
+        // An in-process forward started by another instance keeps running
+        app.running_services.insert(name.to_string(), pid);
+        app.set_status(e.to_string());
+      }
+    }
   }
 
   Ok(())
 }
+
+#[async_trait]
+impl ForwardStarter for Starter {
+  async fn start_forward(&self, config: PortForwardConfig) -> Result<String> {
+    let started = self.start_single(config).await?;
+    self.status(started.clone());
+    Ok(started)
+  }
+
+  fn progress(&self, name: &str, message: &str) {
+    self.status(format!("{}: {}...", name, message));
+    let _ = self.log_sender.try_send((
+      name.to_string(),
+      LogEntry {
+        line: message.to_string(),
+        is_stderr: false,
+      },
+    ));
+  }
+}
@@ -0,0 +1,83 @@
+use crate::app::{App, Mode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use easy_kpf_core::services::profiles;
+use easy_kpf_core::Result;
+
+use super::port_forward::{start_port_forwards, stop_port_forward};
+
+pub async fn handle_profiles_mode(app: &mut App, key: KeyEvent) -> Result<()> {
+  match key.code {
+    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('p') => {
+      app.mode = Mode::Normal;
+    }
+    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+      app.should_quit = true;
+    }
+
+    KeyCode::Char('j') | KeyCode::Down if app.profile_index + 1 < app.profiles.len() => {
+      app.profile_index += 1;
+    }
+    KeyCode::Char('k') | KeyCode::Up => {
+      app.profile_index = app.profile_index.saturating_sub(1);
+    }
+
+    KeyCode::Enter | KeyCode::Char('s') => {
+      if let Some(profile) = app.selected_profile() {
+        start_profile(app, &profile);
+      }
+      app.mode = Mode::Normal;
+    }
+    KeyCode::Char('x') => {
+      if let Some(profile) = app.selected_profile() {
+        stop_profile(app, &profile)?;
+      }
+      app.mode = Mode::Normal;
+    }
+
+    _ => {}
+  }
+  Ok(())
+}
+
+/// Start every forward of `profile` that is not running yet
+fn start_profile(app: &mut App, profile: &str) {
+  let members = match profiles::resolve_profile(&app.profiles, profile, &app.configs) {
+    Ok(members) => members,
+    Err(e) => {
+      app.set_status(e.to_string());
+      return;
+    }
+  };
+
+  let members: Vec<_> = members
+    .into_iter()
+    .filter(|config| !app.running_services.contains_key(&config.name))
+    .collect();
+  app.set_status(format!(
+    "Starting {} services of {}...",
+    members.len(),
+    profile
+  ));
+  start_port_forwards(app, members, profile);
+}
+
+/// Stop every running forward of `profile`
+fn stop_profile(app: &mut App, profile: &str) -> Result<()> {
+  let members = match profiles::resolve_profile(&app.profiles, profile, &app.configs) {
+    Ok(members) => members,
+    Err(e) => {
+      app.set_status(e.to_string());
+      return Ok(());
+    }
+  };
+
+  let mut stopped = 0;
+  for config in members {
+    if app.running_services.contains_key(&config.name) {
+      stop_port_forward(app, &config.name)?;
+      stopped += 1;
+    }
+  }
+  app.set_status(format!("Stopped {} services of {}", stopped, profile));
+  Ok(())
+}
@@ -1,8 +1,10 @@
 use crate::app::{App, Mode};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use easy_kpf_core::Result;
+use easy_kpf_core::services::{exporter, template, ExportFormat, Variables};
+use easy_kpf_core::{AppError, PortForwardConfig, Result};
+use std::path::PathBuf;
 
-use super::port_forward::{start_port_forward, stop_port_forward};
+use super::port_forward::{start_port_forwards, stop_port_forward};
 
 pub async fn handle_visual_mode(app: &mut App, key: KeyEvent) -> Result<()> {
   match key.code {
@@ -36,15 +38,30 @@ pub async fn handle_visual_mode(app: &mut App, key: KeyEvent) -> Result<()> {
       app.select_last();
     }
 
+    // Select the whole context group
+    KeyCode::Char('g') => {
+      app.select_group();
+    }
+
+    // Export selection as a shell script / importable bundle
+    KeyCode::Char('e') => {
+      export_visual_selection(app, ExportFormat::Script);
+      app.exit_visual_mode();
+    }
+    KeyCode::Char('E') => {
+      export_visual_selection(app, ExportFormat::Bundle);
+      app.exit_visual_mode();
+    }
+
     // Toggle all selected services
     KeyCode::Char(' ') | KeyCode::Enter => {
-      toggle_visual_selection(app).await?;
+      toggle_visual_selection(app)?;
       app.exit_visual_mode();
     }
 
     // Start all selected
     KeyCode::Char('s') => {
-      start_visual_selection(app).await?;
+      start_visual_selection(app);
       app.exit_visual_mode();
     }
 
@@ -66,58 +83,45 @@ pub async fn handle_visual_mode(app: &mut App, key: KeyEvent) -> Result<()> {
 }
 
 /// Toggle all services in the visual selection (start stopped ones, stop running ones)
-async fn toggle_visual_selection(app: &mut App) -> Result<()> {
+fn toggle_visual_selection(app: &mut App) -> Result<()> {
   let configs = app.get_visual_selection_configs();
   if configs.is_empty() {
     return Ok(());
   }
 
-  let mut started = 0;
-  let mut stopped = 0;
-
-  for config in configs {
-    let name = config.name.clone();
-    if app.running_services.contains_key(&name) {
-      stop_port_forward(app, &name)?;
-      stopped += 1;
-    } else {
-      start_port_forward(app, config).await?;
-      started += 1;
-    }
+  let (running, stopped): (Vec<_>, Vec<_>) = configs
+    .into_iter()
+    .partition(|config| app.running_services.contains_key(&config.name));
+  for config in &running {
+    stop_port_forward(app, &config.name)?;
   }
 
   app.set_status(format!(
-    "Toggled {} (started {}, stopped {})",
-    started + stopped,
-    started,
-    stopped
+    "Toggled {} (starting {}, stopped {})",
+    running.len() + stopped.len(),
+    stopped.len(),
+    running.len()
   ));
+  if !stopped.is_empty() {
+    start_port_forwards(app, stopped, "the selection");
+  }
   Ok(())
 }
 
 /// Start all stopped services in the visual selection
-async fn start_visual_selection(app: &mut App) -> Result<()> {
-  let configs = app.get_visual_selection_configs();
-  if configs.is_empty() {
-    return Ok(());
-  }
-
-  let mut started = 0;
+fn start_visual_selection(app: &mut App) {
+  let configs: Vec<_> = app
+    .get_visual_selection_configs()
+    .into_iter()
+    .filter(|config| !app.running_services.contains_key(&config.name))
+    .collect();
 
-  for config in configs {
-    let name = config.name.clone();
-    if !app.running_services.contains_key(&name) {
-      start_port_forward(app, config).await?;
-      started += 1;
-    }
-  }
-
-  if started > 0 {
-    app.set_status(format!("Started {} services", started));
-  } else {
+  if configs.is_empty() {
     app.set_status("All selected services already running");
+  } else {
+    app.set_status(format!("Starting {} services...", configs.len()));
+    start_port_forwards(app, configs, "the selection");
   }
-  Ok(())
 }
 
 /// Stop all running services in the visual selection
@@ -144,3 +148,47 @@ fn stop_visual_selection(app: &mut App) -> Result<()> {
   }
   Ok(())
 }
+
+/// Write the visual selection to the working directory
+fn export_visual_selection(app: &mut App, format: ExportFormat) {
+  let configs = app.get_visual_selection_configs();
+  if configs.is_empty() {
+    return;
+  }
+
+  let file_name = match format {
+    ExportFormat::Script => "easykpf-forwards.sh",
+    ExportFormat::Bundle => "easykpf-bundle.yaml",
+  };
+  match write_export(&configs, &app.variables, format, file_name) {
+    Ok(path) => app.set_status(format!(
+      "Exported {} configs to {}",
+      configs.len(),
+      path.display()
+    )),
+    Err(e) => app.set_status(format!("Export failed: {}", e)),
+  }
+}
+
+fn write_export(
+  configs: &[PortForwardConfig],
+  variables: &Variables,
+  format: ExportFormat,
+  file_name: &str,
+) -> Result<PathBuf> {
+  // Exports are snapshots with placeholders resolved
+  let configs = configs
+    .iter()
+    .map(|c| template::expand_config(c, variables).map_err(AppError::Validation))
+    .collect::<Result<Vec<_>>>()?;
+  let path = std::env::current_dir()?.join(file_name);
+  std::fs::write(&path, exporter::export_configs(&configs, format)?)?;
+
+  #[cfg(unix)]
+  if format == ExportFormat::Script {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+  }
+
+  Ok(path)
+}
@@ -1,13 +1,16 @@
 use crate::kubectl::KubectlService;
-use crate::state::{AutocompleteResult, AutocompleteState, EditField};
+use crate::state::{AutocompleteResult, AutocompleteState, EditField, StartUpdate};
 use crate::theme::{Theme, ThemeMode};
 use crate::vim::VimState;
 use easy_kpf_core::{
-  services::{ConfigService, ProcessManager},
-  ForwardType, PortForwardConfig, Result,
+  services::{
+    profiles, template, validation, ConfigService, ConfigWatcher, HostKey, PortConflict,
+    ProcessManager, ProfileStatus, Profiles, ValidationErrors, Variables,
+  },
+  AppError, ConfigSource, ForwardType, PortForwardConfig, Result,
 };
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc as std_mpsc;
 use tokio::sync::mpsc;
 use tui_textarea::TextArea;
@@ -16,6 +19,12 @@ use tui_textarea::TextArea;
 /// Each entry is (context_name, Vec<(visual_index, config_index, config_ref)>)
 pub type ConfigsByContext<'a> = Vec<(String, Vec<(usize, usize, &'a PortForwardConfig)>)>;
 
+/// (source rank, context) - project groups sort before global, then included ones
+type GroupKey = (u8, String);
+
+/// Config changes `u` can undo
+const UNDO_LIMIT: usize = 50;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
   Normal,
@@ -25,6 +34,7 @@ pub enum Mode {
   Edit,
   Create,
   Confirm,
+  Profiles,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,9 +53,14 @@ pub struct App {
   pub mode: Mode,
   pub active_panel: Panel,
   pub configs: Vec<PortForwardConfig>,
+  /// `variables:` from the config files, for `${VAR}` placeholders
+  pub variables: Variables,
+  /// `profiles:` from the config files
+  pub profiles: Profiles,
+  pub profile_index: usize, // Selected row of the profile picker
   pub running_services: HashMap<String, u32>, // name -> pid
-  pub selected_index: usize,                  // Index into visual_order
-  pub visual_anchor: Option<usize>,           // Start of visual selection (when in Visual mode)
+  pub selected_index: usize, // Index into visual_order
+  pub visual_anchor: Option<usize>, // Start of visual selection (when in Visual mode)
   pub search_query: String,
   pub visual_order: Vec<usize>, // Config indices in display order (grouped by context)
   pub logs: HashMap<String, Vec<LogEntry>>,
@@ -54,6 +69,7 @@ pub struct App {
   pub status_message: Option<String>,
   pub should_quit: bool,
   pub config_service: ConfigService,
+  pub config_watcher: Option<ConfigWatcher>, // None if the config files can't be watched
   pub process_manager: ProcessManager,
   pub log_receiver: Option<mpsc::Receiver<(String, LogEntry)>>,
   pub log_sender: mpsc::Sender<(String, LogEntry)>,
@@ -66,6 +82,11 @@ pub struct App {
   pub edit_field_value: String,
   pub edit_cursor_pos: usize, // Cursor position within edit_field_value
   pub name_manually_edited: bool,
+  pub edit_errors: ValidationErrors, // Shown next to fields after a rejected save
+  // Undo/redo: configs before each change, and as last loaded or saved
+  pub undo_stack: Vec<Vec<PortForwardConfig>>,
+  pub redo_stack: Vec<Vec<PortForwardConfig>>,
+  pub saved_configs: Vec<PortForwardConfig>,
   // Confirm mode state
   pub confirm_action: Option<ConfirmAction>,
   // Autocomplete state
@@ -73,6 +94,9 @@ pub struct App {
   pub kubectl_service: KubectlService,
   pub autocomplete_rx: std_mpsc::Receiver<AutocompleteResult>,
   pub autocomplete_tx: std_mpsc::Sender<AutocompleteResult>,
+  // Forwards starting in the background report here
+  pub start_rx: std_mpsc::Receiver<StartUpdate>,
+  pub start_tx: std_mpsc::Sender<StartUpdate>,
   // Vim command mode state (for :w, :q commands in edit form)
   pub command_mode: bool,
   pub command_buffer: String,
@@ -87,6 +111,8 @@ pub enum ConfirmAction {
   StartAll,
   StopAll,
   CancelEdit(Mode), // Stores the mode to return to if user says "No"
+  PortOwners(Box<PortForwardConfig>, Vec<PortConflict>),
+  PinHostKeys(Vec<HostKey>),
 }
 
 impl App {
@@ -97,6 +123,10 @@ impl App {
     let (log_sender, log_receiver) = mpsc::channel(1000);
     let kubectl_service = KubectlService::new(config_service.clone());
     let (autocomplete_tx, autocomplete_rx) = std_mpsc::channel();
+    let (start_tx, start_rx) = std_mpsc::channel();
+    let config_watcher = ConfigWatcher::start(config_service.clone())
+      .map_err(|e| log::warn!("{}", e))
+      .ok();
 
     // Detect system theme
     let theme_mode = ThemeMode::detect();
@@ -106,6 +136,9 @@ impl App {
       mode: Mode::Normal,
       active_panel: Panel::ServiceList,
       configs: Vec::new(),
+      variables: Variables::new(),
+      profiles: Profiles::new(),
+      profile_index: 0,
       running_services: HashMap::new(),
       selected_index: 0,
       visual_anchor: None,
@@ -116,12 +149,17 @@ impl App {
       status_message: None,
       should_quit: false,
       config_service,
+      config_watcher,
       process_manager,
       log_receiver: Some(log_receiver),
       log_sender,
       theme,
       edit_config: None,
       edit_original_config: None,
+      edit_errors: ValidationErrors::default(),
+      undo_stack: Vec::new(),
+      redo_stack: Vec::new(),
+      saved_configs: Vec::new(),
       edit_field_index: 0,
       edit_field_value: String::new(),
       edit_cursor_pos: 0,
@@ -131,6 +169,8 @@ impl App {
       kubectl_service,
       autocomplete_rx,
       autocomplete_tx,
+      start_rx,
+      start_tx,
       command_mode: false,
       command_buffer: String::new(),
       vim_textarea: None,
@@ -138,6 +178,7 @@ impl App {
     };
 
     app.load_configs()?;
+    app.saved_configs = app.configs.clone();
     app.sync_running_services();
     app.update_visual_order();
 
@@ -145,13 +186,109 @@ impl App {
   }
 
   pub fn load_configs(&mut self) -> Result<()> {
-    self.configs = self.config_service.load_port_forwards()?;
+    let loaded = self.config_service.load_port_forwards_with_recovery()?;
+    self.configs = loaded.configs;
+    self.variables = loaded.variables;
+    self.profiles = loaded.profiles;
+    if let Some(path) = loaded.recovered_from {
+      self.set_status(format!(
+        "Config file was invalid and moved to {}",
+        path.display()
+      ));
+    } else if !loaded.conflicts.is_empty() {
+      self.set_status(format!(
+        "Project config overrides global: {}",
+        loaded.conflicts.join(", ")
+      ));
+    } else if let Some(warning) = loaded.warnings.first() {
+      self.set_status(warning.clone());
+    }
     self.update_visual_order();
     Ok(())
   }
 
-  pub fn save_configs(&self) -> Result<()> {
-    self.config_service.save_port_forwards(&self.configs)
+  /// Reload after the config files changed on disk. Only edits made elsewhere
+  /// are announced; the TUI's own saves reload to the same configs.
+  pub fn reload_changed_configs(&mut self) -> Result<()> {
+    let previous = std::mem::take(&mut self.configs);
+    let status = self.status_message.clone();
+    self.load_configs()?;
+    if self.configs != previous {
+      // Undoing past someone else's edit would silently revert it
+      self.saved_configs = self.configs.clone();
+      self.undo_stack.clear();
+      self.redo_stack.clear();
+      if self.status_message == status {
+        self.set_status("Config reloaded from disk");
+      }
+    }
+    Ok(())
+  }
+
+  pub fn save_configs(&mut self) -> Result<()> {
+    self.config_service.save_port_forwards(&self.configs)?;
+    self.record_change();
+    Ok(())
+  }
+
+  /// Make the change from `saved_configs` to `configs` undoable
+  pub fn record_change(&mut self) {
+    if self.configs == self.saved_configs {
+      return;
+    }
+    let previous = std::mem::replace(&mut self.saved_configs, self.configs.clone());
+    self.undo_stack.push(previous);
+    if self.undo_stack.len() > UNDO_LIMIT {
+      self.undo_stack.remove(0);
+    }
+    self.redo_stack.clear();
+  }
+
+  pub fn undo(&mut self) {
+    self.step_history(true);
+  }
+
+  pub fn redo(&mut self) {
+    self.step_history(false);
+  }
+
+  /// Swap in the configs from the undo (or redo) stack and save them
+  fn step_history(&mut self, undo: bool) {
+    let (verb, stack) = if undo {
+      ("undo", &mut self.undo_stack)
+    } else {
+      ("redo", &mut self.redo_stack)
+    };
+    let Some(configs) = stack.pop() else {
+      self.set_status(format!("Nothing to {}", verb));
+      return;
+    };
+
+    let current = std::mem::replace(&mut self.configs, configs);
+    if let Err(e) = self.config_service.save_port_forwards(&self.configs) {
+      let configs = std::mem::replace(&mut self.configs, current);
+      if undo {
+        self.undo_stack.push(configs);
+      } else {
+        self.redo_stack.push(configs);
+      }
+      self.set_status(format!("Failed to {}: {}", verb, e));
+      return;
+    }
+
+    if undo {
+      self.redo_stack.push(current);
+    } else {
+      self.undo_stack.push(current);
+    }
+    self.saved_configs = self.configs.clone();
+    self.update_visual_order();
+    let (done, left) = if undo {
+      ("Undone", self.undo_stack.len())
+    } else {
+      ("Redone", self.redo_stack.len())
+    };
+    self.set_status(format!("{} ({} more to {})", done, left, verb));
   }
 
   pub fn sync_running_services(&mut self) {
@@ -175,34 +312,20 @@ impl App {
         .configs
         .iter()
         .enumerate()
-        .filter(|(_, c)| {
-          c.name.to_lowercase().contains(&query)
-            || c.service.to_lowercase().contains(&query)
-            || c.namespace.to_lowercase().contains(&query)
-            || c.context.to_lowercase().contains(&query)
-        })
+        .filter(|(_, c)| query.split_whitespace().all(|term| matches_search(c, term)))
         .map(|(i, _)| i)
         .collect()
     };
 
-    // Group by context and flatten to get visual order
-    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    // Group by context (project groups first) and flatten to get visual order
+    let mut groups: BTreeMap<GroupKey, Vec<usize>> = BTreeMap::new();
     for idx in filtered {
       if let Some(config) = self.configs.get(idx) {
-        groups.entry(config.context.clone()).or_default().push(idx);
+        groups.entry(group_key(config)).or_default().push(idx);
       }
     }
 
-    // Sort groups by context name and flatten
-    let mut sorted_contexts: Vec<_> = groups.keys().cloned().collect();
-    sorted_contexts.sort();
-
-    self.visual_order.clear();
-    for context in sorted_contexts {
-      if let Some(indices) = groups.get(&context) {
-        self.visual_order.extend(indices);
-      }
-    }
+    self.visual_order = groups.into_values().flatten().collect();
 
     // Ensure selected index is valid
     if self.selected_index >= self.visual_order.len() && !self.visual_order.is_empty() {
@@ -258,6 +381,27 @@ impl App {
     }
   }
 
+  /// Extend the visual selection to the whole group of the selected config
+  pub fn select_group(&mut self) {
+    let Some(key) = self.selected_config().map(group_key) else {
+      return;
+    };
+    let in_group = |&config_idx: &usize| {
+      self
+        .configs
+        .get(config_idx)
+        .is_some_and(|c| group_key(c) == key)
+    };
+
+    // Groups are contiguous in visual order
+    let start = self.visual_order.iter().position(in_group);
+    let end = self.visual_order.iter().rposition(in_group);
+    if let (Some(start), Some(end)) = (start, end) {
+      self.visual_anchor = Some(start);
+      self.selected_index = end;
+    }
+  }
+
   pub fn enter_visual_mode(&mut self) {
     self.visual_anchor = Some(self.selected_index);
     self.mode = Mode::Visual;
@@ -304,6 +448,17 @@ impl App {
       .collect()
   }
 
+  /// Every profile with which of its forwards are running, for the picker
+  pub fn profile_statuses(&self) -> Vec<ProfileStatus> {
+    let running: Vec<String> = self.running_services.keys().cloned().collect();
+    profiles::profile_statuses(&self.profiles, &self.configs, &running)
+  }
+
+  /// Name of the profile under the picker cursor
+  pub fn selected_profile(&self) -> Option<String> {
+    self.profiles.keys().nth(self.profile_index).cloned()
+  }
+
   pub fn set_status(&mut self, msg: impl Into<String>) {
     self.status_message = Some(msg.into());
   }
@@ -327,21 +482,29 @@ impl App {
 
   // Group configs by context for display, preserving visual order
   pub fn configs_by_context(&self) -> ConfigsByContext<'_> {
-    // Returns: Vec<(context, Vec<(visual_index, config_index, config)>)>
-    let mut groups: HashMap<String, Vec<(usize, usize, &PortForwardConfig)>> = HashMap::new();
+    // Returns: Vec<(group label, Vec<(visual_index, config_index, config)>)>
+    let mut groups: BTreeMap<GroupKey, Vec<(usize, usize, &PortForwardConfig)>> = BTreeMap::new();
 
     for (visual_idx, &config_idx) in self.visual_order.iter().enumerate() {
       if let Some(config) = self.configs.get(config_idx) {
         groups
-          .entry(config.context.clone())
+          .entry(group_key(config))
           .or_default()
           .push((visual_idx, config_idx, config));
       }
     }
 
-    let mut result: Vec<_> = groups.into_iter().collect();
-    result.sort_by(|a, b| a.0.cmp(&b.0));
-    result
+    groups
+      .into_iter()
+      .map(|((rank, context), configs)| {
+        let label = match rank {
+          0 => format!("{} · project", context),
+          2 => format!("{} · shared", context),
+          _ => context,
+        };
+        (label, configs)
+      })
+      .collect()
   }
 
   pub fn enter_edit_mode(&mut self) {
@@ -352,6 +515,7 @@ impl App {
       self.edit_field_value = self.get_edit_field_value(0);
       self.edit_cursor_pos = self.edit_field_value.len();
       self.name_manually_edited = true; // Existing config has user-defined name
+      self.edit_errors = ValidationErrors::default();
       self.autocomplete = AutocompleteState::default();
       self.mode = Mode::Edit;
       // Field 0 (Name) doesn't support autocomplete, so no auto-load
@@ -373,6 +537,7 @@ impl App {
       ports: vec![],
       local_interface: None,
       forward_type: ForwardType::Kubectl,
+      ..Default::default()
     };
     self.edit_original_config = Some(new_config.clone()); // Save original for change detection
     self.edit_config = Some(new_config);
@@ -380,6 +545,7 @@ impl App {
     self.edit_field_value = String::new();
     self.edit_cursor_pos = 0;
     self.name_manually_edited = false; // New config starts with auto-generated name
+    self.edit_errors = ValidationErrors::default();
     self.autocomplete = AutocompleteState::default();
     self.mode = Mode::Create;
     // Field 0 (Name) doesn't support autocomplete, so no auto-load
@@ -388,7 +554,7 @@ impl App {
   // Derive a config name from service and port (like the GUI does)
   pub fn derive_config_name(config: &PortForwardConfig) -> String {
     match config.forward_type {
-      ForwardType::Ssh => {
+      ForwardType::Ssh | ForwardType::Tcp => {
         let host = config
           .service
           .split('@')
@@ -401,7 +567,23 @@ impl App {
           .unwrap_or("unknown");
         format!("{}-{}", host, port)
       }
-      ForwardType::Kubectl => {
+      ForwardType::Command => {
+        let program = Path::new(&config.command.program)
+          .file_name()
+          .and_then(|name| name.to_str())
+          .unwrap_or_default();
+        let port = config
+          .ports
+          .first()
+          .and_then(|p| p.split(':').next())
+          .unwrap_or("unknown");
+        if program.is_empty() {
+          "new-forward".to_string()
+        } else {
+          format!("{}-{}", program, port)
+        }
+      }
+      ForwardType::Kubectl | ForwardType::Native => {
         let port = config
           .ports
           .first()
@@ -460,6 +642,50 @@ impl App {
     if field.triggers_name_generation() {
       self.update_auto_generated_name();
     }
+
+    // Once errors are shown, keep them in sync so fixed fields clear
+    if !self.edit_errors.is_empty() {
+      self.edit_errors = self.validate_edit();
+    }
+  }
+
+  /// Validate the config being edited against the other configs
+  pub fn validate_edit(&self) -> ValidationErrors {
+    let Some(ref config) = self.edit_config else {
+      return ValidationErrors::default();
+    };
+    let replacing = match self.mode {
+      Mode::Create => None,
+      _ => self.edit_original_config.as_ref().map(|c| c.name.as_str()),
+    };
+    validation::validate_against(config, &self.configs, replacing, &self.variables)
+  }
+
+  /// Resolved value of a field containing `${VAR}` placeholders, or why it
+  /// cannot be resolved
+  pub fn edit_field_preview(&self, index: usize) -> Option<std::result::Result<String, String>> {
+    if !EditField::from_index(index)?.is_templated() {
+      return None;
+    }
+    let value = if index == self.edit_field_index {
+      self.edit_field_value.clone()
+    } else {
+      self.get_edit_field_value(index)
+    };
+    value
+      .contains("${")
+      .then(|| template::expand(&value, &self.variables))
+  }
+
+  /// `config` with placeholders resolved, as the command builders need it
+  pub fn expand_config(&self, config: &PortForwardConfig) -> Result<PortForwardConfig> {
+    template::expand_config(config, &self.variables).map_err(AppError::Validation)
+  }
+
+  /// Validation message for a form field, if any
+  pub fn edit_field_error(&self, index: usize) -> Option<&str> {
+    let field = EditField::from_index(index)?.config_field()?;
+    self.edit_errors.for_field(field)
   }
 
   pub fn edit_field_count(&self) -> usize {
@@ -481,33 +707,38 @@ impl App {
   }
 
   pub fn next_edit_field(&mut self) {
-    self.set_edit_field_value(self.edit_field_value.clone());
-    self.edit_field_index = (self.edit_field_index + 1) % self.edit_field_count();
-    self.edit_field_value = self.get_edit_field_value(self.edit_field_index);
-    self.edit_cursor_pos = self.edit_field_value.len();
-    self.autocomplete.focused = false;
-    self.autocomplete.typing = false;
-    // Auto-load suggestions for supported fields
-    if let Some(field) = self.current_edit_field() {
-      if field.supports_autocomplete() {
-        self.load_autocomplete();
-        // For static fields (type), sync selection immediately
-        if field == EditField::ForwardType {
-          self.sync_autocomplete_selection();
-        }
-      } else {
-        self.clear_autocomplete();
-      }
-    }
+    self.step_edit_field(true);
   }
 
   pub fn prev_edit_field(&mut self) {
+    self.step_edit_field(false);
+  }
+
+  /// Whether the field at `index` is shown for the config being edited
+  pub fn edit_field_visible(&self, index: usize) -> bool {
+    match (EditField::from_index(index), &self.edit_config) {
+      (Some(field), Some(config)) => field.is_visible(config),
+      (Some(_), None) => true,
+      (None, _) => false,
+    }
+  }
+
+  /// Move to the next or previous visible field
+  fn step_edit_field(&mut self, forward: bool) {
     self.set_edit_field_value(self.edit_field_value.clone());
-    self.edit_field_index = if self.edit_field_index == 0 {
-      self.edit_field_count() - 1
-    } else {
-      self.edit_field_index - 1
-    };
+    let count = self.edit_field_count();
+    let mut index = self.edit_field_index;
+    for _ in 0..count {
+      index = if forward {
+        (index + 1) % count
+      } else {
+        (index + count - 1) % count
+      };
+      if self.edit_field_visible(index) {
+        break;
+      }
+    }
+    self.edit_field_index = index;
     self.edit_field_value = self.get_edit_field_value(self.edit_field_index);
     self.edit_cursor_pos = self.edit_field_value.len();
     self.autocomplete.focused = false;
@@ -517,7 +748,14 @@ impl App {
       if field.supports_autocomplete() {
         self.load_autocomplete();
         // For static fields (type), sync selection immediately
-        if field == EditField::ForwardType {
+        if matches!(
+          field,
+          EditField::ForwardType
+            | EditField::AutoPort
+            | EditField::Proxy
+            | EditField::SshDirection
+            | EditField::HostKeyPolicy
+        ) {
           self.sync_autocomplete_selection();
         }
       } else {
@@ -620,9 +858,30 @@ impl App {
     }
   }
 
+  /// File an entry being edited was included from; such entries are read-only
+  pub fn edit_included_from(&self) -> Option<&PathBuf> {
+    match self.edit_config.as_ref()?.source {
+      ConfigSource::Include(ref path) => Some(path),
+      _ => None,
+    }
+  }
+
+  /// Save the edited config. Invalid configs are kept in the form with the
+  /// errors shown next to their fields.
   pub fn save_edit(&mut self) -> Result<()> {
+    if self.edit_included_from().is_some() {
+      self.cancel_edit();
+      return Ok(());
+    }
+
     self.set_edit_field_value(self.edit_field_value.clone());
 
+    self.edit_errors = self.validate_edit();
+    if !self.edit_errors.is_empty() {
+      self.set_status(format!("Not saved: {}", self.edit_errors));
+      return Ok(());
+    }
+
     if let Some(config) = self.edit_config.take() {
       if self.mode == Mode::Create {
         self.configs.push(config);
@@ -634,11 +893,13 @@ impl App {
     }
 
     self.mode = Mode::Normal;
+    self.set_status("Configuration saved");
     Ok(())
   }
 
   pub fn cancel_edit(&mut self) {
     self.edit_config = None;
+    self.edit_errors = ValidationErrors::default();
     self.edit_original_config = None;
     self.autocomplete = AutocompleteState::default();
     self.mode = Mode::Normal;
@@ -659,6 +920,11 @@ impl App {
       || current.ports != original.ports
       || current.local_interface != original.local_interface
       || current.forward_type != original.forward_type
+      || current.auto_port != original.auto_port
+      || current.proxy != original.proxy
+      || current.tags != original.tags
+      || current.ssh != original.ssh
+      || current.command != original.command
   }
 
   pub fn get_config_file_path(&self) -> PathBuf {
@@ -704,7 +970,9 @@ impl App {
         }
         3 => {
           if !config.context.is_empty() && !config.namespace.is_empty() {
-            AutocompleteResult::Services(kubectl.get_services(&config.context, &config.namespace))
+            AutocompleteResult::Services(
+              kubectl.get_forward_targets(&config.context, &config.namespace),
+            )
           } else {
             AutocompleteResult::Services(vec![])
           }
@@ -714,7 +982,7 @@ impl App {
             && !config.namespace.is_empty()
             && !config.service.is_empty()
           {
-            AutocompleteResult::Ports(kubectl.get_service_ports(
+            AutocompleteResult::Ports(kubectl.get_target_ports(
               &config.context,
               &config.namespace,
               &config.service,
@@ -752,6 +1020,10 @@ impl App {
       3 => &self.autocomplete.services,
       4 => &self.autocomplete.ports,
       6 => &self.autocomplete.types,
+      7 => &self.autocomplete.toggles,
+      9 => &self.autocomplete.directions,
+      15 => &self.autocomplete.host_key_policies,
+      16 | 21 => &self.autocomplete.toggles,
       _ => &[],
     }
   }
@@ -804,6 +1076,29 @@ impl App {
   }
 }
 
+/// One lowercased search term; `tag:db` only looks at tags (prefix match while typing)
+fn matches_search(config: &PortForwardConfig, term: &str) -> bool {
+  let mut tags = config.tags.iter().map(|t| t.to_lowercase());
+  if let Some(tag) = term.strip_prefix("tag:") {
+    return tags.any(|t| t.starts_with(tag));
+  }
+  config.name.to_lowercase().contains(term)
+    || config.service.to_lowercase().contains(term)
+    || config.namespace.to_lowercase().contains(term)
+    || config.context.to_lowercase().contains(term)
+    || tags.any(|t| t.contains(term))
+}
+
+/// Display group of a config. Project entries sort before global ones.
+fn group_key(config: &PortForwardConfig) -> GroupKey {
+  let rank = match config.source {
+    ConfigSource::Project(_) => 0,
+    ConfigSource::Global => 1,
+    ConfigSource::Include(_) => 2,
+  };
+  (rank, config.context.clone())
+}
+
 fn is_process_running(pid: u32) -> bool {
   use std::process::Command;
   Command::new("kill")
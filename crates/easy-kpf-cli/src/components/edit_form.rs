@@ -14,9 +14,14 @@ pub fn draw_edit_form(frame: &mut Frame, app: &App) {
   frame.render_widget(Clear, area);
 
   let title = if app.mode == Mode::Create {
-    " New Port Forward "
+    " New Port Forward ".to_string()
+  } else if let Some(path) = app.edit_included_from() {
+    format!(
+      " Port Forward (read-only, included from {}) ",
+      path.display()
+    )
   } else {
-    " Edit Port Forward "
+    " Edit Port Forward ".to_string()
   };
 
   let block = Block::default()
@@ -39,8 +44,12 @@ pub fn draw_edit_form(frame: &mut Frame, app: &App) {
 }
 
 fn draw_fields_panel(frame: &mut Frame, app: &App, area: Rect) {
-  let field_count = app.edit_field_count();
-  let field_constraints: Vec<Constraint> = (0..field_count)
+  let fields: Vec<usize> = (0..app.edit_field_count())
+    .filter(|&i| app.edit_field_visible(i))
+    .collect();
+  let field_count = fields.len();
+  let field_constraints: Vec<Constraint> = fields
+    .iter()
     .map(|_| Constraint::Length(3))
     .chain(std::iter::once(Constraint::Min(1))) // Instructions
     .collect();
@@ -51,8 +60,8 @@ fn draw_fields_panel(frame: &mut Frame, app: &App, area: Rect) {
     .constraints(field_constraints)
     .split(area);
 
-  for i in 0..field_count {
-    draw_field(frame, app, i, chunks[i]);
+  for (&field_index, &chunk) in fields.iter().zip(chunks.iter()) {
+    draw_field(frame, app, field_index, chunk);
   }
 
   // Instructions at the bottom
@@ -77,7 +86,18 @@ fn draw_field(frame: &mut Frame, app: &App, field_index: usize, area: Rect) {
   let is_vim_mode = app.is_vim_edit_mode() && is_selected;
   let is_editing = is_typing || is_vim_mode;
 
-  let (style, border_style) = get_field_styles(theme, is_selected, is_editing);
+  let (style, mut border_style) = get_field_styles(theme, is_selected, is_editing);
+  let error = app.edit_field_error(field_index);
+  if error.is_some() {
+    border_style = theme.error();
+  }
+  // Errors take the bottom border; otherwise it previews resolved placeholders
+  let error_line = match (error, app.edit_field_preview(field_index)) {
+    (Some(e), _) => Line::styled(format!(" {} ", e), theme.error()),
+    (None, Some(Ok(resolved))) => Line::styled(format!(" = {} ", resolved), theme.text_tertiary()),
+    (None, Some(Err(e))) => Line::styled(format!(" {} ", e), theme.warning()),
+    (None, None) => Line::default(),
+  };
 
   // Build title with optional description
   let title = if description.is_empty() {
@@ -93,6 +113,7 @@ fn draw_field(frame: &mut Frame, app: &App, field_index: usize, area: Rect) {
       ta.set_block(
         Block::default()
           .title(title)
+          .title_bottom(error_line)
           .borders(Borders::ALL)
           .border_type(BorderType::Rounded)
           .border_style(border_style),
@@ -105,6 +126,7 @@ fn draw_field(frame: &mut Frame, app: &App, field_index: usize, area: Rect) {
     let field = Paragraph::new(line).block(
       Block::default()
         .title(title)
+        .title_bottom(error_line)
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(border_style),
@@ -85,7 +85,15 @@ fn build_actions_section(theme: &Theme) -> Vec<Line<'static>> {
     help_line(theme, "  n           ", "Create new config"),
     help_line(theme, "  e           ", "Edit selected config"),
     help_line(theme, "  d / Delete  ", "Delete selected config"),
+    help_line(theme, "  u / Ctrl-r  ", "Undo/redo config changes"),
     help_line(theme, "  r           ", "Refresh/sync processes"),
+    help_line(
+      theme,
+      "  o           ",
+      "Show who holds the ports (adopt/kill)",
+    ),
+    help_line(theme, "  K           ", "Verify and pin an SSH host key"),
+    help_line(theme, "  p           ", "Start/stop a profile (named set)"),
     help_line(theme, "  v           ", "Enter visual mode (multi-select)"),
     Line::from(""),
   ]
@@ -97,9 +105,11 @@ fn build_visual_mode_section(theme: &Theme) -> Vec<Line<'static>> {
     Line::from(""),
     help_line(theme, "  j/k         ", "Extend selection up/down"),
     help_line(theme, "  < / >       ", "Extend to start/end"),
+    help_line(theme, "  g           ", "Select the whole context group"),
     help_line(theme, "  Space/Enter ", "Toggle selected services"),
     help_line(theme, "  s           ", "Start all selected"),
     help_line(theme, "  x           ", "Stop all selected"),
+    help_line(theme, "  e / E       ", "Export as shell script / bundle"),
     help_line(theme, "  Esc / v     ", "Exit visual mode"),
     Line::from(""),
   ]
@@ -126,7 +136,11 @@ fn build_other_section(theme: &Theme) -> Vec<Line<'static>> {
   vec![
     section_header(theme, "Other", false),
     Line::from(""),
-    help_line(theme, "  /           ", "Search/filter services"),
+    help_line(
+      theme,
+      "  /           ",
+      "Search/filter services (tag:db for tags)",
+    ),
     help_line(theme, "  E           ", "Open config in $EDITOR"),
     help_line(theme, "  ?           ", "Toggle this help"),
     help_line(theme, "  q / Ctrl+c  ", "Quit"),
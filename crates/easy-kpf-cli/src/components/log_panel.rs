@@ -1,4 +1,5 @@
 use crate::app::{App, Panel};
+use easy_kpf_core::services::traffic;
 use ratatui::{
   layout::Rect,
   text::{Line, Span},
@@ -16,10 +17,20 @@ pub fn draw_log_panel(frame: &mut Frame, app: &App, area: Rect) {
     theme.border()
   };
 
-  let title = app
-    .selected_name()
-    .map(|k| format!(" Logs: {} ", k))
-    .unwrap_or_else(|| " Logs ".to_string());
+  let title = match app.selected_name() {
+    Some(name) => match app.process_manager.traffic(&name).ok().flatten() {
+      Some(stats) => {
+        let idle = match stats.idle() {
+          Some(idle) if idle.as_secs() < 2 => ", active now".to_string(),
+          Some(idle) => format!(", idle {}", traffic::format_duration(idle)),
+          None => String::new(),
+        };
+        format!(" Logs: {} ({}{}) ", name, stats, idle)
+      }
+      None => format!(" Logs: {} ", name),
+    },
+    None => " Logs ".to_string(),
+  };
 
   let logs = app.get_logs_for_selected();
 
@@ -1,6 +1,7 @@
 mod edit_form;
 mod help_popup;
 mod log_panel;
+mod profile_picker;
 mod search_bar;
 mod service_list;
 mod status_bar;
@@ -8,6 +9,7 @@ mod status_bar;
 pub use edit_form::draw_edit_form;
 pub use help_popup::draw_help_popup;
 pub use log_panel::draw_log_panel;
+pub use profile_picker::draw_profile_picker;
 pub use search_bar::draw_search_bar;
 pub use service_list::draw_service_list;
 pub use status_bar::draw_status_bar;
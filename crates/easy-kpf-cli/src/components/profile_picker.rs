@@ -0,0 +1,82 @@
+use crate::app::App;
+use ratatui::{
+  layout::{Constraint, Direction, Layout, Rect},
+  style::{Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, BorderType, Borders, Clear, List, ListItem},
+  Frame,
+};
+
+pub fn draw_profile_picker(frame: &mut Frame, app: &App) {
+  let theme = &app.theme;
+  let area = centered_rect(60, 50, frame.area());
+  frame.render_widget(Clear, area);
+
+  let items: Vec<ListItem> = app
+    .profile_statuses()
+    .iter()
+    .enumerate()
+    .map(|(i, profile)| {
+      let total = profile.running.len() + profile.stopped.len();
+      let status_style = if profile.is_running() {
+        theme.success()
+      } else {
+        theme.text_tertiary()
+      };
+      let members: Vec<&str> = profile
+        .running
+        .iter()
+        .chain(&profile.stopped)
+        .map(String::as_str)
+        .collect();
+      let style = if i == app.profile_index {
+        theme.cursor()
+      } else {
+        Style::default()
+      };
+
+      ListItem::new(Line::from(vec![
+        Span::styled(
+          format!(" {:20}", profile.name),
+          theme.text().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+          format!("{}/{} running  ", profile.running.len(), total),
+          status_style,
+        ),
+        Span::styled(members.join(", "), theme.text_secondary()),
+      ]))
+      .style(style)
+    })
+    .collect();
+
+  let list = List::new(items).block(
+    Block::default()
+      .title(" Profiles ")
+      .borders(Borders::ALL)
+      .border_type(BorderType::Rounded)
+      .border_style(theme.border_focused()),
+  );
+
+  frame.render_widget(list, area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+  let popup_layout = Layout::default()
+    .direction(Direction::Vertical)
+    .constraints([
+      Constraint::Percentage((100 - percent_y) / 2),
+      Constraint::Percentage(percent_y),
+      Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(area);
+
+  Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([
+      Constraint::Percentage((100 - percent_x) / 2),
+      Constraint::Percentage(percent_x),
+      Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
@@ -1,4 +1,6 @@
 use crate::app::{App, Mode, Panel};
+use easy_kpf_core::services::traffic;
+use easy_kpf_core::PortForwardConfig;
 use ratatui::{
   layout::Rect,
   style::{Modifier, Style},
@@ -58,8 +60,7 @@ pub fn draw_service_list(frame: &mut Frame, app: &App, area: Rect) {
         Style::default()
       };
 
-      // Format ports as a single string
-      let ports_str = config.ports.join(", ");
+      let ports_str = displayed_ports(app, config, is_running);
 
       // Show selection indicator for cursor or visual selection
       let indicator = if is_cursor {
@@ -70,7 +71,7 @@ pub fn draw_service_list(frame: &mut Frame, app: &App, area: Rect) {
         "   "
       };
 
-      let line = Line::from(vec![
+      let mut spans = vec![
         Span::raw(indicator),
         Span::styled(
           checkbox,
@@ -86,9 +87,18 @@ pub fn draw_service_list(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(ports_str, theme.accent()),
         Span::raw("  "),
         Span::styled(status, status_style),
-      ]);
+      ];
+      if let Some(summary) = traffic_summary(app, config) {
+        spans.push(Span::styled(summary, theme.text_secondary()));
+      }
+      if !config.tags.is_empty() {
+        spans.push(Span::styled(
+          format!("  #{}", config.tags.join(" #")),
+          theme.text_tertiary(),
+        ));
+      }
 
-      items.push(ListItem::new(line).style(style));
+      items.push(ListItem::new(Line::from(spans)).style(style));
     }
   }
 
@@ -120,3 +130,33 @@ pub fn draw_service_list(frame: &mut Frame, app: &App, area: Rect) {
 
   frame.render_widget(list, area);
 }
+
+/// Open connections and bytes moved, for forwards whose connections pass
+/// through EasyKpf
+fn traffic_summary(app: &App, config: &PortForwardConfig) -> Option<String> {
+  let stats = app.process_manager.traffic(&config.name).ok().flatten()?;
+  Some(format!(
+    "  {} open, {}",
+    stats.active_connections,
+    traffic::format_bytes(stats.bytes_in + stats.bytes_out)
+  ))
+}
+
+/// Running forwards show the ports they actually bound. SSH forwards lead with
+/// their direction, e.g. `-R 3000:8080`.
+fn displayed_ports(app: &App, config: &PortForwardConfig, is_running: bool) -> String {
+  let ports = app
+    .process_manager
+    .get_process_config(&config.name)
+    .ok()
+    .flatten()
+    .filter(|_| is_running)
+    .map_or_else(
+      || config.ports.join(", "),
+      |started| started.ports.join(", "),
+    );
+  match config.ssh_direction() {
+    Some(direction) => format!("{} {}", direction.flag(), ports),
+    None => ports,
+  }
+}
@@ -21,6 +21,7 @@ pub fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
           ("n", "new"),
           ("e", "edit"),
           ("d", "del"),
+          ("u", "undo"),
           ("/", "search"),
           ("?", "help"),
           ("q", "quit"),
@@ -43,6 +44,12 @@ pub fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     ],
     Mode::Help => vec![("Esc/q/?", "close")],
     Mode::Confirm => vec![("y", "yes"), ("n", "no"), ("Esc", "cancel")],
+    Mode::Profiles => vec![
+      ("j/k", "navigate"),
+      ("Enter/s", "start"),
+      ("x", "stop"),
+      ("Esc", "close"),
+    ],
     Mode::Visual => vec![
       ("j/k", "extend"),
       ("Space", "toggle"),
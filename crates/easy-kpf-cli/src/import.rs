@@ -0,0 +1,76 @@
+use crate::kubectl::KubectlService;
+use anyhow::{bail, Context};
+use easy_kpf_core::services::{importer, ConfigService};
+use easy_kpf_core::CommandImporter;
+use std::io::Read;
+
+const USAGE: &str = "Usage: easykpf import <FILE | - | --history> [--context CONTEXT] [--dry-run]";
+
+/// `easykpf import`: add port forwards found in scripts, stdin or shell history
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+  let mut source = None;
+  let mut context = None;
+  let mut dry_run = false;
+
+  let mut iter = args.iter();
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "--context" => context = Some(iter.next().context(USAGE)?.clone()),
+      "--dry-run" => dry_run = true,
+      "-h" | "--help" => {
+        println!("{}", USAGE);
+        return Ok(());
+      }
+      _ if source.is_none() => source = Some(arg.clone()),
+      _ => bail!(USAGE),
+    }
+  }
+
+  let text = match source.as_deref() {
+    Some("--history") => importer::read_shell_history()?,
+    Some("-") => {
+      let mut text = String::new();
+      std::io::stdin().read_to_string(&mut text)?;
+      text
+    }
+    Some(path) => {
+      std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?
+    }
+    None => bail!(USAGE),
+  };
+
+  let config_service = ConfigService::new()?;
+  let context = context
+    .or_else(|| KubectlService::new(config_service.clone()).get_current_context())
+    .unwrap_or_default();
+
+  let mut configs = config_service.load_port_forwards()?;
+  let report = CommandImporter::new(context).import(&text, &configs);
+
+  for config in &report.configs {
+    println!(
+      "+ {} ({}/{} {} {})",
+      config.name,
+      config.context,
+      config.namespace,
+      config.service,
+      config.ports.join(",")
+    );
+  }
+  for skipped in &report.skipped {
+    eprintln!("skipped: {}", skipped);
+  }
+  println!(
+    "{} new, {} already configured, {} skipped",
+    report.configs.len(),
+    report.duplicates,
+    report.skipped.len()
+  );
+
+  if !dry_run && !report.configs.is_empty() {
+    configs.extend(report.configs);
+    config_service.save_port_forwards(&configs)?;
+  }
+
+  Ok(())
+}
@@ -1,4 +1,4 @@
-use easy_kpf_core::services::ConfigService;
+use easy_kpf_core::{services::ConfigService, ForwardTarget};
 use std::process::Command;
 
 #[derive(Clone)]
@@ -62,6 +62,20 @@ impl KubectlService {
     }
   }
 
+  pub fn get_current_context(&self) -> Option<String> {
+    let output = self
+      .create_command()
+      .args(["config", "current-context"])
+      .output()
+      .ok()?;
+
+    if !output.status.success() {
+      return None;
+    }
+    let context = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!context.is_empty()).then_some(context)
+  }
+
   pub fn get_namespaces(&self, context: &str) -> Vec<String> {
     let output = self
       .create_command()
@@ -84,7 +98,7 @@ impl KubectlService {
     }
   }
 
-  pub fn get_services(&self, context: &str, namespace: &str) -> Vec<String> {
+  fn get_resource_names(&self, context: &str, namespace: &str, resource: &str) -> Vec<String> {
     let output = self
       .create_command()
       .args([
@@ -93,7 +107,7 @@ impl KubectlService {
         "-n",
         namespace,
         "get",
-        "services",
+        resource,
         "-o",
         "jsonpath={.items[*].metadata.name}",
       ])
@@ -102,14 +116,43 @@ impl KubectlService {
     match output {
       Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
         .split_whitespace()
-        .map(|s| format!("svc/{}", s))
+        .map(std::string::ToString::to_string)
         .collect(),
       _ => vec![],
     }
   }
 
-  pub fn get_service_ports(&self, context: &str, namespace: &str, service: &str) -> Vec<String> {
-    let service_name = service.strip_prefix("svc/").unwrap_or(service);
+  pub fn get_services(&self, context: &str, namespace: &str) -> Vec<String> {
+    self
+      .get_resource_names(context, namespace, "services")
+      .into_iter()
+      .map(|s| format!("svc/{}", s))
+      .collect()
+  }
+
+  /// Services, deployments, statefulsets and pods that can be forwarded to
+  pub fn get_forward_targets(&self, context: &str, namespace: &str) -> Vec<String> {
+    let mut targets = self.get_services(context, namespace);
+    for (resource, prefix) in [
+      ("deployments", "deploy"),
+      ("statefulsets", "sts"),
+      ("pods", "pod"),
+    ] {
+      targets.extend(
+        self
+          .get_resource_names(context, namespace, resource)
+          .into_iter()
+          .map(|name| format!("{}/{}", prefix, name)),
+      );
+    }
+    targets
+  }
+
+  pub fn get_target_ports(&self, context: &str, namespace: &str, target: &str) -> Vec<String> {
+    let target = ForwardTarget::parse(target);
+    let (Some(resource), Some(jsonpath)) = (target.resource_type(), target.ports_jsonpath()) else {
+      return vec![];
+    };
 
     let output = self
       .create_command()
@@ -119,10 +162,10 @@ impl KubectlService {
         "-n",
         namespace,
         "get",
-        "service",
-        service_name,
+        resource,
+        target.name(),
         "-o",
-        "jsonpath={.spec.ports[*].port}",
+        jsonpath,
       ])
       .output();
 
@@ -2,6 +2,7 @@ mod actions;
 mod app;
 mod components;
 mod executor;
+mod import;
 mod kubectl;
 mod state;
 mod theme;
@@ -9,7 +10,9 @@ mod tui;
 mod vim;
 
 use app::App;
+use easy_kpf_core::services::{ConfigWatcher, ConfigsChanged};
 use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tui::{restore_terminal, setup_terminal, Event, EventHandler};
 
 #[tokio::main]
@@ -17,6 +20,11 @@ async fn main() -> anyhow::Result<()> {
   // Initialize logging
   env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(String::as_str) == Some("import") {
+    return import::run(&args[1..]);
+  }
+
   // Setup terminal
   let mut terminal = setup_terminal()?;
 
@@ -32,12 +40,20 @@ async fn main() -> anyhow::Result<()> {
 
   // Take the log receiver from app
   let mut log_receiver = app.log_receiver.take();
+  let mut config_events = app.config_watcher.as_ref().map(ConfigWatcher::subscribe);
 
   // Create event handler
   let mut events = EventHandler::new(Duration::from_millis(100));
 
   // Main loop
-  let result = run_app(&mut terminal, &mut app, &mut events, &mut log_receiver).await;
+  let result = run_app(
+    &mut terminal,
+    &mut app,
+    &mut events,
+    &mut log_receiver,
+    &mut config_events,
+  )
+  .await;
 
   // Restore terminal
   restore_terminal()?;
@@ -55,6 +71,7 @@ async fn run_app(
   app: &mut App,
   events: &mut EventHandler,
   log_receiver: &mut Option<tokio::sync::mpsc::Receiver<(String, app::LogEntry)>>,
+  config_events: &mut Option<broadcast::Receiver<ConfigsChanged>>,
 ) -> anyhow::Result<()> {
   loop {
     // Draw UI
@@ -100,6 +117,23 @@ async fn run_app(
         // Check if process terminated
         // This is handled in the log message itself
       }
+
+      // Config files edited outside the TUI
+      Some(changed) = async {
+        match config_events {
+          Some(rx) => Some(rx.recv().await),
+          None => None,
+        }
+      } => {
+        match changed {
+          Ok(_) | Err(RecvError::Lagged(_)) => {
+            if let Err(e) = app.reload_changed_configs() {
+              app.set_status(format!("Failed to reload config: {}", e));
+            }
+          }
+          Err(RecvError::Closed) => *config_events = None,
+        }
+      }
     }
 
     // Check for quit
@@ -110,6 +144,9 @@ async fn run_app(
     // Poll for autocomplete results
     app.poll_autocomplete();
 
+    // Apply progress of forwards starting in the background
+    actions::poll_starts(app);
+
     // Check for terminated processes and update state
     let terminated: Vec<String> = app
       .running_services
@@ -1,3 +1,6 @@
+use super::edit_field::host_key_policy_names;
+use easy_kpf_core::SshDirection;
+
 #[derive(Debug)]
 pub enum AutocompleteResult {
   Contexts(Vec<String>),
@@ -13,6 +16,9 @@ pub struct AutocompleteState {
   pub services: Vec<String>,
   pub ports: Vec<String>,
   pub types: Vec<String>,
+  pub toggles: Vec<String>,
+  pub directions: Vec<String>,
+  pub host_key_policies: Vec<String>,
   pub selected_index: usize,
   pub loading: bool,
   pub focused: bool, // Whether suggestions panel is focused
@@ -26,7 +32,16 @@ impl Default for AutocompleteState {
       namespaces: vec![],
       services: vec![],
       ports: vec![],
-      types: vec!["kubectl".to_string(), "ssh".to_string()],
+      types: vec![
+        "kubectl".to_string(),
+        "ssh".to_string(),
+        "native".to_string(),
+        "tcp".to_string(),
+        "command".to_string(),
+      ],
+      toggles: vec!["no".to_string(), "yes".to_string()],
+      directions: SshDirection::ALL.iter().map(ToString::to_string).collect(),
+      host_key_policies: host_key_policy_names(),
       selected_index: 0,
       loading: false,
       focused: false,
@@ -1,4 +1,6 @@
-use easy_kpf_core::{ForwardType, PortForwardConfig};
+use easy_kpf_core::services::ConfigField;
+use easy_kpf_core::{ForwardType, HostKeyPolicy, PortForwardConfig, SshDirection};
+use std::collections::BTreeMap;
 
 /// Represents a field in the edit form with type-safe access
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,11 +12,26 @@ pub enum EditField {
   Ports,
   LocalInterface,
   ForwardType,
+  AutoPort,
+  Tags,
+  SshDirection,
+  SshUser,
+  SshPort,
+  IdentityFile,
+  JumpHosts,
+  RemoteHost,
+  HostKeyPolicy,
+  Multiplex,
+  Program,
+  Args,
+  Env,
+  ReadyPattern,
+  Proxy,
 }
 
 impl EditField {
   /// Get all fields in order
-  pub const ALL: [EditField; 7] = [
+  pub const ALL: [EditField; 22] = [
     EditField::Name,
     EditField::Context,
     EditField::Namespace,
@@ -22,6 +39,21 @@ impl EditField {
     EditField::Ports,
     EditField::LocalInterface,
     EditField::ForwardType,
+    EditField::AutoPort,
+    EditField::Tags,
+    EditField::SshDirection,
+    EditField::SshUser,
+    EditField::SshPort,
+    EditField::IdentityFile,
+    EditField::JumpHosts,
+    EditField::RemoteHost,
+    EditField::HostKeyPolicy,
+    EditField::Multiplex,
+    EditField::Program,
+    EditField::Args,
+    EditField::Env,
+    EditField::ReadyPattern,
+    EditField::Proxy,
   ];
 
   /// Get field from index
@@ -40,6 +72,21 @@ impl EditField {
       EditField::Ports => 4,
       EditField::LocalInterface => 5,
       EditField::ForwardType => 6,
+      EditField::AutoPort => 7,
+      EditField::Tags => 8,
+      EditField::SshDirection => 9,
+      EditField::SshUser => 10,
+      EditField::SshPort => 11,
+      EditField::IdentityFile => 12,
+      EditField::JumpHosts => 13,
+      EditField::RemoteHost => 14,
+      EditField::HostKeyPolicy => 15,
+      EditField::Multiplex => 16,
+      EditField::Program => 17,
+      EditField::Args => 18,
+      EditField::Env => 19,
+      EditField::ReadyPattern => 20,
+      EditField::Proxy => 21,
     }
   }
 
@@ -49,21 +96,135 @@ impl EditField {
       EditField::Name => "Name",
       EditField::Context => "Context",
       EditField::Namespace => "Namespace",
-      EditField::Service => "Service",
+      EditField::Service => "Target",
       EditField::Ports => "Ports",
       EditField::LocalInterface => "Local Interface",
-      EditField::ForwardType => "Type (kubectl/ssh)",
+      EditField::ForwardType => "Type (kubectl/ssh/native/tcp/command)",
+      EditField::AutoPort => "Free Port Fallback",
+      EditField::Tags => "Tags",
+      EditField::SshDirection => "Direction",
+      EditField::SshUser => "SSH User",
+      EditField::SshPort => "SSH Port",
+      EditField::IdentityFile => "Identity File",
+      EditField::JumpHosts => "Jump Hosts",
+      EditField::RemoteHost => "Remote Host",
+      EditField::HostKeyPolicy => "Host Key Policy",
+      EditField::Multiplex => "Share Connection",
+      EditField::Program => "Program",
+      EditField::Args => "Arguments",
+      EditField::Env => "Environment",
+      EditField::ReadyPattern => "Ready Pattern",
+      EditField::Proxy => "Stable Local Port",
     }
   }
 
   /// Get the description/help text for the field
   pub fn description(&self) -> &'static str {
     match self {
+      EditField::Service => "svc/, pod/, deploy/, sts/ or selector/app=x",
       EditField::LocalInterface => "Optional, e.g. 127.0.0.2 to avoid port conflicts",
+      EditField::AutoPort => "yes: use the next free local port when one is busy",
+      EditField::Tags => "Comma-separated, e.g. db, cache; search with tag:db",
+      EditField::SshDirection => "local -L, remote -R (expose a local port) or dynamic -D (SOCKS)",
+      EditField::SshUser => "Optional, defaults to your ssh config",
+      EditField::SshPort => "Optional, defaults to 22",
+      EditField::IdentityFile => "Optional private key, e.g. ~/.ssh/id_ed25519",
+      EditField::JumpHosts => "Comma-separated ProxyJump chain, e.g. bastion, user@gw:2222",
+      EditField::RemoteHost => {
+        "-L: host the ports forward to (default localhost); -R: remote bind address"
+      }
+      EditField::HostKeyPolicy => "default: app-config.yaml's ssh_host_key_policy (accept-new)",
+      EditField::Multiplex => "yes: one ssh connection for all shared forwards to user@host:port",
+      EditField::Program => "e.g. cloud-sql-proxy, looked up on your shell PATH",
+      EditField::Args => {
+        "Quoted like a shell; {local_port}, {remote_port}, {interface} are filled in"
+      }
+      EditField::Env => "KEY=value pairs, quoted like Arguments",
+      EditField::ReadyPattern => "Optional regex; the forward is up once an output line matches",
+      EditField::Proxy => "yes: EasyKpf keeps the port open and restarts kubectl behind it",
       _ => "",
     }
   }
 
+  /// Config field validation errors are reported against
+  pub fn config_field(&self) -> Option<ConfigField> {
+    match self {
+      EditField::Name => Some(ConfigField::Name),
+      EditField::Context => Some(ConfigField::Context),
+      EditField::Namespace => Some(ConfigField::Namespace),
+      EditField::Service => Some(ConfigField::Service),
+      EditField::Ports => Some(ConfigField::Ports),
+      EditField::LocalInterface => Some(ConfigField::LocalInterface),
+      EditField::SshUser => Some(ConfigField::SshUser),
+      EditField::SshPort => Some(ConfigField::SshPort),
+      EditField::IdentityFile => Some(ConfigField::IdentityFile),
+      EditField::JumpHosts => Some(ConfigField::JumpHosts),
+      EditField::RemoteHost => Some(ConfigField::RemoteHost),
+      EditField::Program | EditField::Args | EditField::Env => Some(ConfigField::Command),
+      EditField::ReadyPattern => Some(ConfigField::ReadyPattern),
+      EditField::ForwardType
+      | EditField::AutoPort
+      | EditField::Tags
+      | EditField::SshDirection
+      | EditField::HostKeyPolicy
+      | EditField::Multiplex
+      | EditField::Proxy => None,
+    }
+  }
+
+  /// Whether `${VAR}` placeholders are resolved in this field
+  pub fn is_templated(&self) -> bool {
+    matches!(
+      self,
+      EditField::Context
+        | EditField::Namespace
+        | EditField::Service
+        | EditField::Ports
+        | EditField::LocalInterface
+        | EditField::SshUser
+        | EditField::IdentityFile
+        | EditField::JumpHosts
+        | EditField::RemoteHost
+        | EditField::Program
+        | EditField::Args
+        | EditField::Env
+    )
+  }
+
+  /// Whether the field only applies to (and is only shown for) SSH forwards
+  pub fn is_ssh_only(&self) -> bool {
+    matches!(
+      self,
+      EditField::SshDirection
+        | EditField::SshUser
+        | EditField::SshPort
+        | EditField::IdentityFile
+        | EditField::JumpHosts
+        | EditField::RemoteHost
+        | EditField::HostKeyPolicy
+        | EditField::Multiplex
+    )
+  }
+
+  /// Whether the field only applies to (and is only shown for) command forwards
+  pub fn is_command_only(&self) -> bool {
+    matches!(
+      self,
+      EditField::Program | EditField::Args | EditField::Env | EditField::ReadyPattern
+    )
+  }
+
+  /// Whether the field is shown when editing `config`
+  pub fn is_visible(&self, config: &PortForwardConfig) -> bool {
+    match config.forward_type {
+      ForwardType::Ssh => !self.is_command_only() && *self != EditField::Proxy,
+      // The command itself says where it connects
+      ForwardType::Command => !self.is_ssh_only() && *self != EditField::Service,
+      ForwardType::Kubectl => !self.is_ssh_only() && !self.is_command_only(),
+      _ => !self.is_ssh_only() && !self.is_command_only() && *self != EditField::Proxy,
+    }
+  }
+
   /// Check if this field supports autocomplete
   pub fn supports_autocomplete(&self) -> bool {
     matches!(
@@ -73,6 +234,11 @@ impl EditField {
         | EditField::Service
         | EditField::Ports
         | EditField::ForwardType
+        | EditField::AutoPort
+        | EditField::SshDirection
+        | EditField::HostKeyPolicy
+        | EditField::Multiplex
+        | EditField::Proxy
     )
   }
 
@@ -88,7 +254,35 @@ impl EditField {
       EditField::ForwardType => match config.forward_type {
         ForwardType::Kubectl => "kubectl".to_string(),
         ForwardType::Ssh => "ssh".to_string(),
+        ForwardType::Native => "native".to_string(),
+        ForwardType::Tcp => "tcp".to_string(),
+        ForwardType::Command => "command".to_string(),
       },
+      EditField::AutoPort => if config.auto_port { "yes" } else { "no" }.to_string(),
+      EditField::Tags => config.tags.join(", "),
+      EditField::SshDirection => config.ssh.direction.to_string(),
+      EditField::SshUser => config.ssh.user.clone().unwrap_or_default(),
+      EditField::SshPort => config.ssh.port.map(|p| p.to_string()).unwrap_or_default(),
+      EditField::IdentityFile => config.ssh.identity_file.clone().unwrap_or_default(),
+      EditField::JumpHosts => config.ssh.jump_hosts.join(", "),
+      EditField::RemoteHost => config.ssh.remote_host.clone().unwrap_or_default(),
+      EditField::HostKeyPolicy => config
+        .ssh
+        .host_key_policy
+        .map_or("default", policy_name)
+        .to_string(),
+      EditField::Multiplex => if config.ssh.multiplex { "yes" } else { "no" }.to_string(),
+      EditField::Program => config.command.program.clone(),
+      EditField::Args => join_words(config.command.args.iter()),
+      EditField::Env => join_words(
+        config
+          .command
+          .env
+          .iter()
+          .map(|(key, value)| format!("{}={}", key, value)),
+      ),
+      EditField::ReadyPattern => config.command.ready_pattern.clone().unwrap_or_default(),
+      EditField::Proxy => if config.proxy { "yes" } else { "no" }.to_string(),
     }
   }
 
@@ -110,12 +304,61 @@ impl EditField {
         config.local_interface = if value.is_empty() { None } else { Some(value) }
       }
       EditField::ForwardType => {
-        config.forward_type = if value.to_lowercase() == "ssh" {
-          ForwardType::Ssh
-        } else {
-          ForwardType::Kubectl
+        config.forward_type = match value.to_lowercase().as_str() {
+          "ssh" => ForwardType::Ssh,
+          "native" => ForwardType::Native,
+          "tcp" => ForwardType::Tcp,
+          "command" => ForwardType::Command,
+          _ => ForwardType::Kubectl,
         }
       }
+      EditField::AutoPort => config.auto_port = is_yes(&value),
+      EditField::Tags => {
+        config.tags = value
+          .split(',')
+          .map(|s| s.trim().to_string())
+          .filter(|s| !s.is_empty())
+          .collect()
+      }
+      EditField::SshDirection => {
+        config.ssh.direction = SshDirection::ALL
+          .into_iter()
+          .find(|d| d.to_string() == value.trim().to_lowercase())
+          .unwrap_or_default()
+      }
+      EditField::SshUser => config.ssh.user = optional(&value),
+      EditField::SshPort => {
+        // Out of range or not a number becomes 0 so validation reports it
+        config.ssh.port = optional(&value).map(|v| v.parse().unwrap_or(0))
+      }
+      EditField::IdentityFile => config.ssh.identity_file = optional(&value),
+      EditField::JumpHosts => {
+        config.ssh.jump_hosts = value
+          .split(',')
+          .map(|s| s.trim().to_string())
+          .filter(|s| !s.is_empty())
+          .collect()
+      }
+      EditField::RemoteHost => config.ssh.remote_host = optional(&value),
+      EditField::HostKeyPolicy => {
+        config.ssh.host_key_policy = HOST_KEY_POLICIES
+          .into_iter()
+          .find(|p| policy_name(*p) == value.trim().to_lowercase())
+      }
+      EditField::Multiplex => config.ssh.multiplex = is_yes(&value),
+      EditField::Program => config.command.program = value.trim().to_string(),
+      EditField::Args => config.command.args = split_words(&value),
+      EditField::Env => {
+        config.command.env = split_words(&value)
+          .into_iter()
+          .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (pair, String::new()),
+          })
+          .collect::<BTreeMap<_, _>>()
+      }
+      EditField::ReadyPattern => config.command.ready_pattern = optional(&value),
+      EditField::Proxy => config.proxy = is_yes(&value),
     }
   }
 
@@ -123,7 +366,7 @@ impl EditField {
   pub fn triggers_name_generation(&self) -> bool {
     matches!(
       self,
-      EditField::Service | EditField::Ports | EditField::ForwardType
+      EditField::Service | EditField::Ports | EditField::ForwardType | EditField::Program
     )
   }
 
@@ -132,3 +375,80 @@ impl EditField {
     Self::ALL.len()
   }
 }
+
+fn optional(value: &str) -> Option<String> {
+  let trimmed = value.trim();
+  if trimmed.is_empty() {
+    None
+  } else {
+    Some(trimmed.to_string())
+  }
+}
+
+/// Split a line into words like a shell: quotes group, backslash escapes
+fn split_words(line: &str) -> Vec<String> {
+  let mut words = Vec::new();
+  let mut word: Option<String> = None;
+  let mut quote = None;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some('"') | None, '\\') => {
+        if let Some(escaped) = chars.next() {
+          word.get_or_insert_with(String::new).push(escaped);
+        }
+      }
+      (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+      (None, '\'' | '"') => {
+        quote = Some(c);
+        word.get_or_insert_with(String::new);
+      }
+      (None, c) if c.is_whitespace() => words.extend(word.take()),
+      (None, c) => word.get_or_insert_with(String::new).push(c),
+    }
+  }
+  words.extend(word);
+  words
+}
+
+/// Join words so `split_words` gives them back
+fn join_words<S: AsRef<str>>(words: impl Iterator<Item = S>) -> String {
+  words
+    .map(|word| {
+      let word = word.as_ref();
+      if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || "'\"\\".contains(c)) {
+        word.to_string()
+      } else {
+        format!("'{}'", word.replace('\'', "'\"'\"'"))
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn is_yes(value: &str) -> bool {
+  matches!(value.to_lowercase().as_str(), "yes" | "y" | "true")
+}
+
+const HOST_KEY_POLICIES: [HostKeyPolicy; 3] = [
+  HostKeyPolicy::Strict,
+  HostKeyPolicy::AcceptNew,
+  HostKeyPolicy::Off,
+];
+
+fn policy_name(policy: HostKeyPolicy) -> &'static str {
+  match policy {
+    HostKeyPolicy::Strict => "strict",
+    HostKeyPolicy::AcceptNew => "accept-new",
+    HostKeyPolicy::Off => "off",
+  }
+}
+
+/// Suggestions for the host key policy field
+pub fn host_key_policy_names() -> Vec<String> {
+  std::iter::once("default")
+    .chain(HOST_KEY_POLICIES.into_iter().map(policy_name))
+    .map(str::to_string)
+    .collect()
+}
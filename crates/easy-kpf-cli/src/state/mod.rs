@@ -1,5 +1,7 @@
 mod autocomplete;
 mod edit_field;
+mod start;
 
 pub use autocomplete::{AutocompleteResult, AutocompleteState};
 pub use edit_field::EditField;
+pub use start::StartUpdate;
@@ -0,0 +1,10 @@
+use easy_kpf_core::PortForwardConfig;
+
+/// Progress of a start running in the background, applied by `poll_starts`
+#[derive(Debug)]
+pub enum StartUpdate {
+  Status(String),
+  Started(String, u32),
+  /// A local port was taken; offer the adopt/kill dialog if nothing else is open
+  PortBusy(Box<PortForwardConfig>),
+}
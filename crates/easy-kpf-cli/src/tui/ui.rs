@@ -1,12 +1,13 @@
 use crate::app::{App, Mode};
 use crate::components::{
-  draw_edit_form, draw_help_popup, draw_log_panel, draw_search_bar, draw_service_list,
-  draw_status_bar,
+  draw_edit_form, draw_help_popup, draw_log_panel, draw_profile_picker, draw_search_bar,
+  draw_service_list, draw_status_bar,
 };
+use easy_kpf_core::services::{OwnerKind, PortConflict};
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
   style::Modifier,
-  widgets::{Block, BorderType, Borders, Clear, Paragraph},
+  widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
   Frame,
 };
 
@@ -29,6 +30,7 @@ pub fn draw(frame: &mut Frame, app: &App) {
     Mode::Help => draw_help_popup(frame, app),
     Mode::Edit | Mode::Create => draw_edit_form(frame, app),
     Mode::Confirm => draw_confirm_dialog(frame, app),
+    Mode::Profiles => draw_profile_picker(frame, app),
     _ => {}
   }
 }
@@ -102,19 +104,54 @@ fn draw_confirm_dialog(frame: &mut Frame, app: &App) {
         if count == 1 { "" } else { "s" }
       )
     }
+    Some(crate::app::ConfirmAction::PortOwners(config, conflicts)) => {
+      let owners: Vec<String> = conflicts.iter().map(PortConflict::describe).collect();
+      let can_adopt = conflicts
+        .iter()
+        .any(|c| matches!(&c.owner.kind, OwnerKind::Orphan { name } if *name == config.name));
+      format!(
+        "Ports of '{}' are in use:\n\n{}\n\n[k] Kill  {}[n] Cancel",
+        config.name,
+        owners.join("\n"),
+        if can_adopt { "[a] Adopt  " } else { "" }
+      )
+    }
+    Some(crate::app::ConfirmAction::PinHostKeys(keys)) => {
+      let store = app.config_service.host_keys();
+      let fingerprints: Vec<String> = keys
+        .iter()
+        .map(|k| {
+          let pinned = store.is_pinned(k).unwrap_or(false);
+          format!(
+            "{} {}{}",
+            k.key_type,
+            k.fingerprint,
+            if pinned { " (pinned)" } else { "" }
+          )
+        })
+        .collect();
+      format!(
+        "Pin host key of {}?\n\n{}\n\nCompare with the fingerprint from the server's admin first.\n\n[y] Pin  [n] Cancel",
+        keys.first().map_or("", |k| k.host.as_str()),
+        fingerprints.join("\n")
+      )
+    }
     Some(crate::app::ConfirmAction::CancelEdit(_)) => {
       "Discard changes?\n\n[y] Yes  [n] No".to_string()
     }
     None => "Confirm?".to_string(),
   };
 
-  let popup = Paragraph::new(message).style(theme.warning()).block(
-    Block::default()
-      .title(" Confirm ")
-      .borders(Borders::ALL)
-      .border_type(BorderType::Rounded)
-      .border_style(theme.warning()),
-  );
+  let popup = Paragraph::new(message)
+    .style(theme.warning())
+    .wrap(Wrap { trim: false })
+    .block(
+      Block::default()
+        .title(" Confirm ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.warning()),
+    );
 
   frame.render_widget(popup, area);
 }
@@ -1,3 +1,4 @@
+use crate::services::validation::ValidationErrors;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,6 +9,9 @@ pub enum AppError {
   #[error("Kubectl error: {0}")]
   Kubectl(String),
 
+  #[error("Kubernetes API error: {0}")]
+  Kubernetes(String),
+
   #[error("Port forward error: {0}")]
   PortForward(String),
 
@@ -26,6 +30,12 @@ pub enum AppError {
   #[error("Invalid input: {0}")]
   InvalidInput(String),
 
+  #[error("Invalid configuration: {0}")]
+  Validation(ValidationErrors),
+
+  #[error("Host key error: {0}")]
+  HostKey(String),
+
   #[error("System error: {0}")]
   System(String),
 }
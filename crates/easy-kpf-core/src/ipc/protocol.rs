@@ -1,14 +1,56 @@
+use crate::services::{ExportFormat, PortConflict, ProfileStatus, TrafficStats};
+use crate::types::{ForwardType, SshDirection};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "cmd", rename_all = "kebab-case")]
 pub enum Request {
   ReconnectAll,
-  Start { name: String },
-  Stop { name: String },
+  Start {
+    name: String,
+  },
+  Stop {
+    name: String,
+  },
+  /// Start every config carrying `tag`
+  StartTag {
+    tag: String,
+  },
+  StopTag {
+    tag: String,
+  },
   List,
   Status,
   Show,
+  /// Named configs, or every config of `context` when `names` is empty
+  Export {
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    context: Option<String>,
+    format: ExportFormat,
+  },
+  /// Processes holding the local ports of config `name`, optionally adopting or killing them
+  PortOwners {
+    name: String,
+    #[serde(default)]
+    action: Option<OwnerAction>,
+  },
+  /// Every profile with its running and stopped forwards
+  Profiles,
+  StartProfile {
+    name: String,
+  },
+  StopProfile {
+    name: String,
+  },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnerAction {
+  Adopt,
+  Kill,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +64,8 @@ pub enum Response {
 #[serde(untagged)]
 pub enum ResponseData {
   Services(Vec<ServiceStatus>),
+  PortOwners(Vec<PortConflict>),
+  Profiles(Vec<ProfileStatus>),
   Reconnected(Vec<String>),
   Text(String),
   Empty,
@@ -31,4 +75,15 @@ pub enum ResponseData {
 pub struct ServiceStatus {
   pub name: String,
   pub running: bool,
+  /// Local port mappings in use, for running services
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub ports: Vec<String>,
+  #[serde(default)]
+  pub forward_type: ForwardType,
+  /// Set for SSH forwards
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ssh_direction: Option<SshDirection>,
+  /// Connection counters, for forwards whose connections pass through EasyKpf
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub traffic: Option<TrafficStats>,
 }
@@ -6,11 +6,13 @@ pub mod types;
 
 pub use error::{AppError, Result};
 pub use services::{
-  ConfigCache, ConfigService, InterfaceManager, KubectlCommandBuilder, LastActiveSet,
-  ProcessDetector, ProcessManager, SshCommandBuilder, SystemInterfaceManager,
+  CommandImporter, ConfigCache, ConfigService, ImportReport, InterfaceManager,
+  KubectlCommandBuilder, LastActiveSet, ProcessDetector, ProcessManager, SshCommandBuilder,
+  SystemInterfaceManager,
 };
 pub use traits::{CommandExecutor, ProcessEvent, ProcessHandle, ProcessOutput};
 pub use types::{
-  AppConfig, ForwardType, PortForwardConfig, PortForwardConfigs, ProcessInfo, ProcessManagerState,
-  SerializableProcessInfo,
+  AppConfig, CommandSettings, ConfigSource, ForwardTarget, ForwardType, HostKeyPolicy,
+  PortForwardConfig, PortForwardConfigs, ProcessInfo, ProcessManagerState, SerializableProcessInfo,
+  SshDirection, SshMaster, SshSettings,
 };
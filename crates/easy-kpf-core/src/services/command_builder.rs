@@ -1,4 +1,10 @@
-use crate::types::PortForwardConfig;
+use crate::error::{AppError, Result};
+use crate::traits::CommandExecutor;
+use crate::types::{ForwardTarget, HostKeyPolicy, PortForwardConfig, SshDirection, SshSettings};
+use std::path::{Path, PathBuf};
+
+/// Program, arguments and environment for a kubectl invocation
+pub type KubectlCommand = (String, Vec<String>, Vec<(String, String)>);
 
 pub struct KubectlCommandBuilder {
   kubectl_path: String,
@@ -15,7 +21,7 @@ impl KubectlCommandBuilder {
 
   /// Build PATH environment variable that includes common locations for credential plugins.
   /// macOS apps launched from Finder don't inherit shell PATH, so we need to construct one.
-  fn build_path_env() -> String {
+  pub(crate) fn build_path_env() -> String {
     let mut paths = Vec::new();
 
     // Start with current PATH if available
@@ -102,10 +108,8 @@ impl KubectlCommandBuilder {
     env_vars
   }
 
-  pub fn build_port_forward_command(
-    &self,
-    config: &PortForwardConfig,
-  ) -> (String, Vec<String>, Vec<(String, String)>) {
+  /// `--context`/`-n` flags shared by every kubectl invocation for a config
+  fn scope_args(config: &PortForwardConfig) -> Vec<String> {
     let mut args = vec![];
 
     // Add context flag only if context is not empty
@@ -113,12 +117,28 @@ impl KubectlCommandBuilder {
       args.extend_from_slice(&["--context".to_string(), config.context.clone()]);
     }
 
-    args.extend_from_slice(&[
-      "-n".to_string(),
-      config.namespace.clone(),
-      "port-forward".to_string(),
-      config.service.clone(),
-    ]);
+    args.extend_from_slice(&["-n".to_string(), config.namespace.clone()]);
+    args
+  }
+
+  fn build_env(&self) -> Vec<(String, String)> {
+    // Get environment variables for credential plugins
+    let mut env_vars = Self::get_credential_env_vars();
+
+    // Add KUBECONFIG if specified
+    if let Some(ref kubeconfig) = self.kubeconfig_path {
+      env_vars.push(("KUBECONFIG".to_string(), kubeconfig.clone()));
+    }
+
+    env_vars
+  }
+
+  /// Build the port-forward command. Selector targets must be resolved with
+  /// `resolve_target` first, kubectl itself cannot forward to a selector.
+  pub fn build_port_forward_command(&self, config: &PortForwardConfig) -> KubectlCommand {
+    let mut args = Self::scope_args(config);
+
+    args.extend_from_slice(&["port-forward".to_string(), config.target().to_string()]);
 
     // Add --address flag if local interface is specified
     if let Some(ref interface) = config.local_interface {
@@ -133,33 +153,199 @@ impl KubectlCommandBuilder {
     // Add port mappings
     args.extend(config.ports.clone());
 
-    // Get environment variables for credential plugins
-    let mut env_vars = Self::get_credential_env_vars();
+    (self.kubectl_path.clone(), args, self.build_env())
+  }
 
-    // Add KUBECONFIG if specified
-    if let Some(ref kubeconfig) = self.kubeconfig_path {
-      env_vars.push(("KUBECONFIG".to_string(), kubeconfig.clone()));
+  /// Build a `kubectl get pods -l <selector>` command listing pod names with their
+  /// Ready condition, one per line. Returns None for non-selector targets.
+  pub fn build_ready_pod_command(&self, config: &PortForwardConfig) -> Option<KubectlCommand> {
+    let ForwardTarget::Selector(selector) = config.target() else {
+      return None;
+    };
+
+    let mut args = Self::scope_args(config);
+    args.extend_from_slice(&[
+      "get".to_string(),
+      "pods".to_string(),
+      "-l".to_string(),
+      selector,
+      "-o".to_string(),
+      r#"jsonpath={range .items[*]}{.metadata.name}{" "}{.status.conditions[?(@.type=="Ready")].status}{"\n"}{end}"#
+        .to_string(),
+    ]);
+
+    Some((self.kubectl_path.clone(), args, self.build_env()))
+  }
+
+  /// Pick the first ready pod from `build_ready_pod_command` output
+  pub fn parse_ready_pod(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+      let mut parts = line.split_whitespace();
+      let name = parts.next()?;
+      (parts.next() == Some("True")).then(|| name.to_string())
+    })
+  }
+
+  /// Return a copy of the config whose target is a concrete resource.
+  /// Selector targets are resolved to the first ready pod; others pass through.
+  pub async fn resolve_target<E: CommandExecutor>(
+    &self,
+    executor: &E,
+    config: &PortForwardConfig,
+  ) -> Result<PortForwardConfig> {
+    let Some((program, args, env)) = self.build_ready_pod_command(config) else {
+      return Ok(config.clone());
+    };
+
+    let output = executor.execute(&program, &args, &env).await?;
+    if !output.success {
+      return Err(AppError::Kubectl(
+        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+      ));
     }
 
-    (self.kubectl_path.clone(), args, env_vars)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Self::with_resolved_pod(config, Self::parse_ready_pod(&stdout))
+  }
+
+  /// Substitute a resolved pod name into a selector config
+  pub fn with_resolved_pod(
+    config: &PortForwardConfig,
+    pod: Option<String>,
+  ) -> Result<PortForwardConfig> {
+    let pod = pod.ok_or_else(|| {
+      AppError::PortForward(format!(
+        "No ready pod matches selector '{}' in {}",
+        config.target().name(),
+        config.namespace
+      ))
+    })?;
+
+    let mut resolved = config.clone();
+    resolved.service = ForwardTarget::Pod(pod).to_string();
+    Ok(resolved)
   }
 }
 
-pub struct SshCommandBuilder;
+/// Builds `ssh` forward commands. Host keys are checked against `known_hosts` when
+/// set, otherwise against the user's own known_hosts files.
+pub struct SshCommandBuilder {
+  known_hosts: Option<PathBuf>,
+  default_policy: HostKeyPolicy,
+  control_dir: PathBuf,
+}
 
 impl SshCommandBuilder {
   pub fn new() -> Self {
-    Self
+    Self {
+      known_hosts: None,
+      default_policy: HostKeyPolicy::default(),
+      control_dir: std::env::temp_dir().join("easykpf-ssh"),
+    }
+  }
+
+  /// `default_policy` applies to configs without their own `host_key_policy`
+  pub fn with_known_hosts(known_hosts: PathBuf, default_policy: HostKeyPolicy) -> Self {
+    Self {
+      known_hosts: Some(known_hosts),
+      default_policy,
+      ..Self::new()
+    }
+  }
+
+  /// Keep the control sockets of multiplexed connections in `dir`
+  pub fn with_control_dir(mut self, dir: PathBuf) -> Self {
+    self.control_dir = dir;
+    self
+  }
+
+  pub fn control_dir(&self) -> &Path {
+    &self.control_dir
+  }
+
+  pub fn policy(&self, settings: &SshSettings) -> HostKeyPolicy {
+    settings.host_key_policy.unwrap_or(self.default_policy)
+  }
+
+  /// `StrictHostKeyChecking` and `UserKnownHostsFile` options
+  pub fn host_key_args(&self, settings: &SshSettings) -> Vec<String> {
+    let policy = self.policy(settings);
+    let mut args = vec![
+      "-o".to_string(),
+      format!("StrictHostKeyChecking={}", policy.ssh_option()),
+    ];
+    // With checks off nothing is read or pinned; a stale key would still make
+    // ssh disable forwarding
+    let known_hosts = match (policy, &self.known_hosts) {
+      (HostKeyPolicy::Off, _) => Some("/dev/null".to_string()),
+      (_, Some(path)) => Some(format!("\"{}\"", path.display())),
+      (_, None) => None,
+    };
+    if let Some(known_hosts) = known_hosts {
+      args.extend([
+        "-o".to_string(),
+        format!("UserKnownHostsFile={}", known_hosts),
+      ]);
+    }
+    args
   }
 
   pub fn build_port_forward_command(&self, config: &PortForwardConfig) -> (String, Vec<String>) {
+    let mut args = self.session_args(config);
+    args.extend(Self::forward_args(config));
+    args.push(config.service.clone());
+
+    ("ssh".to_string(), args)
+  }
+
+  /// A master connection to `config`'s host without any forwards, which
+  /// `build_control_command` adds them to through `control_path`
+  pub fn build_master_command(
+    &self,
+    config: &PortForwardConfig,
+    control_path: &Path,
+  ) -> (String, Vec<String>) {
+    let mut args = vec![
+      "-M".to_string(),
+      "-S".to_string(),
+      control_path.display().to_string(),
+      "-o".to_string(),
+      "ControlPersist=no".to_string(),
+    ];
+    args.extend(self.session_args(config));
+    args.push(config.service.clone());
+
+    ("ssh".to_string(), args)
+  }
+
+  /// `ssh -O <operation>` on the master at `control_path`. `forward` and `cancel`
+  /// carry `config`'s forwards; `check` and `exit` don't need them.
+  pub fn build_control_command(
+    config: &PortForwardConfig,
+    control_path: &Path,
+    operation: &str,
+  ) -> (String, Vec<String>) {
+    let mut args = vec![
+      "-S".to_string(),
+      control_path.display().to_string(),
+      "-O".to_string(),
+      operation.to_string(),
+    ];
+    if matches!(operation, "forward" | "cancel") {
+      args.extend(Self::forward_args(config));
+    }
+    args.push(config.service.clone());
+
+    ("ssh".to_string(), args)
+  }
+
+  /// Options and connection arguments shared by forwards and masters
+  fn session_args(&self, config: &PortForwardConfig) -> Vec<String> {
     let mut args = vec![
       "-N".to_string(), // Don't execute remote command
       "-o".to_string(),
       "BatchMode=yes".to_string(), // Don't prompt for passwords
       "-o".to_string(),
-      "StrictHostKeyChecking=no".to_string(), // Don't prompt for host key verification
-      "-o".to_string(),
       "ConnectTimeout=10".to_string(), // 10 second connection timeout
       "-o".to_string(),
       "ServerAliveInterval=60".to_string(), // Keep connection alive
@@ -167,18 +353,51 @@ impl SshCommandBuilder {
       "ServerAliveCountMax=3".to_string(), // Max keep-alive attempts
     ];
 
-    // Add port forwarding arguments
-    let port_mapper = SshPortMapper::new();
-    let forward_args =
-      port_mapper.build_port_mappings(&config.ports, config.local_interface.as_deref());
+    args.extend(self.host_key_args(&config.ssh));
+    args.extend(Self::connection_args(&config.ssh));
+    args
+  }
 
-    for forward_arg in forward_args {
-      args.extend_from_slice(&["-L".to_string(), forward_arg]);
-    }
+  /// One `-L`, `-R` or `-D` option per port mapping, in the config's direction
+  pub fn forward_args(config: &PortForwardConfig) -> Vec<String> {
+    let port_mapper = SshPortMapper::new();
+    let local_interface = config.local_interface.as_deref();
+    let mappings = match config.ssh.direction {
+      SshDirection::Local => port_mapper.build_port_mappings(
+        &config.ports,
+        local_interface,
+        config.ssh.remote_host.as_deref().unwrap_or("localhost"),
+      ),
+      SshDirection::Remote => port_mapper.build_remote_mappings(
+        &config.ports,
+        local_interface,
+        config.ssh.remote_host.as_deref(),
+      ),
+      SshDirection::Dynamic => port_mapper.build_dynamic_mappings(&config.ports, local_interface),
+    };
 
-    args.push(config.service.clone());
+    mappings
+      .into_iter()
+      .flat_map(|mapping| [config.ssh.direction.flag().to_string(), mapping])
+      .collect()
+  }
 
-    ("ssh".to_string(), args)
+  /// `-l`, `-p`, `-i` and `-J` options for reaching the host
+  pub fn connection_args(settings: &SshSettings) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(ref user) = settings.user {
+      args.extend(["-l".to_string(), user.clone()]);
+    }
+    if let Some(port) = settings.port {
+      args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(ref identity_file) = settings.identity_file {
+      args.extend(["-i".to_string(), identity_file.clone()]);
+    }
+    if !settings.jump_hosts.is_empty() {
+      args.extend(["-J".to_string(), settings.jump_hosts.join(",")]);
+    }
+    args
   }
 }
 
@@ -199,20 +418,68 @@ impl SshPortMapper {
     &self,
     ports: &[String],
     local_interface: Option<&str>,
+    remote_host: &str,
+  ) -> Vec<String> {
+    let (bind_ip, bind_port_override) = Self::parse_interface(local_interface);
+
+    ports
+      .iter()
+      .map(|port_mapping| {
+        self.format_port_mapping(port_mapping, bind_ip, bind_port_override, remote_host)
+      })
+      .collect()
+  }
+
+  /// `-R` arguments: `local:remote` listens on `remote` on the SSH host (bound to
+  /// `remote_bind` if given) and delivers to `local` on the local interface
+  pub fn build_remote_mappings(
+    &self,
+    ports: &[String],
+    local_interface: Option<&str>,
+    remote_bind: Option<&str>,
+  ) -> Vec<String> {
+    let (local_ip, _) = Self::parse_interface(local_interface);
+    let bind = remote_bind.map(|b| format!("{}:", b)).unwrap_or_default();
+
+    ports
+      .iter()
+      .map(|port_mapping| {
+        let parts: Vec<&str> = port_mapping.split(':').collect();
+        match parts.as_slice() {
+          [port] => format!("{}{}:{}:{}", bind, port, local_ip, port),
+          [local, remote] => format!("{}{}:{}:{}", bind, remote, local_ip, local),
+          _ => format!("{}{}", bind, port_mapping),
+        }
+      })
+      .collect()
+  }
+
+  /// `-D` arguments: a SOCKS proxy on each mapping's local port
+  pub fn build_dynamic_mappings(
+    &self,
+    ports: &[String],
+    local_interface: Option<&str>,
   ) -> Vec<String> {
-    // Parse interface - may contain "ip:port" (e.g., "127.0.0.2:5335") or just "ip"
-    let (bind_ip, bind_port_override) = match local_interface {
+    let (bind_ip, bind_port_override) = Self::parse_interface(local_interface);
+
+    ports
+      .iter()
+      .map(|port_mapping| {
+        let local_port = port_mapping.split(':').next().unwrap_or(port_mapping);
+        format!("{}:{}", bind_ip, bind_port_override.unwrap_or(local_port))
+      })
+      .collect()
+  }
+
+  /// Interface may contain "ip:port" (e.g., "127.0.0.2:5335") or just "ip"
+  fn parse_interface(local_interface: Option<&str>) -> (&str, Option<&str>) {
+    match local_interface {
       Some(iface) => match iface.rsplit_once(':') {
         Some((ip, port)) if port.parse::<u16>().is_ok() => (ip, Some(port)),
         _ => (iface, None),
       },
       None => ("127.0.0.1", None),
-    };
-
-    ports
-      .iter()
-      .map(|port_mapping| self.format_port_mapping(port_mapping, bind_ip, bind_port_override))
-      .collect()
+    }
   }
 
   fn format_port_mapping(
@@ -220,6 +487,7 @@ impl SshPortMapper {
     port_mapping: &str,
     bind_ip: &str,
     bind_port_override: Option<&str>,
+    remote_host: &str,
   ) -> String {
     let parts: Vec<&str> = port_mapping.split(':').collect();
 
@@ -227,12 +495,12 @@ impl SshPortMapper {
       1 => {
         // Single port - use override as local port if present, otherwise same port
         let local_port = bind_port_override.unwrap_or(parts[0]);
-        format!("{}:{}:localhost:{}", bind_ip, local_port, parts[0])
+        format!("{}:{}:{}:{}", bind_ip, local_port, remote_host, parts[0])
       }
       2 => {
         // "local:remote" format - use override as local port if present
         let local_port = bind_port_override.unwrap_or(parts[0]);
-        format!("{}:{}:localhost:{}", bind_ip, local_port, parts[1])
+        format!("{}:{}:{}:{}", bind_ip, local_port, remote_host, parts[1])
       }
       _ => {
         // Already in correct format or custom format
@@ -247,3 +515,72 @@ impl Default for SshPortMapper {
     Self::new()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::ForwardType;
+
+  #[test]
+  fn builds_ssh_forward_through_a_bastion() {
+    let config = PortForwardConfig {
+      name: "db".to_string(),
+      service: "db-proxy.internal".to_string(),
+      ports: vec!["5432".to_string(), "6380:cache.internal:6379".to_string()],
+      forward_type: ForwardType::Ssh,
+      ssh: SshSettings {
+        user: Some("deploy".to_string()),
+        port: Some(2222),
+        identity_file: Some("~/.ssh/deploy".to_string()),
+        jump_hosts: vec![
+          "bastion.example.com".to_string(),
+          "ops@inner:2200".to_string(),
+        ],
+        remote_host: Some("postgres.internal".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+
+    let (program, args) = SshCommandBuilder::new().build_port_forward_command(&config);
+    assert_eq!(program, "ssh");
+    let command = args.join(" ");
+    assert!(
+      command.contains("-l deploy -p 2222 -i ~/.ssh/deploy -J bastion.example.com,ops@inner:2200")
+    );
+    assert!(command.ends_with(
+      "-L 127.0.0.1:5432:postgres.internal:5432 -L 127.0.0.1:6380:cache.internal:6379 db-proxy.internal"
+    ));
+  }
+
+  #[test]
+  fn builds_reverse_and_dynamic_forwards_and_detects_them() {
+    let mut config = PortForwardConfig {
+      name: "dev-server".to_string(),
+      service: "box.example.com".to_string(),
+      ports: vec!["3000:8080".to_string()],
+      forward_type: ForwardType::Ssh,
+      ssh: SshSettings {
+        direction: SshDirection::Remote,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let builder = SshCommandBuilder::new();
+    let (_, args) = builder.build_port_forward_command(&config);
+    let command = format!("ssh {}", args.join(" "));
+    assert!(command.ends_with("-R 8080:127.0.0.1:3000 box.example.com"));
+
+    let detector = crate::services::ProcessDetector::new();
+    let ps_line = format!("me 4242 0.0 0.1 0 0 ?? S 10:00 0:00.01 {}", command);
+    assert!(detector.matches_command(&ps_line, &config));
+
+    config.ssh.direction = SshDirection::Dynamic;
+    config.ports = vec!["1080".to_string()];
+    let (_, args) = builder.build_port_forward_command(&config);
+    assert!(args
+      .join(" ")
+      .ends_with("-D 127.0.0.1:1080 box.example.com"));
+    assert!(!detector.matches_command(&ps_line, &config));
+  }
+}
@@ -1,8 +1,12 @@
 use crate::error::{AppError, Result};
-use crate::services::ConfigService;
-use crate::types::{ForwardType, PortForwardConfig};
+use crate::services::{
+  template, validation, ConfigService, ConfigWatcher, Profiles, ValidationErrors, Variables,
+};
+use crate::types::{ConfigSource, ForwardType, PortForwardConfig};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
 
 #[derive(Clone)]
 pub struct ConfigCache {
@@ -12,8 +16,19 @@ pub struct ConfigCache {
 }
 
 struct CacheData {
-  configs: Option<Vec<PortForwardConfig>>,
+  files: Option<CachedFiles>,
   last_updated: Option<Instant>,
+  /// Invalidated by a `ConfigWatcher`, so the TTL no longer applies
+  watched: bool,
+  /// Where an unparseable config file was moved on reload, until reported
+  recovered_from: Option<PathBuf>,
+}
+
+/// What the config files held when last read
+struct CachedFiles {
+  configs: Vec<PortForwardConfig>,
+  variables: Variables,
+  profiles: Profiles,
 }
 
 /// Helper to convert PoisonError to AppError
@@ -26,8 +41,10 @@ impl ConfigCache {
     Self {
       config_service,
       cache: Arc::new(Mutex::new(CacheData {
-        configs: None,
+        files: None,
         last_updated: None,
+        watched: false,
+        recovered_from: None,
       })),
       ttl: Duration::from_secs(5), // Cache for 5 seconds
     }
@@ -38,32 +55,37 @@ impl ConfigCache {
     Self {
       config_service,
       cache: Arc::new(Mutex::new(CacheData {
-        configs: None,
+        files: None,
         last_updated: None,
+        watched: false,
+        recovered_from: None,
       })),
       ttl,
     }
   }
 
   pub fn get_configs(&self) -> Result<Vec<PortForwardConfig>> {
-    let mut cache_data = self.cache.lock().map_err(lock_error)?;
-
-    if let Some(configs) = self.get_valid_cache(&cache_data) {
-      return Ok(configs);
-    }
-
-    // Cache is invalid, reload from service
-    let configs = self.config_service.load_port_forwards()?;
-    cache_data.configs = Some(configs.clone());
-    cache_data.last_updated = Some(Instant::now());
+    self.read_files(|files| files.configs.clone())
+  }
 
-    Ok(configs)
+  /// Drop the cached list whenever `watcher` sees the files change, instead of
+  /// re-reading them every TTL
+  pub fn invalidate_on_change(&self, watcher: &ConfigWatcher) -> Result<()> {
+    self.cache.lock().map_err(lock_error)?.watched = true;
+
+    let mut events = watcher.subscribe();
+    let cache = self.clone();
+    std::thread::spawn(move || {
+      while !matches!(events.blocking_recv(), Err(RecvError::Closed)) {
+        let _ = cache.invalidate();
+      }
+    });
+    Ok(())
   }
 
-  #[allow(dead_code)]
   pub fn invalidate(&self) -> Result<()> {
     let mut cache_data = self.cache.lock().map_err(lock_error)?;
-    cache_data.configs = None;
+    cache_data.files = None;
     cache_data.last_updated = None;
     Ok(())
   }
@@ -72,10 +94,12 @@ impl ConfigCache {
     // Save to persistent storage
     self.config_service.save_port_forwards(&configs)?;
 
-    // Update cache
+    // Update cache; saving leaves the variables and profiles as they were
     let mut cache_data = self.cache.lock().map_err(lock_error)?;
-    cache_data.configs = Some(configs);
-    cache_data.last_updated = Some(Instant::now());
+    if let Some(ref mut files) = cache_data.files {
+      files.configs = configs;
+      cache_data.last_updated = Some(Instant::now());
+    }
 
     Ok(())
   }
@@ -87,20 +111,90 @@ impl ConfigCache {
 
   pub fn add_config(&self, config: PortForwardConfig) -> Result<()> {
     let mut configs = self.get_configs()?;
+    let variables = self.get_variables()?;
+    reject_invalid(validation::validate_against(
+      &config, &configs, None, &variables,
+    ))?;
     configs.push(config);
     self.update_configs(configs)
   }
 
+  /// Check `config` against the cached list; `replacing` names the entry being edited
+  pub fn validate_config(
+    &self,
+    config: &PortForwardConfig,
+    replacing: Option<&str>,
+  ) -> Result<ValidationErrors> {
+    let configs = self.get_configs()?;
+    let variables = self.get_variables()?;
+    Ok(validation::validate_against(
+      config, &configs, replacing, &variables,
+    ))
+  }
+
+  /// `variables:` of the loaded config files, for resolving `${VAR}` placeholders
+  pub fn get_variables(&self) -> Result<Variables> {
+    self.read_files(|files| files.variables.clone())
+  }
+
+  /// `profiles:` of the loaded config files
+  pub fn get_profiles(&self) -> Result<Profiles> {
+    self.read_files(|files| files.profiles.clone())
+  }
+
+  /// Where the config file was moved after failing to parse, once per recovery
+  pub fn take_recovered_from(&self) -> Result<Option<PathBuf>> {
+    Ok(self.cache.lock().map_err(lock_error)?.recovered_from.take())
+  }
+
+  /// `config` with placeholders resolved, ready for the command builders
+  pub fn expand_config(&self, config: &PortForwardConfig) -> Result<PortForwardConfig> {
+    template::expand_config(config, &self.get_variables()?).map_err(AppError::Validation)
+  }
+
+  /// All configs with placeholders resolved where possible, e.g. for matching
+  /// running processes
+  pub fn get_expanded_configs(&self) -> Result<Vec<PortForwardConfig>> {
+    Ok(template::expand_configs_lenient(
+      &self.get_configs()?,
+      &self.get_variables()?,
+    ))
+  }
+
+  /// Fails for entries pulled in by an `include:` directive, which are read-only
+  pub fn ensure_writable(&self, service_key: &str) -> Result<()> {
+    match self.find_config(service_key)? {
+      Some(config) => check_writable(&config),
+      None => Ok(()),
+    }
+  }
+
   pub fn remove_config(&self, service_key: &str) -> Result<()> {
     let mut configs = self.get_configs()?;
+    if let Some(config) = configs.iter().find(|c| c.name == service_key) {
+      check_writable(config)?;
+    }
     configs.retain(|c| c.name != service_key);
     self.update_configs(configs)
   }
 
-  pub fn update_config(&self, old_service_key: &str, new_config: PortForwardConfig) -> Result<()> {
+  pub fn update_config(
+    &self,
+    old_service_key: &str,
+    mut new_config: PortForwardConfig,
+  ) -> Result<()> {
     let mut configs = self.get_configs()?;
 
     if let Some(index) = configs.iter().position(|c| c.name == old_service_key) {
+      check_writable(&configs[index])?;
+      reject_invalid(validation::validate_against(
+        &new_config,
+        &configs,
+        Some(old_service_key),
+        &self.get_variables()?,
+      ))?;
+      // Edits stay in the file the entry came from
+      new_config.source = configs[index].source.clone();
       configs[index] = new_config;
       self.update_configs(configs)
     } else {
@@ -140,23 +234,60 @@ impl ConfigCache {
     self.update_configs(configs)
   }
 
-  /// Get cached configs if valid, None otherwise
-  fn get_valid_cache(&self, cache_data: &CacheData) -> Option<Vec<PortForwardConfig>> {
-    let configs = cache_data.configs.as_ref()?;
-    let last_updated = cache_data.last_updated?;
+  /// Run `read` on the cached files, reloading them first if the cache is invalid
+  fn read_files<T>(&self, read: impl FnOnce(&CachedFiles) -> T) -> Result<T> {
+    let mut cache_data = self.cache.lock().map_err(lock_error)?;
+    let fresh = cache_data
+      .last_updated
+      .is_some_and(|last_updated| cache_data.watched || last_updated.elapsed() < self.ttl);
+
+    let files = match cache_data.files.take().filter(|_| fresh) {
+      Some(files) => files,
+      None => {
+        let loaded = self.config_service.load_port_forwards_with_recovery()?;
+        loaded.log_problems();
+        cache_data.last_updated = Some(Instant::now());
+        if loaded.recovered_from.is_some() {
+          cache_data.recovered_from = loaded.recovered_from;
+        }
+        CachedFiles {
+          configs: loaded.configs,
+          variables: loaded.variables,
+          profiles: loaded.profiles,
+        }
+      }
+    };
+    let value = read(&files);
+    cache_data.files = Some(files);
+    Ok(value)
+  }
+}
+
+fn reject_invalid(errors: ValidationErrors) -> Result<()> {
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(AppError::Validation(errors))
+  }
+}
 
-    if last_updated.elapsed() < self.ttl {
-      Some(configs.clone())
-    } else {
-      None
-    }
+fn check_writable(config: &PortForwardConfig) -> Result<()> {
+  match config.source {
+    ConfigSource::Include(ref path) => Err(AppError::InvalidInput(format!(
+      "'{}' is read-only (included from {})",
+      config.name,
+      path.display()
+    ))),
+    _ => Ok(()),
   }
 }
 
 fn config_group_key(config: &PortForwardConfig) -> &str {
   match &config.forward_type {
     ForwardType::Ssh => "SSH",
-    ForwardType::Kubectl => &config.context,
+    ForwardType::Tcp => "TCP",
+    ForwardType::Command => "Commands",
+    ForwardType::Kubectl | ForwardType::Native => &config.context,
   }
 }
 
@@ -209,6 +340,7 @@ mod tests {
       ports: vec!["8080:80".to_string()],
       local_interface: None,
       forward_type,
+      ..Default::default()
     }
   }
 
@@ -248,4 +380,20 @@ mod tests {
     assert_eq!(names, vec!["ssh-1", "ssh-2", "kube"]);
     Ok(())
   }
+
+  #[test]
+  fn reports_a_recovered_config_file_once() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("easykpf-cache-recovery-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let service = ConfigService::with_config_dir(dir.clone(), None)?;
+    std::fs::write(dir.join("port-forwards.yaml"), "configs: [unclosed")?;
+
+    let cache = ConfigCache::new(service);
+    assert!(cache.get_configs()?.is_empty());
+    assert!(cache.take_recovered_from()?.is_some());
+    assert!(cache.take_recovered_from()?.is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+  }
 }
@@ -0,0 +1,128 @@
+use crate::error::{AppError, Result};
+use serde_yaml::{Mapping, Value};
+
+/// Schema version written by this build. Bump it and append a step to
+/// `MIGRATIONS` whenever `port-forwards.yaml` changes shape.
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+type Migration = fn(&mut Mapping) -> Result<()>;
+
+/// `MIGRATIONS[n]` upgrades a document from version `n` to `n + 1`
+const MIGRATIONS: [Migration; CURRENT_CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Read the schema version of a raw document. Files written before versioning
+/// have no `version` key and are treated as version 0.
+pub fn document_version(doc: &Value) -> Result<u32> {
+  match doc.get("version") {
+    None | Some(Value::Null) => Ok(0),
+    Some(value) => value
+      .as_u64()
+      .and_then(|v| u32::try_from(v).ok())
+      .ok_or_else(|| malformed(format!("Invalid config version: {:?}", value))),
+  }
+}
+
+/// Upgrade a raw document in place to `CURRENT_CONFIG_VERSION`, one step at a time.
+/// Returns the version the document had before migrating.
+pub fn migrate(doc: &mut Value) -> Result<u32> {
+  let from = document_version(doc)?;
+  if from > CURRENT_CONFIG_VERSION {
+    return Err(AppError::Config(format!(
+      "Config file version {} is newer than supported version {}",
+      from, CURRENT_CONFIG_VERSION
+    )));
+  }
+
+  let mapping = doc
+    .as_mapping_mut()
+    .ok_or_else(|| malformed("Config file must be a YAML mapping".to_string()))?;
+
+  for version in from..CURRENT_CONFIG_VERSION {
+    log::info!(
+      "Migrating port-forwards.yaml from v{} to v{}",
+      version,
+      version + 1
+    );
+    MIGRATIONS[version as usize](mapping)?;
+    mapping.insert("version".into(), Value::from(version + 1));
+  }
+
+  Ok(from)
+}
+
+/// A document not shaped like a config file. Reported like a YAML syntax error, so
+/// that loading moves the file aside instead of failing to start.
+fn malformed(message: String) -> AppError {
+  AppError::Serde(serde::de::Error::custom(message))
+}
+
+fn configs_mut(doc: &mut Mapping) -> impl Iterator<Item = &mut Mapping> {
+  doc
+    .get_mut("configs")
+    .and_then(Value::as_sequence_mut)
+    .into_iter()
+    .flatten()
+    .filter_map(Value::as_mapping_mut)
+}
+
+/// v0 files were hand-edited freely: ports were often written as bare numbers,
+/// `configs` could be missing and an empty `local_interface` meant "none".
+fn migrate_v0_to_v1(doc: &mut Mapping) -> Result<()> {
+  if !doc.contains_key("configs") || doc.get("configs") == Some(&Value::Null) {
+    doc.insert("configs".into(), Value::Sequence(vec![]));
+  }
+
+  for config in configs_mut(doc) {
+    if let Some(Value::Sequence(ports)) = config.get_mut("ports") {
+      for port in ports.iter_mut() {
+        if let Value::Number(number) = port {
+          *port = Value::String(number.to_string());
+        }
+      }
+    }
+
+    if let Some(Value::String(interface)) = config.get("local_interface") {
+      if interface.trim().is_empty() {
+        config.remove("local_interface");
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn migrates_unversioned_file_to_current() -> Result<()> {
+    let mut doc: Value = serde_yaml::from_str(
+      r#"
+configs:
+  - name: api
+    context: dev
+    namespace: default
+    service: svc/api
+    ports: [8080, "9090:90"]
+    local_interface: ""
+"#,
+    )?;
+
+    assert_eq!(migrate(&mut doc)?, 0);
+    assert_eq!(document_version(&doc)?, CURRENT_CONFIG_VERSION);
+
+    let config = &doc["configs"][0];
+    assert_eq!(config["ports"][0], Value::from("8080"));
+    assert_eq!(config["ports"][1], Value::from("9090:90"));
+    assert!(config.get("local_interface").is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn rejects_files_from_newer_versions() -> Result<()> {
+    let mut doc: Value = serde_yaml::from_str("version: 99\nconfigs: []\n")?;
+    assert!(migrate(&mut doc).is_err());
+    Ok(())
+  }
+}
@@ -1,11 +1,61 @@
 use crate::error::{AppError, Result};
-use crate::types::{AppConfig, PortForwardConfig, PortForwardConfigs};
+use crate::services::config_migrations::{self, CURRENT_CONFIG_VERSION};
+use crate::services::dependencies;
+use crate::services::file_lock::FileLock;
+use crate::services::history::{
+  self, ConfigHistory, SnapshotDiff, SnapshotInfo, DEFAULT_HISTORY_LIMIT,
+};
+use crate::services::profiles::{self, Profiles};
+use crate::services::template::Variables;
+use crate::services::validation;
+use crate::services::{HostKeyStore, SshCommandBuilder};
+use crate::types::{AppConfig, ConfigSource, PortForwardConfig, PortForwardConfigs};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Per-project config file, looked up from the working directory upwards
+pub const PROJECT_CONFIG_FILE: &str = ".easykpf.yaml";
+
+/// Result of loading `port-forwards.yaml` merged with the project file
+pub struct LoadedConfigs {
+  pub configs: Vec<PortForwardConfig>,
+  /// Set when the file could not be parsed and was moved aside to this path
+  pub recovered_from: Option<PathBuf>,
+  /// Names defined both in the project file and globally; the project entry wins
+  pub conflicts: Vec<String>,
+  /// Problems that did not prevent loading, e.g. an unreadable project file
+  pub warnings: Vec<String>,
+  /// `variables:` of the global and project files, project values winning
+  pub variables: Variables,
+  /// `profiles:` of the global and project files, project profiles winning
+  pub profiles: Profiles,
+}
+
+impl LoadedConfigs {
+  /// Log the conflicts and warnings, for callers that don't show them
+  pub fn log_problems(&self) {
+    for name in &self.conflicts {
+      log::warn!(
+        "Config '{}' is defined in both the project and global config; using the project entry",
+        name
+      );
+    }
+    for warning in &self.warnings {
+      log::warn!("{}", warning);
+    }
+  }
+}
 
 #[derive(Clone)]
 pub struct ConfigService {
   config_dir: PathBuf,
+  project_file: Option<PathBuf>,
+  /// Configs each writable file held when last loaded or saved, the base that
+  /// saves are compared against
+  loaded: Arc<Mutex<HashMap<PathBuf, Vec<PortForwardConfig>>>>,
 }
 
 impl ConfigService {
@@ -14,47 +64,337 @@ impl ConfigService {
       .ok_or_else(|| AppError::Config("Could not find config directory".to_string()))?
       .join("EasyKpf");
 
+    let working_dir = std::env::current_dir().ok();
+    Self::with_config_dir(config_dir, working_dir.as_deref())
+  }
+
+  /// Use an explicit config dir, looking for a project file above `working_dir`
+  pub fn with_config_dir(config_dir: PathBuf, working_dir: Option<&Path>) -> Result<Self> {
     fs::create_dir_all(&config_dir)?;
 
-    Ok(Self { config_dir })
+    let project_file = working_dir.and_then(find_project_file);
+    if let Some(ref path) = project_file {
+      log::info!("Using project config {:?}", path);
+    }
+
+    Ok(Self {
+      config_dir,
+      project_file,
+      loaded: Arc::new(Mutex::new(HashMap::new())),
+    })
   }
 
   pub fn config_dir(&self) -> &PathBuf {
     &self.config_dir
   }
 
+  pub fn project_file(&self) -> Option<&PathBuf> {
+    self.project_file.as_ref()
+  }
+
+  fn port_forwards_path(&self) -> PathBuf {
+    self.config_dir.join("port-forwards.yaml")
+  }
+
+  /// Load port forwards, migrating older files. A file that cannot be parsed is
+  /// moved aside and an empty list returned so that the UIs can still start.
   pub fn load_port_forwards(&self) -> Result<Vec<PortForwardConfig>> {
-    let config_path = self.config_dir.join("port-forwards.yaml");
+    let loaded = self.load_port_forwards_with_recovery()?;
+    loaded.log_problems();
+    Ok(loaded.configs)
+  }
 
-    if !config_path.exists() {
-      let default_configs = PortForwardConfigs { configs: vec![] };
-      self.save_port_forwards(&default_configs.configs)?;
-      return Ok(default_configs.configs);
+  pub fn load_port_forwards_with_recovery(&self) -> Result<LoadedConfigs> {
+    let config_path = self.port_forwards_path();
+    let (global, recovered_from) = self.load_global_port_forwards()?;
+
+    let mut warnings = Vec::new();
+    let mut profiles = global.profiles.clone();
+    self.remember_loaded(&config_path, &global.configs)?;
+    let (global, mut variables) =
+      with_includes(&config_path, global, &ConfigSource::Global, &mut warnings);
+
+    let project = match self.project_file {
+      Some(ref path) => match read_project_file(path) {
+        Ok(file) => {
+          profiles.extend(file.profiles.clone());
+          self.remember_loaded(path, &file.configs)?;
+          let (configs, project_variables) = with_includes(
+            path,
+            file,
+            &ConfigSource::Project(path.clone()),
+            &mut warnings,
+          );
+          variables.extend(project_variables);
+          configs
+        }
+        Err(e) => {
+          warnings.push(format!("Ignoring project config {}: {}", path.display(), e));
+          vec![]
+        }
+      },
+      None => vec![],
+    };
+
+    let (configs, conflicts) = merge_project_configs(project, global);
+    for (name, errors) in validation::validate_configs(&configs, &variables) {
+      warnings.push(format!("Config '{}' is invalid: {}", name, errors));
     }
+    warnings.extend(dependencies::check_dependencies(&configs));
+    warnings.extend(profiles::check_profiles(&profiles, &configs));
 
-    let config_content = fs::read_to_string(&config_path)?;
-    let mut configs: PortForwardConfigs = serde_yaml::from_str(&config_content)?;
+    Ok(LoadedConfigs {
+      configs,
+      recovered_from,
+      conflicts,
+      warnings,
+      variables,
+      profiles,
+    })
+  }
+
+  /// Every file the configs are loaded from: the global file, the project file and
+  /// their includes. Parent directories are canonical, matching file watcher events.
+  pub fn watched_files(&self) -> Vec<PathBuf> {
+    let top_level: Vec<PathBuf> = std::iter::once(self.port_forwards_path())
+      .chain(self.project_file.clone())
+      .collect();
 
-    // Migrate old configs to include new fields with defaults
-    for config in &mut configs.configs {
-      if config.local_interface.is_none() {
-        config.local_interface = None; // Keep as None for existing configs
+    let mut files = top_level.clone();
+    for path in top_level {
+      let Ok((file, _)) = parse_config_file(&path) else {
+        continue;
+      };
+      let mut stack = vec![canonical(&path)];
+      let included = resolve_includes(
+        &path,
+        &file.include,
+        &mut stack,
+        &mut Variables::new(),
+        &mut Vec::new(),
+      );
+      for config in included {
+        if let ConfigSource::Include(include) = config.source {
+          if !files.contains(&include) {
+            files.push(include);
+          }
+        }
       }
     }
 
-    Ok(configs.configs)
+    files
+      .into_iter()
+      .map(|path| match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => canonical(dir).join(name),
+        _ => path,
+      })
+      .collect()
   }
 
+  fn load_global_port_forwards(&self) -> Result<(PortForwardConfigs, Option<PathBuf>)> {
+    let config_path = self.port_forwards_path();
+
+    if !config_path.exists() {
+      write_config_file(&config_path, &[], &PortForwardConfigs::default())?;
+      return Ok((PortForwardConfigs::default(), None));
+    }
+
+    match read_config_file(&config_path) {
+      Ok(file) => Ok((file, None)),
+      // Syntax/shape errors are recoverable; anything else (e.g. a file from a
+      // newer version) must not be touched
+      Err(AppError::Serde(e)) => {
+        let broken_path = backup_path(&config_path, &format!("broken-{}", unix_timestamp()));
+        log::error!(
+          "Failed to parse {:?}: {}. Moving it to {:?}",
+          config_path,
+          e,
+          broken_path
+        );
+        fs::rename(&config_path, &broken_path)?;
+        write_config_file(&config_path, &[], &PortForwardConfigs::default())?;
+        Ok((PortForwardConfigs::default(), Some(broken_path)))
+      }
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Save configs back to the file each one came from. Global entries that are
+  /// shadowed by a project entry of the same name are preserved.
+  ///
+  /// Files that changed on disk since they were loaded are merged per entry;
+  /// an entry edited both here and on disk fails the save instead.
   pub fn save_port_forwards(&self, configs: &[PortForwardConfig]) -> Result<()> {
-    let config_path = self.config_dir.join("port-forwards.yaml");
-    let configs_wrapper = PortForwardConfigs {
-      configs: configs.to_vec(),
+    // Included entries are read-only and stay in the file they came from
+    let (project, mut global): (Vec<_>, Vec<_>) = configs
+      .iter()
+      .filter(|c| !c.source.is_read_only())
+      .cloned()
+      .partition(|c| matches!(c.source, ConfigSource::Project(_)));
+
+    // Serialises read-merge-write cycles of the TUI, the app and kpfctl
+    let config_path = self.port_forwards_path();
+    let _lock = FileLock::acquire(&config_path)?;
+
+    let existing_global = if config_path.exists() {
+      read_config_file(&config_path).ok()
+    } else {
+      None
     };
-    let yaml_content = serde_yaml::to_string(&configs_wrapper)?;
-    fs::write(&config_path, yaml_content)?;
+
+    let mut saved_project = Vec::new();
+    if let Some(ref project_path) = self.project_file {
+      let project: Vec<_> = project
+        .into_iter()
+        .filter(|c| c.source == ConfigSource::Project(project_path.clone()))
+        .collect();
+
+      // A project file we failed to load is left alone rather than overwritten.
+      // Otherwise these are its entries from before and after this save.
+      let project_configs = match read_project_file(project_path) {
+        Ok(existing) => {
+          let mut project = self.merge_with_disk(project_path, project, &existing.configs)?;
+          for config in &mut project {
+            config.source = ConfigSource::Project(project_path.clone());
+          }
+          // Avoid reformatting a hand-written, version-controlled file needlessly
+          if strip_sources(&existing.configs) != strip_sources(&project) {
+            write_config_file(project_path, &project, &existing)?;
+          }
+          self.remember_loaded(project_path, &project)?;
+          saved_project.clone_from(&project);
+          [existing.configs, project].concat()
+        }
+        Err(e) => {
+          log::warn!("Not saving project config {:?}: {}", project_path, e);
+          vec![]
+        }
+      };
+
+      // Global entries hidden behind a project entry (before or after this save)
+      // never reach the UI, so carry them over from disk
+      if let Some(ref existing) = existing_global {
+        let shadowed: Vec<_> = existing
+          .configs
+          .iter()
+          .filter(|c| {
+            let in_project = project_configs.iter().any(|p| p.name == c.name);
+            in_project && !global.iter().any(|g| g.name == c.name)
+          })
+          .cloned()
+          .collect();
+        global.extend(shadowed);
+      }
+    }
+
+    let existing_global = existing_global.unwrap_or_default();
+    let global = self.merge_with_disk(&config_path, global, &existing_global.configs)?;
+    write_config_file(&config_path, &global, &existing_global)?;
+    self.remember_loaded(&config_path, &global)?;
+
+    // Snapshots are a safety net; failing to keep one doesn't fail the save
+    if let Err(e) = self.history().record(&[saved_project, global].concat()) {
+      log::warn!("Failed to keep a config snapshot: {}", e);
+    }
     Ok(())
   }
 
+  /// Snapshots of the writable configs, taken on every save
+  pub fn history(&self) -> ConfigHistory {
+    let limit = self
+      .load_app_config()
+      .ok()
+      .and_then(|config| config.history_limit)
+      .unwrap_or(DEFAULT_HISTORY_LIMIT);
+    ConfigHistory::new(self.config_dir.join("history"), limit)
+  }
+
+  /// Newest first
+  pub fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+    self.history().list()
+  }
+
+  /// What restoring snapshot `id` would change in the current configs
+  pub fn diff_snapshot(&self, id: &str) -> Result<SnapshotDiff> {
+    let snapshot = self.history().load(id)?;
+    Ok(history::diff(&self.writable_configs()?, &snapshot.configs))
+  }
+
+  /// Replace the writable configs with snapshot `id`. The current configs are
+  /// snapshotted first (they may be hand edits), so the restore can be undone.
+  ///
+  /// History is shared by all projects; a snapshot holding another project's
+  /// entries is refused, as saving here would silently drop them.
+  pub fn restore_snapshot(&self, id: &str) -> Result<()> {
+    let history = self.history();
+    let snapshot = history.load(id)?;
+    let foreign = snapshot.configs.iter().find_map(|c| match c.source {
+      ConfigSource::Project(ref path) if self.project_file.as_ref() != Some(path) => Some(path),
+      _ => None,
+    });
+    if let Some(path) = foreign {
+      return Err(AppError::InvalidInput(format!(
+        "Snapshot {} includes entries of the project {}; restore it from that project",
+        id,
+        path.display()
+      )));
+    }
+    // Loading also makes the files on disk the base, so the restore replaces
+    // them rather than merging
+    history.record(&self.writable_configs()?)?;
+    self.save_port_forwards(&snapshot.configs)
+  }
+
+  fn writable_configs(&self) -> Result<Vec<PortForwardConfig>> {
+    Ok(
+      self
+        .load_port_forwards_with_recovery()?
+        .configs
+        .into_iter()
+        .filter(|c| !c.source.is_read_only())
+        .collect(),
+    )
+  }
+
+  fn remember_loaded(&self, path: &Path, configs: &[PortForwardConfig]) -> Result<()> {
+    self
+      .loaded
+      .lock()
+      .map_err(|_| AppError::System("Lock poisoned".to_string()))?
+      .insert(path.to_path_buf(), strip_sources(configs));
+    Ok(())
+  }
+
+  /// Compare-and-swap `ours` against what `path` held when it was last loaded.
+  /// If `theirs` (the file now) differs, changes made on disk meanwhile are merged in.
+  fn merge_with_disk(
+    &self,
+    path: &Path,
+    ours: Vec<PortForwardConfig>,
+    theirs: &[PortForwardConfig],
+  ) -> Result<Vec<PortForwardConfig>> {
+    let base = self
+      .loaded
+      .lock()
+      .map_err(|_| AppError::System("Lock poisoned".to_string()))?
+      .get(path)
+      .cloned();
+    let theirs = strip_sources(theirs);
+    match base {
+      Some(base) if base != theirs => {
+        log::info!("{:?} changed on disk since it was loaded; merging", path);
+        merge_configs(&base, &strip_sources(&ours), &theirs).map_err(|conflicts| {
+          AppError::Config(format!(
+            "{} was changed elsewhere; conflicting edits to {}. Reload and try again",
+            path.display(),
+            conflicts.join(", ")
+          ))
+        })
+      }
+      _ => Ok(ours),
+    }
+  }
+
   pub fn load_app_config(&self) -> Result<AppConfig> {
     let config_path = self.config_dir.join("app-config.yaml");
 
@@ -62,6 +402,8 @@ impl ConfigService {
       let default_config = AppConfig {
         kubectl_path: None,
         kubeconfig_path: None,
+        history_limit: None,
+        ssh_host_key_policy: None,
       };
       self.save_app_config(&default_config)?;
       return Ok(default_config);
@@ -92,6 +434,27 @@ impl ConfigService {
     self.save_app_config(&config)
   }
 
+  /// EasyKpf's own known_hosts, kept apart from `~/.ssh/known_hosts`
+  pub fn known_hosts_path(&self) -> PathBuf {
+    self.config_dir.join("known_hosts")
+  }
+
+  pub fn host_keys(&self) -> HostKeyStore {
+    HostKeyStore::new(self.known_hosts_path())
+  }
+
+  /// SSH command builder verifying host keys against `known_hosts_path`, with
+  /// multiplexed connections' control sockets in the config dir
+  pub fn ssh_command_builder(&self) -> SshCommandBuilder {
+    let policy = self
+      .load_app_config()
+      .ok()
+      .and_then(|config| config.ssh_host_key_policy)
+      .unwrap_or_default();
+    SshCommandBuilder::with_known_hosts(self.known_hosts_path(), policy)
+      .with_control_dir(self.config_dir.join("ssh"))
+  }
+
   pub fn load_kubeconfig_path(&self) -> Result<Option<String>> {
     let config = self.load_app_config()?;
     Ok(config.kubeconfig_path)
@@ -103,3 +466,575 @@ impl ConfigService {
     self.save_app_config(&config)
   }
 }
+
+fn find_project_file(start: &Path) -> Option<PathBuf> {
+  start
+    .ancestors()
+    .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+    .find(|path| path.is_file())
+}
+
+/// Project entries first, then global entries not shadowed by a project entry
+fn merge_project_configs(
+  project: Vec<PortForwardConfig>,
+  global: Vec<PortForwardConfig>,
+) -> (Vec<PortForwardConfig>, Vec<String>) {
+  let mut conflicts = Vec::new();
+  let mut merged = project;
+
+  for config in global {
+    if merged.iter().any(|c| c.name == config.name) {
+      conflicts.push(config.name);
+    } else {
+      merged.push(config);
+    }
+  }
+
+  (merged, conflicts)
+}
+
+fn strip_sources(configs: &[PortForwardConfig]) -> Vec<PortForwardConfig> {
+  configs
+    .iter()
+    .cloned()
+    .map(|mut c| {
+      c.source = ConfigSource::Global;
+      c
+    })
+    .collect()
+}
+
+/// Tag a file's own entries with `source` and layer them, and its variables, over
+/// its includes
+fn with_includes(
+  path: &Path,
+  file: PortForwardConfigs,
+  source: &ConfigSource,
+  warnings: &mut Vec<String>,
+) -> (Vec<PortForwardConfig>, Variables) {
+  let mut stack = vec![canonical(path)];
+  let mut variables = Variables::new();
+  let included = resolve_includes(path, &file.include, &mut stack, &mut variables, warnings);
+  variables.extend(file.variables);
+  let own = file
+    .configs
+    .into_iter()
+    .map(|mut config| {
+      config.source = source.clone();
+      config
+    })
+    .collect();
+  (layer_configs(own, included), variables)
+}
+
+/// Load the entries of every file in `include`, recursively, adding their
+/// variables to `variables` unless already set. Missing files, parse errors and
+/// cycles are reported as warnings and skipped.
+fn resolve_includes(
+  path: &Path,
+  include: &[String],
+  stack: &mut Vec<PathBuf>,
+  variables: &mut Variables,
+  warnings: &mut Vec<String>,
+) -> Vec<PortForwardConfig> {
+  let base = path.parent().unwrap_or(Path::new("."));
+  let mut resolved = Vec::new();
+
+  for entry in include {
+    let include_path = include_path(base, entry);
+    let key = canonical(&include_path);
+
+    if stack.contains(&key) {
+      let chain: Vec<String> = stack
+        .iter()
+        .chain(std::iter::once(&key))
+        .map(|p| p.display().to_string())
+        .collect();
+      warnings.push(format!("Include cycle: {}", chain.join(" -> ")));
+      continue;
+    }
+
+    // Shared fragments are often version-controlled elsewhere; never rewrite them
+    let file = match parse_config_file(&include_path) {
+      Ok((file, _)) => file,
+      Err(e) => {
+        warnings.push(format!(
+          "Ignoring include {} from {}: {}",
+          include_path.display(),
+          path.display(),
+          e
+        ));
+        continue;
+      }
+    };
+
+    // Like entries, a file's variables win over those of its own includes
+    for (name, value) in file.variables {
+      variables.entry(name).or_insert(value);
+    }
+    stack.push(key);
+    let nested = resolve_includes(&include_path, &file.include, stack, variables, warnings);
+    stack.pop();
+
+    let own = file
+      .configs
+      .into_iter()
+      .map(|mut config| {
+        config.source = ConfigSource::Include(include_path.clone());
+        config
+      })
+      .collect();
+    let layered = layer_configs(own, nested);
+    resolved = layer_configs(resolved, layered);
+  }
+
+  resolved
+}
+
+/// `top` entries win over `bottom` entries with the same name
+fn layer_configs(
+  mut top: Vec<PortForwardConfig>,
+  bottom: Vec<PortForwardConfig>,
+) -> Vec<PortForwardConfig> {
+  for config in bottom {
+    if !top.iter().any(|c| c.name == config.name) {
+      top.push(config);
+    }
+  }
+  top
+}
+
+/// Include paths may be absolute, start with `~/` or be relative to the including file
+fn include_path(base: &Path, entry: &str) -> PathBuf {
+  if let Some(rest) = entry.strip_prefix("~/") {
+    if let Some(home) = dirs::home_dir() {
+      return home.join(rest);
+    }
+  }
+  base.join(entry)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Parse and migrate a config file in memory, returning the version it had on disk
+fn parse_config_file(path: &Path) -> Result<(PortForwardConfigs, u32)> {
+  let content = fs::read_to_string(path)?;
+  let mut doc: serde_yaml::Value = serde_yaml::from_str(&content)?;
+  if doc.is_null() {
+    return Ok((PortForwardConfigs::default(), CURRENT_CONFIG_VERSION));
+  }
+
+  let from_version = config_migrations::migrate(&mut doc)?;
+  Ok((serde_yaml::from_value(doc)?, from_version))
+}
+
+/// Parse and, if needed, migrate a config file. Migrated files are backed up
+/// before being rewritten in the current schema.
+fn read_config_file(path: &Path) -> Result<PortForwardConfigs> {
+  let (file, from_version) = parse_config_file(path)?;
+
+  if from_version < CURRENT_CONFIG_VERSION {
+    let backup = backup_path(path, &format!("v{}.bak", from_version));
+    if !backup.exists() {
+      fs::copy(path, &backup)?;
+      log::info!("Backed up pre-migration config to {:?}", backup);
+    }
+    write_config_file(path, &file.configs, &file)?;
+  }
+
+  Ok(file)
+}
+
+/// Parse a project file, migrating it in memory only. Project files are usually
+/// hand-written and version-controlled, so they are rewritten only by a save that
+/// changes their entries.
+fn read_project_file(path: &Path) -> Result<PortForwardConfigs> {
+  Ok(parse_config_file(path)?.0)
+}
+
+/// Atomic write: a crash mid-write must never leave a truncated config behind
+/// `header` supplies the file's `include:`, `variables:` and `profiles:`, which the
+/// UIs never edit.
+fn write_config_file(
+  path: &Path,
+  configs: &[PortForwardConfig],
+  header: &PortForwardConfigs,
+) -> Result<()> {
+  let configs_wrapper = PortForwardConfigs {
+    version: CURRENT_CONFIG_VERSION,
+    include: header.include.clone(),
+    variables: header.variables.clone(),
+    profiles: header.profiles.clone(),
+    configs: strip_sources(configs),
+  };
+  let yaml_content = serde_yaml::to_string(&configs_wrapper)?;
+
+  let temp_path = backup_path(path, "tmp");
+  fs::write(&temp_path, yaml_content)?;
+  fs::rename(&temp_path, path)?;
+  Ok(())
+}
+
+/// Three-way merge by name of `ours` and `theirs`, both edited from `base`. An
+/// entry changed differently on both sides (or changed on one side and deleted
+/// on the other) is a conflict.
+fn merge_configs(
+  base: &[PortForwardConfig],
+  ours: &[PortForwardConfig],
+  theirs: &[PortForwardConfig],
+) -> std::result::Result<Vec<PortForwardConfig>, Vec<String>> {
+  let find = |configs: &[PortForwardConfig], name: &str| -> Option<PortForwardConfig> {
+    configs.iter().find(|c| c.name == name).cloned()
+  };
+  let mut merged = Vec::new();
+  let mut conflicts = Vec::new();
+
+  for our in ours {
+    let original = find(base, &our.name);
+    let their = find(theirs, &our.name);
+    if original.as_ref() == Some(our) {
+      // Untouched here: whatever happened on disk wins, including a delete
+      merged.extend(their);
+    } else if their == original || their.as_ref() == Some(our) {
+      merged.push(our.clone());
+    } else {
+      conflicts.push(our.name.clone());
+    }
+  }
+
+  for their in theirs.iter().filter(|t| find(ours, &t.name).is_none()) {
+    match find(base, &their.name) {
+      // Added on disk
+      None => merged.push(their.clone()),
+      // Deleted here and edited on disk
+      Some(original) if &original != their => conflicts.push(their.name.clone()),
+      // Deleted here
+      Some(_) => {}
+    }
+  }
+
+  if conflicts.is_empty() {
+    Ok(merged)
+  } else {
+    Err(conflicts)
+  }
+}
+
+/// `port-forwards.yaml` -> `port-forwards.yaml.<suffix>`
+fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = path.as_os_str().to_owned();
+  name.push(format!(".{}", suffix));
+  PathBuf::from(name)
+}
+
+fn unix_timestamp() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("easykpf-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+  }
+
+  fn including(include: &[String]) -> PortForwardConfigs {
+    PortForwardConfigs {
+      include: include.to_vec(),
+      ..Default::default()
+    }
+  }
+
+  fn config(name: &str, service: &str) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      context: "dev".to_string(),
+      namespace: "default".to_string(),
+      service: service.to_string(),
+      ports: vec!["8080:80".to_string()],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn merges_project_file_and_keeps_shadowed_global_entries() -> Result<()> {
+    let root = temp_dir("project-merge")?;
+    let repo = root.join("repo");
+    let nested = repo.join("services").join("api");
+    fs::create_dir_all(&nested)?;
+
+    let project_path = repo.join(PROJECT_CONFIG_FILE);
+    let project_header = PortForwardConfigs {
+      variables: [("NS".to_string(), "team".to_string())].into(),
+      ..Default::default()
+    };
+    write_config_file(
+      &project_path,
+      &[config("api", "svc/api-project")],
+      &project_header,
+    )?;
+
+    let service = ConfigService::with_config_dir(root.join("global"), Some(&nested))?;
+    write_config_file(
+      &service.port_forwards_path(),
+      &[config("db", "svc/db"), config("api", "svc/api-global")],
+      &PortForwardConfigs::default(),
+    )?;
+
+    let loaded = service.load_port_forwards_with_recovery()?;
+    let names: Vec<&str> = loaded.configs.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["api", "db"]);
+    assert_eq!(loaded.conflicts, vec!["api".to_string()]);
+    assert_eq!(loaded.variables, project_header.variables);
+    assert_eq!(
+      loaded.configs[0].source,
+      ConfigSource::Project(project_path.clone())
+    );
+
+    // Dropping the project entry must not lose the global one it shadowed
+    let remaining: Vec<_> = loaded
+      .configs
+      .into_iter()
+      .filter(|c| c.source.is_global())
+      .collect();
+    service.save_port_forwards(&remaining)?;
+
+    let project = read_config_file(&project_path)?;
+    assert!(project.configs.is_empty());
+    assert_eq!(project.variables, project_header.variables);
+    let global = read_config_file(&service.port_forwards_path())?;
+    assert!(global.configs.iter().any(|c| c.service == "svc/api-global"));
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+  }
+
+  #[test]
+  fn resolves_includes_with_local_overrides_and_cycles() -> Result<()> {
+    let root = temp_dir("includes")?;
+    let shared = root.join("shared");
+    fs::create_dir_all(&shared)?;
+
+    // team.yaml includes base.yaml, which includes team.yaml back
+    write_config_file(
+      &shared.join("team.yaml"),
+      &[config("api", "svc/api-team"), config("cache", "svc/redis")],
+      &including(&["base.yaml".to_string()]),
+    )?;
+    write_config_file(
+      &shared.join("base.yaml"),
+      &[
+        config("cache", "svc/memcached"),
+        config("queue", "svc/rabbit"),
+      ],
+      &including(&["team.yaml".to_string()]),
+    )?;
+
+    let service = ConfigService::with_config_dir(root.join("global"), None)?;
+    let include = vec![shared.join("team.yaml").display().to_string()];
+    write_config_file(
+      &service.port_forwards_path(),
+      &[config("api", "svc/api-local")],
+      &including(&include),
+    )?;
+
+    let loaded = service.load_port_forwards_with_recovery()?;
+    let services: Vec<&str> = loaded.configs.iter().map(|c| c.service.as_str()).collect();
+    assert_eq!(services, vec!["svc/api-local", "svc/redis", "svc/rabbit"]);
+    assert!(loaded.configs[0].source.is_global());
+    assert!(loaded.configs[1].source.is_read_only());
+    assert!(loaded
+      .warnings
+      .iter()
+      .any(|w| w.starts_with("Include cycle")));
+
+    // Saving keeps the directive and never copies included entries locally
+    service.save_port_forwards(&loaded.configs)?;
+    let global = read_config_file(&service.port_forwards_path())?;
+    assert_eq!(global.include, include);
+    assert_eq!(global.configs.len(), 1);
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+  }
+
+  #[test]
+  fn layers_included_variables_and_survives_a_broken_project_file() -> Result<()> {
+    let root = temp_dir("include-variables")?;
+    let repo = root.join("repo");
+    fs::create_dir_all(&repo)?;
+    fs::write(repo.join(PROJECT_CONFIG_FILE), "configs: [unclosed")?;
+
+    let shared = root.join("shared.yaml");
+    let shared_header = PortForwardConfigs {
+      variables: [
+        ("NS".to_string(), "shared".to_string()),
+        ("CTX".to_string(), "shared".to_string()),
+      ]
+      .into(),
+      ..Default::default()
+    };
+    write_config_file(&shared, &[], &shared_header)?;
+
+    let service = ConfigService::with_config_dir(root.join("global"), Some(&repo))?;
+    let global_header = PortForwardConfigs {
+      include: vec![shared.display().to_string()],
+      variables: [("NS".to_string(), "global".to_string())].into(),
+      ..Default::default()
+    };
+    write_config_file(&service.port_forwards_path(), &[], &global_header)?;
+
+    let loaded = service.load_port_forwards_with_recovery()?;
+    assert_eq!(
+      loaded.variables.get("NS").map(String::as_str),
+      Some("global")
+    );
+    assert_eq!(
+      loaded.variables.get("CTX").map(String::as_str),
+      Some("shared")
+    );
+    assert!(loaded
+      .warnings
+      .iter()
+      .any(|w| w.starts_with("Ignoring project config")));
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+  }
+
+  #[test]
+  fn moves_malformed_config_files_aside() -> Result<()> {
+    let cases = [
+      ("syntax", "configs: [unclosed"),
+      ("scalar", "just a string\n"),
+      ("list", "- name: api\n- name: db\n"),
+      ("version", "version: abc\nconfigs: []\n"),
+    ];
+    for (case, content) in cases {
+      let dir = temp_dir(&format!("malformed-{}", case))?;
+      let service = ConfigService::with_config_dir(dir.clone(), None)?;
+      fs::write(service.port_forwards_path(), content)?;
+
+      let loaded = service.load_port_forwards_with_recovery()?;
+      assert!(loaded.configs.is_empty(), "{}", case);
+      let broken = loaded.recovered_from.unwrap_or_default();
+      assert_eq!(fs::read_to_string(&broken)?, content, "{}", case);
+
+      let _ = fs::remove_dir_all(&dir);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn leaves_config_files_from_newer_versions_alone() -> Result<()> {
+    let dir = temp_dir("newer-version")?;
+    let service = ConfigService::with_config_dir(dir.clone(), None)?;
+    fs::write(service.port_forwards_path(), "version: 99\nconfigs: []\n")?;
+
+    assert!(service.load_port_forwards_with_recovery().is_err());
+    assert!(service.port_forwards_path().exists());
+
+    let _ = fs::remove_dir_all(&dir);
+    Ok(())
+  }
+
+  #[test]
+  fn loads_an_unversioned_project_file_without_rewriting_it() -> Result<()> {
+    let root = temp_dir("project-v0")?;
+    let repo = root.join("repo");
+    fs::create_dir_all(&repo)?;
+    let project_path = repo.join(PROJECT_CONFIG_FILE);
+    let hand_written = "configs:\n  - name: api\n    context: dev\n    namespace: default\n    service: svc/api\n    ports: [8080]\n";
+    fs::write(&project_path, hand_written)?;
+
+    let service = ConfigService::with_config_dir(root.join("global"), Some(&repo))?;
+    let loaded = service.load_port_forwards_with_recovery()?;
+    assert_eq!(loaded.configs[0].ports, vec!["8080".to_string()]);
+
+    // Saving without touching the project's entries leaves the file alone too
+    service.save_port_forwards(&loaded.configs)?;
+    assert_eq!(fs::read_to_string(&project_path)?, hand_written);
+    let names: Vec<_> = fs::read_dir(&repo)?
+      .filter_map(std::result::Result::ok)
+      .map(|entry| entry.file_name())
+      .collect();
+    assert_eq!(names, vec![std::ffi::OsString::from(PROJECT_CONFIG_FILE)]);
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+  }
+
+  #[test]
+  fn refuses_to_restore_another_projects_snapshot() -> Result<()> {
+    let root = temp_dir("restore-project")?;
+    let repo = root.join("repo");
+    fs::create_dir_all(&repo)?;
+    let project_path = repo.join(PROJECT_CONFIG_FILE);
+    write_config_file(&project_path, &[], &PortForwardConfigs::default())?;
+
+    let in_project = ConfigService::with_config_dir(root.join("global"), Some(&repo))?;
+    in_project.load_port_forwards()?;
+    let api = PortForwardConfig {
+      source: ConfigSource::Project(project_path),
+      ..config("api", "svc/api")
+    };
+    in_project.save_port_forwards(&[api, config("db", "svc/db")])?;
+    let id = in_project.list_snapshots()?[0].id.clone();
+
+    let elsewhere = ConfigService::with_config_dir(root.join("global"), None)?;
+    assert!(matches!(
+      elsewhere.restore_snapshot(&id),
+      Err(AppError::InvalidInput(_))
+    ));
+    in_project.restore_snapshot(&id)?;
+
+    let _ = fs::remove_dir_all(&root);
+    Ok(())
+  }
+
+  #[test]
+  fn merges_concurrent_saves_and_rejects_conflicting_edits() -> Result<()> {
+    let dir = temp_dir("concurrent-save")?;
+    let tui = ConfigService::with_config_dir(dir.clone(), None)?;
+    let app = ConfigService::with_config_dir(dir.clone(), None)?;
+    tui.save_port_forwards(&[config("api", "api"), config("db", "db")])?;
+
+    let mut from_tui = tui.load_port_forwards()?;
+    let mut from_app = app.load_port_forwards()?;
+    from_app.push(config("web", "web"));
+    app.save_port_forwards(&from_app)?;
+
+    // The TUI still holds the configs from before the app added `web`
+    from_tui[0].service = "api-v2".to_string();
+    tui.save_port_forwards(&from_tui)?;
+    let names: Vec<_> = tui
+      .load_port_forwards()?
+      .into_iter()
+      .map(|c| c.name)
+      .collect();
+    assert_eq!(names, vec!["api", "db", "web"]);
+
+    let mut from_tui = tui.load_port_forwards()?;
+    let mut from_app = app.load_port_forwards()?;
+    from_app[1].service = "postgres".to_string();
+    app.save_port_forwards(&from_app)?;
+    from_tui[1].service = "mysql".to_string();
+    assert!(matches!(
+      tui.save_port_forwards(&from_tui),
+      Err(AppError::Config(_))
+    ));
+    assert_eq!(tui.load_port_forwards()?[1].service, "postgres");
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
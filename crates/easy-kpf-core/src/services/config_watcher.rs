@@ -0,0 +1,138 @@
+use crate::error::{AppError, Result};
+use crate::services::ConfigService;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, PoisonError, Weak};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// One save is several events (temp file, rename); wait this long for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Published after a watched config file changed on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigsChanged;
+
+/// Watches `port-forwards.yaml`, the project file and their includes.
+///
+/// Directories are watched rather than files: atomic saves replace the file, which
+/// would silently end a watch on the old inode.
+pub struct ConfigWatcher {
+  sender: broadcast::Sender<ConfigsChanged>,
+  _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+impl ConfigWatcher {
+  pub fn start(config_service: ConfigService) -> Result<Self> {
+    let (sender, _) = broadcast::channel(16);
+    let (raw_tx, raw_rx) = mpsc::channel::<notify::Result<Event>>();
+
+    let watcher = notify::recommended_watcher(move |event| {
+      let _ = raw_tx.send(event);
+    })
+    .map_err(|e| watch_error(&e))?;
+    let watcher = Arc::new(Mutex::new(watcher));
+
+    let mut files = config_service.watched_files();
+    let mut watched_dirs = Vec::new();
+    watch_dirs(&watcher, &files, &mut watched_dirs)?;
+
+    let events = sender.clone();
+    let weak: Weak<Mutex<RecommendedWatcher>> = Arc::downgrade(&watcher);
+    std::thread::spawn(move || {
+      // Ends once the watcher is dropped and its callback (the only sender) with it
+      while let Ok(event) = raw_rx.recv() {
+        let mut relevant = is_relevant(&event, &files);
+        while let Ok(event) = raw_rx.recv_timeout(DEBOUNCE) {
+          relevant |= is_relevant(&event, &files);
+        }
+        if !relevant {
+          continue;
+        }
+
+        // The change may have added or removed includes
+        files = config_service.watched_files();
+        if let Some(watcher) = weak.upgrade() {
+          if let Err(e) = watch_dirs(&watcher, &files, &mut watched_dirs) {
+            log::warn!("{}", e);
+          }
+        }
+        log::debug!("Config files changed on disk");
+        let _ = events.send(ConfigsChanged);
+      }
+    });
+
+    Ok(Self {
+      sender,
+      _watcher: watcher,
+    })
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<ConfigsChanged> {
+    self.sender.subscribe()
+  }
+}
+
+/// Start watching the directories of `files` not watched yet
+fn watch_dirs(
+  watcher: &Mutex<RecommendedWatcher>,
+  files: &[PathBuf],
+  watched: &mut Vec<PathBuf>,
+) -> Result<()> {
+  let mut watcher = watcher
+    .lock()
+    .map_err(|_: PoisonError<_>| AppError::System("Lock poisoned".to_string()))?;
+  for dir in files.iter().filter_map(|f| f.parent()) {
+    if watched.iter().any(|w| w == dir) || !dir.is_dir() {
+      continue;
+    }
+    watcher
+      .watch(dir, RecursiveMode::NonRecursive)
+      .map_err(|e| watch_error(&e))?;
+    watched.push(dir.to_path_buf());
+  }
+  Ok(())
+}
+
+fn is_relevant(event: &notify::Result<Event>, files: &[PathBuf]) -> bool {
+  let Ok(event) = event else {
+    return false;
+  };
+  !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|path| files.contains(path))
+}
+
+fn watch_error(e: &notify::Error) -> AppError {
+  AppError::System(format!("Failed to watch config files: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+  use std::time::Instant;
+
+  #[test]
+  fn publishes_changes_to_the_config_file() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("easykpf-watch-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    let service = ConfigService::with_config_dir(dir.clone(), None)?;
+    service.save_port_forwards(&[])?;
+
+    let watcher = ConfigWatcher::start(service.clone())?;
+    let mut events = watcher.subscribe();
+    fs::write(dir.join("unrelated.txt"), "x")?;
+    service.save_port_forwards(&[crate::types::PortForwardConfig {
+      name: "api".to_string(),
+      ..Default::default()
+    }])?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while events.try_recv().is_err() {
+      assert!(Instant::now() < deadline, "no change event");
+      std::thread::sleep(Duration::from_millis(20));
+    }
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,239 @@
+use crate::error::{AppError, Result};
+use crate::services::validation::parse_interface;
+use crate::services::{KubectlCommandBuilder, ProcessManager};
+use crate::traits::{CommandExecutor, ProcessEvent, ProcessHandle};
+use crate::types::PortForwardConfig;
+use regex::Regex;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long a command gets to print its ready pattern; cloud CLIs may log in first
+pub const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Program, args and env for `config`'s command, with placeholders filled in
+pub fn build_command(config: &PortForwardConfig) -> (String, Vec<String>, Vec<(String, String)>) {
+  let fill = |value: &str| fill_placeholders(value, config);
+  let mut env: Vec<(String, String)> = config
+    .command
+    .env
+    .iter()
+    .map(|(key, value)| (key.clone(), fill(value)))
+    .collect();
+
+  // The tools and their credential helpers live on the user's shell PATH, which
+  // apps launched from Finder don't inherit
+  let path = match env.iter().find(|(key, _)| key == "PATH") {
+    Some((_, path)) => path.clone(),
+    None => {
+      let path = KubectlCommandBuilder::build_path_env();
+      env.push(("PATH".to_string(), path.clone()));
+      path
+    }
+  };
+
+  let program = resolve_program(&fill(&config.command.program), &path);
+  let args = config.command.args.iter().map(|arg| fill(arg)).collect();
+  (program, args, env)
+}
+
+/// Spawn `config`'s command through `executor`. With a ready pattern this returns
+/// once an output line matches it, and fails (stopping the command) if it exits
+/// or no line matches within `READY_TIMEOUT`. Lines read while waiting are
+/// replayed on the returned receiver.
+pub async fn spawn<E: CommandExecutor>(
+  executor: &E,
+  config: &PortForwardConfig,
+) -> Result<(ProcessHandle, mpsc::Receiver<ProcessEvent>)> {
+  let pattern = config
+    .command
+    .ready_pattern
+    .as_deref()
+    .map(Regex::new)
+    .transpose()
+    .map_err(|e| AppError::InvalidInput(format!("Invalid ready pattern: {}", e)))?;
+
+  let (program, args, env) = build_command(config);
+  let (handle, events) = executor.spawn(&program, &args, &env).await?;
+  let Some(pattern) = pattern else {
+    return Ok((handle, events));
+  };
+
+  let (seen, mut events) = match wait_until_ready(events, &pattern, READY_TIMEOUT).await {
+    Ok(ready) => ready,
+    Err(e) => {
+      let _ = ProcessManager::kill_process(handle.pid);
+      return Err(AppError::PortForward(format!("{}: {}", config.name, e)));
+    }
+  };
+  let (tx, rx) = mpsc::channel(100);
+  executor.spawn_task(async move {
+    for event in seen {
+      let _ = tx.send(event).await;
+    }
+    while let Some(event) = events.recv().await {
+      if tx.send(event).await.is_err() {
+        break;
+      }
+    }
+  });
+  Ok((handle, rx))
+}
+
+/// Read `events` until a line matches `pattern`. Returns the events read so far
+/// and the rest of the stream.
+pub(crate) async fn wait_until_ready(
+  mut events: mpsc::Receiver<ProcessEvent>,
+  pattern: &Regex,
+  timeout: Duration,
+) -> Result<(Vec<ProcessEvent>, mpsc::Receiver<ProcessEvent>)> {
+  let deadline = tokio::time::Instant::now() + timeout;
+  let mut seen = Vec::new();
+  loop {
+    let event = tokio::time::timeout_at(deadline, events.recv())
+      .await
+      .map_err(|_| {
+        AppError::PortForward(format!(
+          "no output matched the ready pattern after {}s",
+          timeout.as_secs()
+        ))
+      })?;
+    let ready = match &event {
+      Some(ProcessEvent::Stdout(line) | ProcessEvent::Stderr(line)) => {
+        pattern.is_match(&String::from_utf8_lossy(line))
+      }
+      Some(ProcessEvent::Error(_)) => false,
+      Some(ProcessEvent::Terminated { .. }) | None => {
+        return Err(AppError::PortForward(format!(
+          "exited before it was ready: {}",
+          last_line(&seen)
+        )))
+      }
+    };
+    seen.extend(event);
+    if ready {
+      return Ok((seen, events));
+    }
+  }
+}
+
+fn last_line(events: &[ProcessEvent]) -> String {
+  events
+    .iter()
+    .rev()
+    .find_map(|event| match event {
+      ProcessEvent::Stdout(line) | ProcessEvent::Stderr(line) => {
+        Some(String::from_utf8_lossy(line).to_string())
+      }
+      ProcessEvent::Error(message) => Some(message.clone()),
+      ProcessEvent::Terminated { .. } => None,
+    })
+    .unwrap_or_else(|| "no output".to_string())
+}
+
+/// Replace `{local_port}` and `{remote_port}` with the first port mapping's and
+/// `{interface}` with the local interface's address
+pub(crate) fn fill_placeholders(value: &str, config: &PortForwardConfig) -> String {
+  let mapping = config.ports.first().map_or("", String::as_str);
+  let (local, remote) = mapping.split_once(':').unwrap_or((mapping, mapping));
+  let interface = config
+    .local_interface
+    .as_deref()
+    .and_then(parse_interface)
+    .map_or_else(|| "127.0.0.1".to_string(), |(ip, _)| ip.to_string());
+  value
+    .replace("{local_port}", local)
+    .replace("{remote_port}", remote)
+    .replace("{interface}", &interface)
+}
+
+/// Look a bare program name up on `path`, since the command is started with the
+/// app's own PATH
+fn resolve_program(program: &str, path: &str) -> String {
+  if program.contains(std::path::MAIN_SEPARATOR) {
+    return program.to_string();
+  }
+  std::env::split_paths(path)
+    .map(|dir| dir.join(program))
+    .find(|candidate| candidate.is_file())
+    .and_then(|candidate| candidate.to_str().map(str::to_string))
+    .unwrap_or_else(|| program.to_string())
+}
+
+/// Program name as it shows up in `ps`, for detection
+pub(crate) fn program_name(program: &str) -> &str {
+  Path::new(program)
+    .file_name()
+    .and_then(|name| name.to_str())
+    .unwrap_or(program)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::{CommandSettings, ForwardType};
+  use std::collections::BTreeMap;
+
+  /// A finished stream of `events`, as if the command had printed them
+  fn output(events: Vec<ProcessEvent>) -> mpsc::Receiver<ProcessEvent> {
+    let (tx, rx) = mpsc::channel(events.len() + 1);
+    for event in events {
+      let _ = tx.try_send(event);
+    }
+    rx
+  }
+
+  #[tokio::test]
+  async fn fills_placeholders_and_waits_for_the_ready_line() -> Result<()> {
+    let config = PortForwardConfig {
+      name: "orders-db".to_string(),
+      ports: vec!["15432:5432".to_string()],
+      local_interface: Some("127.0.0.2".to_string()),
+      forward_type: ForwardType::Command,
+      command: CommandSettings {
+        program: "/usr/local/bin/cloud-sql-proxy".to_string(),
+        args: vec![
+          "--address={interface}".to_string(),
+          "--port={local_port}".to_string(),
+          r#"{"portNumber":["{remote_port}"]}"#.to_string(),
+        ],
+        env: BTreeMap::from([("PATH".to_string(), "/usr/bin".to_string())]),
+        ready_pattern: Some("ready for new connections".to_string()),
+      },
+      ..Default::default()
+    };
+    let (program, args, env) = build_command(&config);
+    assert_eq!(program, "/usr/local/bin/cloud-sql-proxy");
+    assert_eq!(
+      args,
+      [
+        "--address=127.0.0.2",
+        "--port=15432",
+        r#"{"portNumber":["5432"]}"#
+      ]
+    );
+    assert_eq!(env, [("PATH".to_string(), "/usr/bin".to_string())]);
+    assert_eq!(program_name(&program), "cloud-sql-proxy");
+
+    let pattern =
+      Regex::new("ready for new connections").map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    let started = output(vec![
+      ProcessEvent::Stderr(b"Authorizing with ADC".to_vec()),
+      ProcessEvent::Stdout(
+        b"The proxy has started successfully and is ready for new connections!".to_vec(),
+      ),
+    ]);
+    let (seen, _rest) = wait_until_ready(started, &pattern, Duration::from_secs(1)).await?;
+    assert_eq!(seen.len(), 2);
+
+    let failed = output(vec![
+      ProcessEvent::Stderr(b"invalid instance name".to_vec()),
+      ProcessEvent::Terminated { code: Some(1) },
+    ]);
+    let exited = wait_until_ready(failed, &pattern, Duration::from_secs(1)).await;
+    assert!(
+      matches!(exited, Err(AppError::PortForward(m)) if m.ends_with("ready: invalid instance name"))
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,286 @@
+use crate::error::{AppError, Result};
+use crate::services::validation::local_endpoints;
+use crate::services::{ProcessDetector, ProcessManager};
+use crate::types::PortForwardConfig;
+use async_trait::async_trait;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// How long a dependency may take to accept connections before dependents give up
+pub const READY_TIMEOUT: Duration = Duration::from_secs(20);
+
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Forwards to start for `name`: its `depends_on` chain (dependencies first,
+/// skipping ones in `running`), then `name` itself
+pub fn start_plan(
+  name: &str,
+  configs: &[PortForwardConfig],
+  running: &[String],
+) -> Result<Vec<PortForwardConfig>> {
+  let target = configs
+    .iter()
+    .find(|c| c.name == name)
+    .ok_or_else(|| AppError::NotFound(format!("Service '{}' not found", name)))?;
+
+  let mut walk = Walk::new(configs);
+  walk.visit(target)?;
+
+  Ok(
+    walk
+      .order
+      .into_iter()
+      .filter(|c| c.name == name || !running.contains(&c.name))
+      .cloned()
+      .collect(),
+  )
+}
+
+/// How a frontend starts a single forward, for `start_with_dependencies`
+#[async_trait]
+pub trait ForwardStarter: Sync {
+  /// Start `config` on its own, ignoring `depends_on`. Returns a message for the user.
+  async fn start_forward(&self, config: PortForwardConfig) -> Result<String>;
+
+  /// Progress of starting `name`, such as the dependency it is waiting for
+  fn progress(&self, name: &str, message: &str);
+}
+
+/// Start `config` after its `depends_on` chain, waiting for each dependency to
+/// accept connections before starting the next. Returns the message of every
+/// forward started, `config`'s last.
+pub async fn start_with_dependencies<S: ForwardStarter>(
+  starter: &S,
+  process_manager: &ProcessManager,
+  config: PortForwardConfig,
+  configs: &[PortForwardConfig],
+) -> Result<Vec<String>> {
+  let mut messages = Vec::new();
+  if !config.depends_on.is_empty() {
+    let running = process_manager.get_running_services()?;
+    let plan = start_plan(&config.name, configs, &running)?;
+    for dependency in plan.into_iter().filter(|c| c.name != config.name) {
+      let name = dependency.name.clone();
+      starter.progress(
+        &config.name,
+        &format!("Starting {}, which it depends on", name),
+      );
+      messages.push(starter.start_forward(dependency).await?);
+
+      let pid = process_manager.get_process_pid(&name)?;
+      let started = process_manager.get_process_config(&name)?;
+      let (Some(pid), Some(started)) = (pid, started) else {
+        return Err(AppError::PortForward(format!("{} is not running", name)));
+      };
+      starter.progress(
+        &config.name,
+        &format!("Waiting for {} to accept connections", name),
+      );
+      wait_until_ready(&started, pid, READY_TIMEOUT).await?;
+    }
+  }
+
+  messages.push(starter.start_forward(config).await?);
+  Ok(messages)
+}
+
+/// Unknown dependencies and cycles, for load-time warnings
+pub fn check_dependencies(configs: &[PortForwardConfig]) -> Vec<String> {
+  let mut walk = Walk::new(configs);
+  let mut failed: Vec<&str> = Vec::new();
+  let mut warnings: Vec<String> = Vec::new();
+
+  for config in configs {
+    if failed.contains(&config.name.as_str()) {
+      continue;
+    }
+    walk.visiting.clear();
+    if let Err(e) = walk.visit(config) {
+      failed.extend(walk.visiting.iter().copied());
+      let message = e.to_string();
+      if !warnings.contains(&message) {
+        warnings.push(message);
+      }
+    }
+  }
+  warnings
+}
+
+/// Running forwards that directly or indirectly depend on `name`
+pub fn running_dependents(
+  name: &str,
+  configs: &[PortForwardConfig],
+  running: &[String],
+) -> Vec<String> {
+  configs
+    .iter()
+    .filter(|c| c.name != name && running.contains(&c.name))
+    .filter(|c| {
+      let mut walk = Walk::new(configs);
+      let _ = walk.visit(c);
+      walk.order.iter().any(|d| d.name == name)
+    })
+    .map(|c| c.name.clone())
+    .collect()
+}
+
+/// Wait until the forward started from `config` as `pid` accepts connections on
+/// every local port. Forwards on random local ports only need to stay alive.
+pub async fn wait_until_ready(
+  config: &PortForwardConfig,
+  pid: u32,
+  timeout: Duration,
+) -> Result<()> {
+  let endpoints: Vec<SocketAddr> = local_endpoints(config)
+    .into_iter()
+    .map(|(ip, port)| SocketAddr::new(connectable(ip), port))
+    .collect();
+  let detector = ProcessDetector::new();
+  let deadline = Instant::now() + timeout;
+
+  loop {
+    if !detector.is_process_actually_running(pid)? {
+      return Err(AppError::PortForward(format!(
+        "{} exited before it was ready",
+        config.name
+      )));
+    }
+    if all_accept(&endpoints).await {
+      return Ok(());
+    }
+    if Instant::now() >= deadline {
+      return Err(AppError::PortForward(format!(
+        "{} was not ready after {}s",
+        config.name,
+        timeout.as_secs()
+      )));
+    }
+    tokio::time::sleep(READY_POLL_INTERVAL).await;
+  }
+}
+
+async fn all_accept(endpoints: &[SocketAddr]) -> bool {
+  for endpoint in endpoints {
+    let connect = tokio::net::TcpStream::connect(endpoint);
+    if !matches!(
+      tokio::time::timeout(CONNECT_TIMEOUT, connect).await,
+      Ok(Ok(_))
+    ) {
+      return false;
+    }
+  }
+  true
+}
+
+/// A wildcard listener is reached through loopback
+fn connectable(ip: IpAddr) -> IpAddr {
+  match ip {
+    IpAddr::V4(v4) if v4.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(v6) if v6.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+    _ => ip,
+  }
+}
+
+/// Depth-first walk over `depends_on`, collecting configs dependencies-first
+struct Walk<'a> {
+  configs: &'a [PortForwardConfig],
+  visiting: Vec<&'a str>,
+  order: Vec<&'a PortForwardConfig>,
+}
+
+impl<'a> Walk<'a> {
+  fn new(configs: &'a [PortForwardConfig]) -> Self {
+    Self {
+      configs,
+      visiting: Vec::new(),
+      order: Vec::new(),
+    }
+  }
+
+  fn visit(&mut self, config: &'a PortForwardConfig) -> Result<()> {
+    if self.order.iter().any(|c| c.name == config.name) {
+      return Ok(());
+    }
+    if let Some(start) = self.visiting.iter().position(|n| *n == config.name) {
+      let mut cycle = self.visiting[start..].to_vec();
+      cycle.push(&config.name);
+      return Err(AppError::InvalidInput(format!(
+        "Dependency cycle: {}",
+        cycle.join(" -> ")
+      )));
+    }
+
+    self.visiting.push(&config.name);
+    for dependency in &config.depends_on {
+      let dependency = self
+        .configs
+        .iter()
+        .find(|c| &c.name == dependency)
+        .ok_or_else(|| {
+          AppError::NotFound(format!(
+            "'{}' depends on unknown config '{}'",
+            config.name, dependency
+          ))
+        })?;
+      self.visit(dependency)?;
+    }
+    self.visiting.pop();
+    self.order.push(config);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(name: &str, depends_on: &[&str]) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      depends_on: depends_on.iter().copied().map(String::from).collect(),
+      ..Default::default()
+    }
+  }
+
+  fn names(configs: Vec<PortForwardConfig>) -> Vec<String> {
+    configs.into_iter().map(|c| c.name).collect()
+  }
+
+  #[test]
+  fn orders_dependencies_and_detects_cycles() -> Result<()> {
+    let configs = vec![
+      config("gateway", &["auth", "db"]),
+      config("auth", &["db"]),
+      config("db", &[]),
+    ];
+    assert_eq!(
+      names(start_plan("gateway", &configs, &[])?),
+      vec!["db", "auth", "gateway"]
+    );
+    assert_eq!(
+      names(start_plan("gateway", &configs, &["db".to_string()])?),
+      vec!["auth", "gateway"]
+    );
+    assert_eq!(
+      running_dependents("db", &configs, &["gateway".to_string(), "db".to_string()]),
+      vec!["gateway"]
+    );
+    assert!(check_dependencies(&configs).is_empty());
+
+    let cyclic = vec![
+      config("a", &["b"]),
+      config("b", &["a"]),
+      config("c", &["x"]),
+    ];
+    assert_eq!(
+      check_dependencies(&cyclic),
+      vec![
+        "Invalid input: Dependency cycle: a -> b -> a".to_string(),
+        "Not found: 'c' depends on unknown config 'x'".to_string()
+      ]
+    );
+    assert!(start_plan("b", &cyclic, &[]).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,255 @@
+use crate::error::{AppError, Result};
+use crate::services::command_builder::{KubectlCommandBuilder, SshCommandBuilder};
+use crate::services::config_migrations::{self, CURRENT_CONFIG_VERSION};
+use crate::services::{custom_command, tcp_relay, validation};
+use crate::types::{
+  ConfigSource, ForwardTarget, ForwardType, PortForwardConfig, PortForwardConfigs,
+};
+use serde::{Deserialize, Serialize};
+
+/// Output of an export: a runnable script or a bundle for `easykpf import`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+  Script,
+  Bundle,
+}
+
+/// Stands in for the selector's pod until the script resolves it
+const POD_PLACEHOLDER: &str = "__EASYKPF_POD__";
+
+/// Configs named in `names`, or all configs of `context` when `names` is empty
+pub fn select_for_export(
+  configs: &[PortForwardConfig],
+  names: &[String],
+  context: Option<&str>,
+) -> Result<Vec<PortForwardConfig>> {
+  if let Some(missing) = names
+    .iter()
+    .find(|n| !configs.iter().any(|c| &c.name == *n))
+  {
+    return Err(AppError::NotFound(format!(
+      "Service '{}' not found",
+      missing
+    )));
+  }
+
+  let selected: Vec<_> = configs
+    .iter()
+    .filter(|c| names.is_empty() || names.contains(&c.name))
+    .filter(|c| context.is_none_or(|ctx| c.context == ctx))
+    .cloned()
+    .collect();
+
+  if selected.is_empty() {
+    return Err(AppError::InvalidInput("Nothing to export".to_string()));
+  }
+  Ok(selected)
+}
+
+pub fn export_configs(configs: &[PortForwardConfig], format: ExportFormat) -> Result<String> {
+  match format {
+    ExportFormat::Script => Ok(export_script(configs)),
+    ExportFormat::Bundle => export_bundle(configs),
+  }
+}
+
+/// A POSIX shell script running the same commands EasyKpf would, in parallel.
+/// kubectl is taken from `$KUBECTL` or `PATH`, and the caller's kubeconfig is used.
+pub fn export_script(configs: &[PortForwardConfig]) -> String {
+  let kubectl = KubectlCommandBuilder::new("kubectl".to_string(), None);
+  let ssh = SshCommandBuilder::new();
+
+  let mut script = format!(
+    "#!/bin/sh\n\
+     # Generated by EasyKpf: {} port forward(s). Stop them with Ctrl-C.\n\
+     set -u\n\
+     KUBECTL=\"${{KUBECTL:-kubectl}}\"\n\
+     trap 'kill $(jobs -p) 2>/dev/null' EXIT INT TERM\n",
+    configs.len()
+  );
+
+  for config in configs {
+    script.push_str(&format!("\n# {}\n", config.name));
+
+    match config.forward_type {
+      ForwardType::Ssh => {
+        let (program, args) = ssh.build_port_forward_command(config);
+        script.push_str(&format!("{} &\n", shell_command(&program, &args)));
+      }
+      // Outside EasyKpf socat relays, one per port
+      ForwardType::Tcp => {
+        for mapping in &config.ports {
+          script.push_str(&format!(
+            "{} &\n",
+            shell_command("socat", &socat_args(config, mapping))
+          ));
+        }
+      }
+      // The user's own env only; the script runs with the shell's PATH
+      ForwardType::Command => {
+        let fill = |value: &str| custom_command::fill_placeholders(value, config);
+        let args: Vec<String> = config.command.args.iter().map(|a| fill(a)).collect();
+        let env: String = config
+          .command
+          .env
+          .iter()
+          .map(|(key, value)| format!("{}={} ", key, quote(&fill(value))))
+          .collect();
+        script.push_str(&format!(
+          "{}{} &\n",
+          env,
+          shell_command(&fill(&config.command.program), &args)
+        ));
+      }
+      // Native forwards run inside EasyKpf; outside it kubectl does the same job
+      ForwardType::Kubectl | ForwardType::Native => match kubectl.build_ready_pod_command(config) {
+        Some((_, lookup_args, _)) => {
+          let resolved = PortForwardConfig {
+            service: ForwardTarget::Pod(POD_PLACEHOLDER.to_string()).to_string(),
+            ..config.clone()
+          };
+          let (_, args, _) = kubectl.build_port_forward_command(&resolved);
+          let forward = shell_command("$KUBECTL", &args)
+            .replace(&format!("pod/{}", POD_PLACEHOLDER), "\"pod/$pod\"");
+
+          script.push_str(&format!(
+            "pod=$({} | awk '$2 == \"True\" {{ print $1; exit }}')\n\
+             if [ -n \"$pod\" ]; then\n  {} &\n\
+             else\n  echo {} >&2\nfi\n",
+            shell_command("$KUBECTL", &lookup_args),
+            forward,
+            quote(&format!(
+              "{}: no ready pod for {}",
+              config.name,
+              config.target()
+            ))
+          ));
+        }
+        None => {
+          let (_, args, _) = kubectl.build_port_forward_command(config);
+          script.push_str(&format!("{} &\n", shell_command("$KUBECTL", &args)));
+        }
+      },
+    }
+  }
+
+  script.push_str("\nwait\n");
+  script
+}
+
+/// A standalone `port-forwards.yaml` document, importable with `easykpf import`
+/// or usable as an `include:` fragment
+pub fn export_bundle(configs: &[PortForwardConfig]) -> Result<String> {
+  let bundle = PortForwardConfigs {
+    version: CURRENT_CONFIG_VERSION,
+    configs: configs
+      .iter()
+      .cloned()
+      .map(|c| PortForwardConfig {
+        source: ConfigSource::Global,
+        ..c
+      })
+      .collect(),
+    ..Default::default()
+  };
+
+  Ok(format!(
+    "# EasyKpf bundle. Import with `easykpf import <file>`.\n{}",
+    serde_yaml::to_string(&bundle)?
+  ))
+}
+
+/// Parse an exported bundle. Returns None when `text` is not a YAML config document,
+/// e.g. a list of shell commands.
+pub fn read_bundle(text: &str) -> Option<Result<Vec<PortForwardConfig>>> {
+  let mut doc: serde_yaml::Value = serde_yaml::from_str(text).ok()?;
+  doc.get("configs")?;
+
+  Some(
+    config_migrations::migrate(&mut doc)
+      .and_then(|_| Ok(serde_yaml::from_value::<PortForwardConfigs>(doc)?))
+      .map(|bundle| bundle.configs),
+  )
+}
+
+/// socat arguments listening like EasyKpf's relay for one port mapping
+fn socat_args(config: &PortForwardConfig, mapping: &str) -> Vec<String> {
+  let (local, remote) = mapping.split_once(':').unwrap_or((mapping, mapping));
+  let ip = config
+    .local_interface
+    .as_deref()
+    .and_then(validation::parse_interface)
+    .map_or_else(|| "127.0.0.1".to_string(), |(ip, _)| ip.to_string());
+  let port = remote.parse().unwrap_or_default();
+  vec![
+    format!("TCP-LISTEN:{},bind={},fork,reuseaddr", local, ip),
+    format!("TCP:{}", tcp_relay::target(config.service.trim(), port)),
+  ]
+}
+
+/// `$KUBECTL` stays unquoted-expandable, everything else is quoted as needed
+fn shell_command(program: &str, args: &[String]) -> String {
+  let program = if program.starts_with('$') {
+    format!("\"{}\"", program)
+  } else {
+    quote(program)
+  };
+  std::iter::once(program)
+    .chain(args.iter().map(|a| quote(a)))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn quote(arg: &str) -> String {
+  let safe = !arg.is_empty()
+    && arg
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+  if safe {
+    arg.to_string()
+  } else {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(name: &str, service: &str) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      context: "dev".to_string(),
+      namespace: "default".to_string(),
+      service: service.to_string(),
+      ports: vec!["8080:80".to_string()],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn script_runs_builder_commands_and_resolves_selectors() {
+    let script = export_script(&[
+      config("api", "svc/api"),
+      config("worker", "selector/app=worker,tier in (jobs)"),
+    ]);
+
+    assert!(
+      script.contains(r#""$KUBECTL" --context dev -n default port-forward svc/api 8080:80 &"#)
+    );
+    assert!(script.contains(r#"-l 'app=worker,tier in (jobs)'"#));
+    assert!(script.contains(r#"port-forward "pod/$pod" 8080:80 &"#));
+    assert!(script.ends_with("wait\n"));
+  }
+
+  #[test]
+  fn bundle_round_trips() -> Result<()> {
+    let configs = vec![config("api", "svc/api")];
+    let bundle = export_bundle(&configs)?;
+    let read = read_bundle(&bundle).ok_or_else(|| AppError::Config("not a bundle".into()))??;
+    assert_eq!(read, configs);
+    assert!(read_bundle("kubectl port-forward svc/api 8080").is_none());
+    Ok(())
+  }
+}
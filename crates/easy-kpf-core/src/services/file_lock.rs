@@ -0,0 +1,50 @@
+use crate::error::Result;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+/// Advisory lock shared by every EasyKpf process (TUI, app, kpfctl), released
+/// when dropped.
+///
+/// Locked files are replaced by atomic renames, so the lock is taken on a
+/// `<file>.lock` sidecar rather than on the file itself.
+pub struct FileLock {
+  _file: File,
+}
+
+impl FileLock {
+  /// Block until the lock for `path` is free
+  pub fn acquire(path: &Path) -> Result<Self> {
+    let file = open_lock_file(path)?;
+    file.lock()?;
+    Ok(Self { _file: file })
+  }
+
+  /// Take the lock for `path`, or `None` if someone else holds it
+  pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+    let file = open_lock_file(path)?;
+    match file.try_lock() {
+      Ok(()) => Ok(Some(Self { _file: file })),
+      Err(TryLockError::WouldBlock) => Ok(None),
+      Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+  }
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  Ok(
+    OpenOptions::new()
+      .create(true)
+      .truncate(false)
+      .write(true)
+      .open(lock_path(path))?,
+  )
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+  let mut name = path.as_os_str().to_owned();
+  name.push(".lock");
+  PathBuf::from(name)
+}
@@ -0,0 +1,201 @@
+use crate::error::{AppError, Result};
+use crate::types::PortForwardConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Snapshots kept when `history_limit` is not set in `app-config.yaml`
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// The writable configs as saved at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+  pub id: String,
+  /// Unix time in milliseconds
+  pub created_at: u64,
+  pub configs: Vec<PortForwardConfig>,
+}
+
+/// A snapshot without its configs, for listing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotInfo {
+  pub id: String,
+  pub created_at: u64,
+  pub config_count: usize,
+}
+
+/// Config names that differ between two sets of configs
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotDiff {
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+  pub changed: Vec<String>,
+}
+
+impl SnapshotDiff {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+/// Timestamped snapshots in `<config dir>/history`, one YAML file each, the
+/// oldest dropped once there are more than `limit`
+pub struct ConfigHistory {
+  dir: PathBuf,
+  limit: usize,
+}
+
+impl ConfigHistory {
+  pub fn new(dir: PathBuf, limit: usize) -> Self {
+    Self { dir, limit }
+  }
+
+  /// Keep `configs` as a new snapshot unless they match the latest one
+  pub fn record(&self, configs: &[PortForwardConfig]) -> Result<()> {
+    if self.limit == 0 {
+      return Ok(());
+    }
+    let ids = self.ids()?;
+    if let Some(latest) = ids.last() {
+      if self.load(latest)?.configs == configs {
+        return Ok(());
+      }
+    }
+
+    fs::create_dir_all(&self.dir)?;
+    let mut created_at = unix_millis();
+    while self.path(&created_at.to_string()).exists() {
+      created_at += 1;
+    }
+    let snapshot = Snapshot {
+      id: created_at.to_string(),
+      created_at,
+      configs: configs.to_vec(),
+    };
+    fs::write(self.path(&snapshot.id), serde_yaml::to_string(&snapshot)?)?;
+
+    let ids = self.ids()?;
+    for id in &ids[..ids.len().saturating_sub(self.limit)] {
+      fs::remove_file(self.path(id))?;
+    }
+    Ok(())
+  }
+
+  /// Newest first
+  pub fn list(&self) -> Result<Vec<SnapshotInfo>> {
+    let mut snapshots = Vec::new();
+    for id in self.ids()?.iter().rev() {
+      let snapshot = self.load(id)?;
+      snapshots.push(SnapshotInfo {
+        id: snapshot.id,
+        created_at: snapshot.created_at,
+        config_count: snapshot.configs.len(),
+      });
+    }
+    Ok(snapshots)
+  }
+
+  pub fn load(&self, id: &str) -> Result<Snapshot> {
+    // Ids are timestamps; anything else could point outside the history dir
+    let path = self.path(id);
+    if !id.bytes().all(|b| b.is_ascii_digit()) || !path.exists() {
+      return Err(AppError::NotFound(format!("Snapshot '{}' not found", id)));
+    }
+    Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  /// Snapshot ids, oldest first
+  fn ids(&self) -> Result<Vec<String>> {
+    if !self.dir.exists() {
+      return Ok(Vec::new());
+    }
+    let mut ids: Vec<(u64, String)> = Vec::new();
+    for entry in fs::read_dir(&self.dir)? {
+      let path = entry?.path();
+      let id = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_suffix(".yaml"));
+      if let Some((millis, id)) = id.and_then(|id| Some((id.parse().ok()?, id.to_string()))) {
+        ids.push((millis, id));
+      }
+    }
+    ids.sort();
+    Ok(ids.into_iter().map(|(_, id)| id).collect())
+  }
+
+  fn path(&self, id: &str) -> PathBuf {
+    self.dir.join(format!("{}.yaml", id))
+  }
+}
+
+/// What changes going from `from` to `to`, by config name
+pub fn diff(from: &[PortForwardConfig], to: &[PortForwardConfig]) -> SnapshotDiff {
+  let mut result = SnapshotDiff::default();
+  for config in to {
+    match from.iter().find(|c| c.name == config.name) {
+      None => result.added.push(config.name.clone()),
+      Some(old) if old != config => result.changed.push(config.name.clone()),
+      Some(_) => {}
+    }
+  }
+  result.removed = from
+    .iter()
+    .filter(|c| !to.iter().any(|t| t.name == c.name))
+    .map(|c| c.name.clone())
+    .collect();
+  result
+}
+
+fn unix_millis() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(name: &str, service: &str) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      service: service.to_string(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn records_prunes_and_diffs_snapshots() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("easykpf-history-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let history = ConfigHistory::new(dir.clone(), 2);
+
+    history.record(&[config("api", "api")])?;
+    history.record(&[config("api", "api")])?;
+    assert_eq!(history.list()?.len(), 1);
+
+    history.record(&[config("api", "api"), config("db", "db")])?;
+    history.record(&[config("api", "api-v2")])?;
+    let snapshots = history.list()?;
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[0].config_count, 1);
+
+    let older = history.load(&snapshots[1].id)?;
+    let newer = history.load(&snapshots[0].id)?;
+    assert_eq!(
+      diff(&newer.configs, &older.configs),
+      SnapshotDiff {
+        added: vec!["db".to_string()],
+        removed: vec![],
+        changed: vec!["api".to_string()],
+      }
+    );
+    assert!(history.load("../port-forwards").is_err());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
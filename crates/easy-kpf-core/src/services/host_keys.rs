@@ -0,0 +1,217 @@
+use crate::error::{AppError, Result};
+use crate::services::file_lock::FileLock;
+use crate::types::PortForwardConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A public key offered by an SSH host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostKey {
+  /// Host as written to known_hosts: `host`, or `[host]:port` off port 22
+  pub host: String,
+  pub key_type: String,
+  /// Base64 public key
+  pub key: String,
+  /// `SHA256:...` as printed by `ssh-keygen -l`
+  pub fingerprint: String,
+}
+
+impl HostKey {
+  fn known_hosts_line(&self) -> String {
+    format!("{} {} {}", self.host, self.key_type, self.key)
+  }
+}
+
+/// EasyKpf's own known_hosts file, which SSH forwards verify host keys against
+pub struct HostKeyStore {
+  path: PathBuf,
+}
+
+impl HostKeyStore {
+  pub fn new(path: PathBuf) -> Self {
+    Self { path }
+  }
+
+  /// Whether exactly this key is pinned for its host
+  pub fn is_pinned(&self, key: &HostKey) -> Result<bool> {
+    Ok(
+      self
+        .lines()?
+        .iter()
+        .any(|line| *line == key.known_hosts_line()),
+    )
+  }
+
+  /// Trust `keys`, replacing whatever was pinned for their hosts before
+  pub fn pin(&self, keys: &[HostKey]) -> Result<()> {
+    let _lock = FileLock::acquire(&self.path)?;
+    let mut lines: Vec<String> = self
+      .lines()?
+      .into_iter()
+      .filter(|line| !keys.iter().any(|k| names_host(line, &k.host)))
+      .collect();
+    lines.extend(keys.iter().map(HostKey::known_hosts_line));
+
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(&self.path, lines.join("\n") + "\n")?;
+    Ok(())
+  }
+
+  fn lines(&self) -> Result<Vec<String>> {
+    if !self.path.exists() {
+      return Ok(Vec::new());
+    }
+    Ok(
+      fs::read_to_string(&self.path)?
+        .lines()
+        .map(str::to_string)
+        .collect(),
+    )
+  }
+}
+
+/// Fetch the keys the host of an SSH forward offers, with their fingerprints, for
+/// the user to compare before pinning them.
+///
+/// `ssh-keyscan` connects directly, so hosts only reachable through jump hosts
+/// can't be fetched this way. Blocks for up to five seconds.
+pub fn fetch(config: &PortForwardConfig) -> Result<Vec<HostKey>> {
+  if !config.ssh.jump_hosts.is_empty() {
+    return Err(AppError::HostKey(format!(
+      "{} is behind jump hosts and can't be scanned directly; \
+       connect once with host_key_policy accept-new instead",
+      config.service
+    )));
+  }
+  let (host, port) = ssh_target(config);
+  let output = Command::new("ssh-keyscan")
+    .args(["-T", "5", "-p", &port.to_string(), &host])
+    .output()
+    .map_err(|e| AppError::System(format!("Failed to run ssh-keyscan: {}", e)))?;
+  let scanned = String::from_utf8_lossy(&output.stdout).to_string();
+  let mut keys = parse_keyscan(&scanned);
+  if keys.is_empty() {
+    return Err(AppError::HostKey(format!(
+      "No host keys received from {}:{}",
+      host, port
+    )));
+  }
+
+  // ssh-keygen only fingerprints files; it prints one line per key, in order
+  let scan_file = std::env::temp_dir().join(format!("easykpf-keyscan-{}", std::process::id()));
+  fs::write(&scan_file, &scanned)?;
+  let fingerprints = Command::new("ssh-keygen")
+    .arg("-l")
+    .arg("-f")
+    .arg(&scan_file)
+    .output();
+  let _ = fs::remove_file(&scan_file);
+
+  let fingerprints =
+    fingerprints.map_err(|e| AppError::System(format!("Failed to run ssh-keygen: {}", e)))?;
+  let fingerprints = String::from_utf8_lossy(&fingerprints.stdout).to_string();
+  for (key, line) in keys.iter_mut().zip(fingerprints.lines()) {
+    key.fingerprint = line
+      .split_whitespace()
+      .nth(1)
+      .unwrap_or_default()
+      .to_string();
+  }
+  Ok(keys)
+}
+
+/// Host and port ssh connects to for `config`
+pub fn ssh_target(config: &PortForwardConfig) -> (String, u16) {
+  let host = config
+    .service
+    .rsplit_once('@')
+    .map_or(config.service.as_str(), |(_, host)| host);
+  (host.to_string(), config.ssh.port.unwrap_or(22))
+}
+
+/// A clear error for ssh host key failures, `None` for any other stderr
+pub fn classify_ssh_error(stderr: &str) -> Option<AppError> {
+  let changed = stderr.contains("REMOTE HOST IDENTIFICATION HAS CHANGED")
+    || (stderr.contains("Host key for") && stderr.contains("has changed"));
+  if changed {
+    return Some(AppError::HostKey(
+      "The host key changed since it was pinned. Unless the server was rebuilt, \
+       someone may be intercepting the connection; otherwise verify and pin the new key"
+        .to_string(),
+    ));
+  }
+  if stderr.contains("host key is known for") || stderr.contains("Host key verification failed") {
+    return Some(AppError::HostKey(
+      "The host key is not pinned yet; verify and pin it, or set host_key_policy to accept-new"
+        .to_string(),
+    ));
+  }
+  None
+}
+
+/// `host keytype key` lines of `ssh-keyscan` output, without fingerprints
+fn parse_keyscan(output: &str) -> Vec<HostKey> {
+  output
+    .lines()
+    .filter(|line| !line.starts_with('#'))
+    .filter_map(|line| {
+      let mut parts = line.split_whitespace();
+      Some(HostKey {
+        host: parts.next()?.to_string(),
+        key_type: parts.next()?.to_string(),
+        key: parts.next()?.to_string(),
+        fingerprint: String::new(),
+      })
+    })
+    .collect()
+}
+
+/// Whether a known_hosts line is for `host` (its first field lists hosts)
+fn names_host(line: &str, host: &str) -> bool {
+  line
+    .split_whitespace()
+    .next()
+    .is_some_and(|hosts| hosts.split(',').any(|h| h == host))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pins_scanned_keys_and_classifies_mismatches() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("easykpf-hostkeys-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = HostKeyStore::new(dir.join("known_hosts"));
+
+    let scanned = "# bastion:2222 SSH-2.0-OpenSSH_9.6\n\
+      [bastion]:2222 ssh-ed25519 AAAAold\n";
+    let old = parse_keyscan(scanned);
+    assert_eq!(old.len(), 1);
+    assert_eq!(old[0].host, "[bastion]:2222");
+    store.pin(&old)?;
+    assert!(store.is_pinned(&old[0])?);
+
+    let new = HostKey {
+      key: "AAAAnew".to_string(),
+      ..old[0].clone()
+    };
+    store.pin(std::slice::from_ref(&new))?;
+    assert!(store.is_pinned(&new)?);
+    assert!(!store.is_pinned(&old[0])?);
+
+    let stderr = "@@@ WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED! @@@\n\
+      Host key verification failed.";
+    assert!(
+      matches!(classify_ssh_error(stderr), Some(AppError::HostKey(m)) if m.contains("changed"))
+    );
+    assert!(classify_ssh_error("Connection refused").is_none());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,586 @@
+use crate::error::{AppError, Result};
+use crate::services::{exporter, validation, Variables};
+use crate::types::{ForwardTarget, ForwardType, PortForwardConfig, SshSettings};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Configs recovered from command lines, plus the lines that could not be used
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+  pub configs: Vec<PortForwardConfig>,
+  /// Port forward commands that were recognised but could not be imported
+  pub skipped: Vec<String>,
+  /// Commands whose forward already exists in the current configs
+  pub duplicates: usize,
+}
+
+impl ImportReport {
+  fn add(&mut self, config: PortForwardConfig, existing: &[PortForwardConfig]) {
+    let known = existing.iter().chain(self.configs.iter());
+    if known.clone().any(|c| same_forward(c, &config)) {
+      self.duplicates += 1;
+      return;
+    }
+    let taken: Vec<&str> = known.map(|c| c.name.as_str()).collect();
+    let config = PortForwardConfig {
+      name: unique_name(&config.name, &taken),
+      ..config
+    };
+
+    let mut all = existing.to_vec();
+    all.extend(self.configs.iter().cloned());
+    let errors = validation::validate_against(&config, &all, None, &Variables::new());
+    if errors.is_empty() {
+      self.configs.push(config);
+    } else {
+      self.skipped.push(format!("{}: {}", config.name, errors));
+    }
+  }
+}
+
+/// Turns `kubectl port-forward ...` and `ssh -L ...` command lines into configs
+pub struct CommandImporter {
+  default_context: String,
+}
+
+/// kubectl flags that take a value, so the value is not mistaken for the target
+const KUBECTL_VALUE_FLAGS: [&str; 9] = [
+  "--kubeconfig",
+  "--cluster",
+  "--user",
+  "--pod-running-timeout",
+  "--request-timeout",
+  "-s",
+  "--server",
+  "--token",
+  "--as",
+];
+
+/// ssh options that take a value (see `man ssh`)
+const SSH_VALUE_FLAGS: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+impl CommandImporter {
+  /// `default_context` is used for kubectl commands without `--context`
+  pub fn new(default_context: impl Into<String>) -> Self {
+    Self {
+      default_context: default_context.into(),
+    }
+  }
+
+  /// Parse every port forward in `text` (a script, a file of commands, shell
+  /// history or an exported bundle). Forwards matching one in `existing` are
+  /// counted, not returned.
+  pub fn import(&self, text: &str, existing: &[PortForwardConfig]) -> ImportReport {
+    let mut report = ImportReport::default();
+
+    // Bundles written by `easykpf export` are taken as-is
+    if let Some(bundle) = exporter::read_bundle(text) {
+      match bundle {
+        Ok(configs) => configs
+          .into_iter()
+          .for_each(|config| report.add(config, existing)),
+        Err(e) => report.skipped.push(format!("bundle: {}", e)),
+      }
+      return report;
+    }
+
+    for command in split_commands(text) {
+      match self.parse_command(&command) {
+        Ok(configs) => configs
+          .into_iter()
+          .for_each(|config| report.add(config, existing)),
+        Err(e) => report.skipped.push(format!("{}: {}", command, e)),
+      }
+    }
+
+    report
+  }
+
+  /// Parse a single command into its port forwards, none for commands that are not
+  /// port forwards. An ssh command gives one config per local interface it binds.
+  pub fn parse_command(&self, command: &str) -> Result<Vec<PortForwardConfig>> {
+    let words = strip_redirections(split_words(command)?);
+    let Some(program) = words
+      .iter()
+      .position(|w| is_program(w, "kubectl") || is_program(w, "ssh"))
+    else {
+      return Ok(vec![]);
+    };
+
+    let args = &words[program + 1..];
+    if is_program(&words[program], "kubectl") {
+      if !args.iter().any(|a| a == "port-forward") {
+        return Ok(vec![]);
+      }
+      Ok(vec![self.parse_kubectl(args)?])
+    } else {
+      parse_ssh(args)
+    }
+  }
+
+  fn parse_kubectl(&self, args: &[String]) -> Result<PortForwardConfig> {
+    let mut context = None;
+    let mut namespace = None;
+    let mut address = None;
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+      let (flag, inline) = match arg.split_once('=') {
+        Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+        _ => (arg.as_str(), None),
+      };
+      let mut value = || inline.clone().or_else(|| iter.next().cloned());
+
+      match flag {
+        "port-forward" => {}
+        "--context" => context = value(),
+        "-n" | "--namespace" => namespace = value(),
+        "--address" => address = value(),
+        _ if flag.starts_with("-n") && flag.len() > 2 => namespace = Some(flag[2..].to_string()),
+        _ if KUBECTL_VALUE_FLAGS.contains(&flag) => {
+          value();
+        }
+        _ if flag.starts_with('-') => {}
+        _ => positional.push(arg.clone()),
+      }
+    }
+
+    let mut positional = positional.into_iter();
+    let target = positional
+      .next()
+      .ok_or_else(|| AppError::InvalidInput("missing port-forward target".to_string()))?;
+    let ports: Vec<String> = positional.collect();
+    if ports.is_empty() {
+      return Err(AppError::InvalidInput("missing ports".to_string()));
+    }
+
+    let context = context.unwrap_or_else(|| self.default_context.clone());
+    if context.is_empty() {
+      return Err(AppError::InvalidInput(
+        "no --context and no current context".to_string(),
+      ));
+    }
+
+    let name = format!(
+      "{}-{}",
+      ForwardTarget::parse(&target).name(),
+      local_port(&ports[0])
+    );
+    Ok(PortForwardConfig {
+      name,
+      context,
+      namespace: namespace.unwrap_or_else(|| "default".to_string()),
+      service: target,
+      ports,
+      local_interface: address.and_then(|a| bind_interface(a.split(',').next().unwrap_or(""))),
+      forward_type: ForwardType::Kubectl,
+      ..Default::default()
+    })
+  }
+}
+
+/// The `TYPE/NAME` argument of a `kubectl ... port-forward ...` command line: the
+/// first positional argument after `port-forward`, skipping flags and their values
+pub(crate) fn kubectl_target<'a>(args: &[&'a str]) -> Option<&'a str> {
+  let mut iter = args
+    .iter()
+    .copied()
+    .skip_while(|arg| *arg != "port-forward")
+    .skip(1);
+  while let Some(arg) = iter.next() {
+    if !arg.starts_with('-') {
+      return Some(arg);
+    }
+    let takes_value = matches!(arg, "--context" | "-n" | "--namespace" | "--address")
+      || KUBECTL_VALUE_FLAGS.contains(&arg);
+    if takes_value {
+      iter.next();
+    }
+  }
+  None
+}
+
+fn parse_ssh(args: &[String]) -> Result<Vec<PortForwardConfig>> {
+  let mut forwards = Vec::new();
+  let mut ssh = SshSettings::default();
+  let mut port = None;
+  let mut host = None;
+
+  let mut iter = args.iter();
+  while let Some(arg) = iter.next() {
+    if let Some(flags) = arg.strip_prefix('-') {
+      // Options may be bundled (`-fNL 8080:...`); a value flag consumes the rest
+      for (i, flag) in flags.char_indices() {
+        if !SSH_VALUE_FLAGS.contains(flag) {
+          continue;
+        }
+        let rest = &flags[i + flag.len_utf8()..];
+        let value = if rest.is_empty() {
+          iter.next().cloned().unwrap_or_default()
+        } else {
+          rest.to_string()
+        };
+        match flag {
+          'L' => forwards.push(value),
+          'l' => ssh.user = Some(value),
+          'p' => port = Some(value),
+          'i' => ssh.identity_file = Some(value),
+          'J' => ssh.jump_hosts = value.split(',').map(str::to_string).collect(),
+          _ => {}
+        }
+        break;
+      }
+    } else {
+      // The first non-option is the destination; anything after is a remote command
+      host = Some(arg.clone());
+      break;
+    }
+  }
+
+  if forwards.is_empty() {
+    return Ok(vec![]);
+  }
+  if let Some(port) = port.filter(|p| p != "22") {
+    ssh.port = Some(
+      port
+        .parse()
+        .map_err(|_| AppError::InvalidInput(format!("invalid ssh port '{}'", port)))?,
+    );
+  }
+
+  let host = host.ok_or_else(|| AppError::InvalidInput("missing ssh destination".to_string()))?;
+
+  // A config has one local interface, so forwards binding different ones are split
+  let mut by_interface: Vec<(Option<String>, Vec<String>)> = Vec::new();
+  for forward in &forwards {
+    let (bind, port) = ssh_forward_port(forward)?;
+    let interface = bind.and_then(|b| bind_interface(&b));
+    match by_interface.iter_mut().find(|(i, _)| *i == interface) {
+      Some((_, ports)) => ports.push(port),
+      None => by_interface.push((interface, vec![port])),
+    }
+  }
+
+  let host_name = host.split('@').next_back().unwrap_or(&host).to_string();
+  Ok(
+    by_interface
+      .into_iter()
+      .map(|(local_interface, ports)| PortForwardConfig {
+        name: format!("{}-{}", host_name, local_port(&ports[0])),
+        context: host.clone(),
+        namespace: "default".to_string(),
+        service: host.clone(),
+        ports,
+        local_interface,
+        forward_type: ForwardType::Ssh,
+        ssh: ssh.clone(),
+        ..Default::default()
+      })
+      .collect(),
+  )
+}
+
+/// `[bind:]port:host:hostport` -> (bind, config port). Forwards to localhost use the
+/// short `local:remote` form, anything else keeps the remote host.
+fn ssh_forward_port(forward: &str) -> Result<(Option<String>, String)> {
+  let parts: Vec<&str> = forward.split(':').collect();
+  let (bind, rest) = match parts.len() {
+    3 => (None, &parts[..]),
+    4 => (Some(parts[0].to_string()), &parts[1..]),
+    _ => {
+      return Err(AppError::InvalidInput(format!(
+        "unsupported -L forward '{}'",
+        forward
+      )))
+    }
+  };
+
+  let (local, host, remote) = (rest[0], rest[1], rest[2]);
+  let port = match host {
+    "localhost" | "127.0.0.1" if local == remote => local.to_string(),
+    "localhost" | "127.0.0.1" => format!("{}:{}", local, remote),
+    _ => format!("{}:{}:{}", local, host, remote),
+  };
+  Ok((bind, port))
+}
+
+/// Loopback binds are the default and are not stored
+fn bind_interface(address: &str) -> Option<String> {
+  match address.trim() {
+    "" | "localhost" | "127.0.0.1" => None,
+    other => Some(other.to_string()),
+  }
+}
+
+fn local_port(port: &str) -> &str {
+  port.split(':').next().unwrap_or(port)
+}
+
+/// Drop `> file`, `2>&1`, `<input` and similar so they are not read as arguments
+fn strip_redirections(words: Vec<String>) -> Vec<String> {
+  let mut result = Vec::new();
+  let mut iter = words.into_iter();
+  while let Some(word) = iter.next() {
+    let operator = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '&');
+    if operator.starts_with(['<', '>']) {
+      // A bare operator is followed by its target word
+      if operator.trim_start_matches(['<', '>', '&']).is_empty() && !word.ends_with('&') {
+        iter.next();
+      }
+      continue;
+    }
+    result.push(word);
+  }
+  result
+}
+
+fn is_program(word: &str, name: &str) -> bool {
+  word.rsplit('/').next() == Some(name)
+}
+
+fn same_forward(a: &PortForwardConfig, b: &PortForwardConfig) -> bool {
+  a.forward_type == b.forward_type
+    && a.context == b.context
+    && a.namespace == b.namespace
+    && a.service == b.service
+    && a.ports == b.ports
+    && a.local_interface == b.local_interface
+    && a.ssh == b.ssh
+}
+
+fn unique_name(name: &str, taken: &[&str]) -> String {
+  if !taken.contains(&name) {
+    return name.to_string();
+  }
+  (2..)
+    .map(|n| format!("{}-{}", name, n))
+    .find(|candidate| !taken.contains(&candidate.as_str()))
+    .unwrap_or_else(|| name.to_string())
+}
+
+/// Split text into individual commands: continuation lines are joined, history
+/// prefixes removed and `;`, `&&`, `||`, `|` and `&` separate commands.
+fn split_commands(text: &str) -> Vec<String> {
+  let mut commands = Vec::new();
+  let mut pending = String::new();
+
+  for line in text.lines() {
+    let line = strip_history_prefix(line);
+    if let Some(continued) = line.strip_suffix('\\') {
+      pending.push_str(continued);
+      pending.push(' ');
+      continue;
+    }
+    pending.push_str(line);
+    commands.extend(split_on_operators(&pending));
+    pending.clear();
+  }
+  commands.extend(split_on_operators(&pending));
+
+  commands
+}
+
+/// zsh extended history (`: 1700000000:0;cmd`) and fish history (`- cmd: cmd`)
+fn strip_history_prefix(line: &str) -> &str {
+  if let Some(rest) = line.strip_prefix(": ") {
+    if let Some((_, command)) = rest.split_once(';') {
+      return command;
+    }
+  }
+  line.strip_prefix("- cmd: ").unwrap_or(line)
+}
+
+fn split_on_operators(line: &str) -> Vec<String> {
+  let mut commands = Vec::new();
+  let mut current = String::new();
+  let mut quote = None;
+
+  for c in line.chars() {
+    match (quote, c) {
+      (Some(q), c) if c == q => {
+        quote = None;
+        current.push(c);
+      }
+      (None, '\'' | '"') => {
+        quote = Some(c);
+        current.push(c);
+      }
+      // `2>&1` and `&>` are redirections, not separators
+      (None, '&') if current.ends_with('>') => current.push(c),
+      (None, ';' | '&' | '|') => {
+        commands.push(std::mem::take(&mut current));
+      }
+      (None, '#') if current.trim().is_empty() => break,
+      _ => current.push(c),
+    }
+  }
+  commands.push(current);
+
+  commands
+    .into_iter()
+    .map(|c| c.trim().to_string())
+    .filter(|c| !c.is_empty())
+    .collect()
+}
+
+/// Minimal POSIX-style word splitting with single/double quotes and backslashes
+fn split_words(command: &str) -> Result<Vec<String>> {
+  let mut words = Vec::new();
+  let mut current = String::new();
+  let mut in_word = false;
+  let mut chars = command.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => current.push(c),
+            None => return Err(AppError::InvalidInput("unterminated quote".to_string())),
+          }
+        }
+      }
+      '"' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => current.extend(chars.next()),
+            Some(c) => current.push(c),
+            None => return Err(AppError::InvalidInput("unterminated quote".to_string())),
+          }
+        }
+      }
+      '\\' => {
+        in_word = true;
+        current.extend(chars.next());
+      }
+      c if c.is_whitespace() => {
+        if in_word {
+          words.push(std::mem::take(&mut current));
+          in_word = false;
+        }
+      }
+      c => {
+        in_word = true;
+        current.push(c);
+      }
+    }
+  }
+  if in_word {
+    words.push(current);
+  }
+
+  Ok(words)
+}
+
+/// Shell history files that exist for the current user, honouring `$HISTFILE`
+pub fn history_files() -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = std::env::var_os("HISTFILE")
+    .map(PathBuf::from)
+    .into_iter()
+    .collect();
+
+  if let Some(home) = dirs::home_dir() {
+    files.push(home.join(".bash_history"));
+    files.push(home.join(".zsh_history"));
+  }
+  if let Some(data) = dirs::data_dir() {
+    files.push(data.join("fish").join("fish_history"));
+  }
+
+  files.dedup();
+  files.into_iter().filter(|f| f.is_file()).collect()
+}
+
+/// Concatenated contents of all shell history files
+pub fn read_shell_history() -> Result<String> {
+  let files = history_files();
+  if files.is_empty() {
+    return Err(AppError::NotFound(
+      "No shell history file found".to_string(),
+    ));
+  }
+
+  let mut history = String::new();
+  for file in files {
+    // zsh history may contain non-UTF-8 metafied bytes
+    let bytes = fs::read(&file)?;
+    history.push_str(&String::from_utf8_lossy(&bytes));
+    history.push('\n');
+  }
+  Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn imports_kubectl_and_ssh_commands() {
+    let text = r#"
+: 1700000000:0;kubectl --context=prod port-forward -n api svc/api 8080:80 9090 --address 0.0.0.0
+cd ~/src && kubectl port-forward deploy/worker 5005:5005 > /tmp/worker.log 2>&1 &
+kubectl get pods
+ssh -fN -L 127.0.0.1:5432:localhost:5432 -p 2222 -J jump.example.com \
+  -L 6379:cache.internal:6379 deploy@bastion
+kubectl port-forward svc/api 8080:80 9090 -n api --context prod --address=0.0.0.0,::
+kubectl port-forward svc/missing-ports
+"#;
+    let report = CommandImporter::new("dev").import(text, &[]);
+
+    assert_eq!(report.configs.len(), 3);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.skipped.len(), 1);
+
+    let api = &report.configs[0];
+    assert_eq!(api.name, "api-8080");
+    assert_eq!(
+      (api.context.as_str(), api.namespace.as_str()),
+      ("prod", "api")
+    );
+    assert_eq!(api.ports, vec!["8080:80", "9090"]);
+    assert_eq!(api.local_interface.as_deref(), Some("0.0.0.0"));
+
+    let worker = &report.configs[1];
+    assert_eq!(
+      (worker.context.as_str(), worker.namespace.as_str()),
+      ("dev", "default")
+    );
+    assert_eq!(worker.service, "deploy/worker");
+
+    let ssh = &report.configs[2];
+    assert_eq!(ssh.forward_type, ForwardType::Ssh);
+    assert_eq!(ssh.service, "deploy@bastion");
+    assert_eq!(ssh.ports, vec!["5432", "6379:cache.internal:6379"]);
+    assert_eq!(ssh.local_interface, None);
+    assert_eq!(ssh.ssh.port, Some(2222));
+    assert_eq!(ssh.ssh.jump_hosts, vec!["jump.example.com"]);
+  }
+
+  #[test]
+  fn splits_ssh_forwards_by_bind_address() {
+    let text = "ssh -N -L 8080:web:80 -L 0.0.0.0:9090:metrics:9090 -L localhost:8443:web:443 box";
+    let report = CommandImporter::new("dev").import(text, &[]);
+
+    let split: Vec<(Option<&str>, &[String])> = report
+      .configs
+      .iter()
+      .map(|c| (c.local_interface.as_deref(), c.ports.as_slice()))
+      .collect();
+    assert_eq!(
+      split,
+      vec![
+        (
+          None,
+          &["8080:web:80".to_string(), "8443:web:443".to_string()][..]
+        ),
+        (Some("0.0.0.0"), &["9090:metrics:9090".to_string()][..]),
+      ]
+    );
+  }
+}
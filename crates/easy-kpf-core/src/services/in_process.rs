@@ -0,0 +1,148 @@
+use crate::error::{AppError, Result};
+use crate::services::traffic::{self, Bytes, Connection, Traffic};
+use crate::traits::ProcessEvent;
+use crate::types::PortForwardConfig;
+use std::fmt::Display;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+
+/// A forward running as a task in this process rather than as a child process.
+/// Aborting `task` closes its listeners and connections.
+pub struct ForwardTask {
+  pub task: JoinHandle<()>,
+  /// kubectl-style progress and errors, ending with `Terminated` if every
+  /// listener fails
+  pub events: mpsc::Receiver<ProcessEvent>,
+  /// Counters of the connections passing through it
+  pub traffic: Arc<Traffic>,
+  /// Local addresses its listeners are bound to
+  pub addresses: Vec<SocketAddr>,
+}
+
+/// Listen on the local side of a port mapping. An empty port (`:80`) picks any
+/// free one, like kubectl.
+pub(crate) async fn listen(config: &PortForwardConfig, local: &str) -> Result<TcpListener> {
+  let ip = bind_ip(config)?;
+  let port: u16 = match local {
+    "" => 0,
+    port => port
+      .parse()
+      .map_err(|_| AppError::InvalidInput(format!("Invalid local port '{}'", port)))?,
+  };
+  TcpListener::bind((ip, port))
+    .await
+    .map_err(|e| AppError::PortForward(format!("Unable to listen on {}:{}: {}", ip, port, e)))
+}
+
+/// Accept connections on each listener and hand them to `connect` along with the
+/// remote end the listener leads to. Each connection is counted in the task's
+/// traffic and logged with its client and duration once it closes.
+pub(crate) fn serve<R, F, Fut>(listeners: Vec<(TcpListener, R)>, connect: F) -> ForwardTask
+where
+  R: Display + Clone + Send + 'static,
+  F: Fn(Connection, R, mpsc::Sender<ProcessEvent>) -> Fut + Clone + Send + 'static,
+  Fut: Future<Output = ()> + Send + 'static,
+{
+  let (events, rx) = mpsc::channel(100);
+  let traffic = Arc::new(Traffic::default());
+  let counters = traffic.clone();
+  let addresses = listeners
+    .iter()
+    .filter_map(|(listener, _)| listener.local_addr().ok())
+    .collect();
+  let task = tokio::spawn(async move {
+    let mut accepting = JoinSet::new();
+    for (listener, remote) in listeners {
+      if let Ok(local) = listener.local_addr() {
+        send(&events, format!("Forwarding from {} -> {}", local, remote)).await;
+      }
+      accepting.spawn(accept_loop(
+        listener,
+        remote,
+        connect.clone(),
+        events.clone(),
+        counters.clone(),
+      ));
+    }
+    while accepting.join_next().await.is_some() {}
+    let _ = events.send(ProcessEvent::Terminated { code: None }).await;
+  });
+
+  ForwardTask {
+    task,
+    events: rx,
+    traffic,
+    addresses,
+  }
+}
+
+async fn accept_loop<R, F, Fut>(
+  listener: TcpListener,
+  remote: R,
+  connect: F,
+  events: mpsc::Sender<ProcessEvent>,
+  traffic: Arc<Traffic>,
+) where
+  R: Clone + Send + 'static,
+  F: Fn(Connection, R, mpsc::Sender<ProcessEvent>) -> Fut,
+  Fut: Future<Output = ()> + Send + 'static,
+{
+  // Dropped with the task, which closes every connection still open
+  let mut connections = JoinSet::new();
+  loop {
+    let (socket, client) = match listener.accept().await {
+      Ok(accepted) => accepted,
+      Err(e) => {
+        let _ = events
+          .send(ProcessEvent::Error(format!("Stopped listening: {}", e)))
+          .await;
+        return;
+      }
+    };
+    let port = listener.local_addr().map_or(0, |local| local.port());
+    let bytes = Arc::new(Bytes::default());
+    let connection = connect(
+      traffic.open(socket, bytes.clone()),
+      remote.clone(),
+      events.clone(),
+    );
+    let (traffic, events) = (traffic.clone(), events.clone());
+    connections.spawn(async move {
+      let opened = Instant::now();
+      connection.await;
+      traffic.close();
+      let line = format!(
+        "Connection from {} to port {} closed after {}, {}",
+        client,
+        port,
+        traffic::format_duration(opened.elapsed()),
+        bytes.summary()
+      );
+      send(&events, line).await;
+    });
+    while connections.try_join_next().is_some() {}
+  }
+}
+
+/// Address to listen on: the local interface without any port, like kubectl's
+/// `--address`
+fn bind_ip(config: &PortForwardConfig) -> Result<IpAddr> {
+  let Some(ref interface) = config.local_interface else {
+    return Ok(IpAddr::V4(Ipv4Addr::LOCALHOST));
+  };
+  let ip = match interface.rsplit_once(':') {
+    Some((ip, port)) if port.parse::<u16>().is_ok() => ip,
+    _ => interface,
+  };
+  ip.parse()
+    .map_err(|_| AppError::InvalidInput(format!("Invalid local interface '{}'", interface)))
+}
+
+async fn send(events: &mpsc::Sender<ProcessEvent>, line: String) {
+  let _ = events.send(ProcessEvent::Stdout(line.into_bytes())).await;
+}
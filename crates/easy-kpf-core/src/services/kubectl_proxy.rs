@@ -0,0 +1,349 @@
+use crate::error::{AppError, Result};
+use crate::services::in_process::{self, ForwardTask};
+use crate::services::traffic::Connection;
+use crate::services::{custom_command, dependencies, KubectlCommandBuilder, ProcessManager};
+use crate::traits::{CommandExecutor, ProcessEvent};
+use crate::types::PortForwardConfig;
+use regex::Regex;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+
+/// How long a client connection waits for kubectl to come back after a restart
+const RECONNECT_WAIT: Duration = Duration::from_secs(15);
+/// Longest pause between kubectl restarts
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+/// kubectl that stayed up this long is restarted without waiting
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// Listen on `config`'s local ports in this process and relay connections to a
+/// kubectl port-forward on internal ports. kubectl is restarted whenever it exits
+/// while the local ports stay open, so clients only see the connections that
+/// were open at the time drop.
+pub async fn start<E: CommandExecutor + 'static>(
+  executor: E,
+  builder: KubectlCommandBuilder,
+  config: &PortForwardConfig,
+) -> Result<ForwardTask> {
+  let mut listeners = Vec::new();
+  let mut internal = Vec::new();
+  for mapping in &config.ports {
+    let (local, remote) = mapping.split_once(':').unwrap_or((mapping, mapping));
+    let port = free_port().await?;
+    let backend = Backend {
+      port,
+      remote: remote.to_string(),
+    };
+    listeners.push((in_process::listen(config, local).await?, backend));
+    internal.push(format!("{}:{}", port, remote));
+  }
+
+  let kubectl = Kubectl {
+    executor,
+    builder,
+    config: PortForwardConfig {
+      ports: internal,
+      local_interface: None,
+      ..config.clone()
+    },
+    ready: Regex::new("^Forwarding from").map_err(|e| AppError::System(e.to_string()))?,
+  };
+  let (process, output) = kubectl.start().await?;
+
+  let ForwardTask {
+    task: proxy,
+    events: mut proxy_events,
+    traffic,
+    addresses,
+  } = in_process::serve(listeners, relay);
+  let (events, rx) = mpsc::channel(100);
+  let supervisor = tokio::spawn(kubectl.supervise(process, output, events.clone()));
+  let task = tokio::spawn(async move {
+    let _proxy = AbortOnDrop(proxy.abort_handle());
+    let _supervisor = AbortOnDrop(supervisor.abort_handle());
+    while let Some(event) = proxy_events.recv().await {
+      if events.send(event).await.is_err() {
+        break;
+      }
+    }
+  });
+  Ok(ForwardTask {
+    task,
+    events: rx,
+    traffic,
+    addresses,
+  })
+}
+
+/// kubectl's internal port for one mapping, shown as the remote port it leads to
+#[derive(Clone)]
+struct Backend {
+  port: u16,
+  remote: String,
+}
+
+impl fmt::Display for Backend {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.remote)
+  }
+}
+
+struct Kubectl<E> {
+  executor: E,
+  builder: KubectlCommandBuilder,
+  /// The forward with the internal ports as its local ones
+  config: PortForwardConfig,
+  ready: Regex,
+}
+
+impl<E: CommandExecutor> Kubectl<E> {
+  /// Start kubectl, resolving selectors again, and wait until it listens
+  async fn start(&self) -> Result<(KillOnDrop, mpsc::Receiver<ProcessEvent>)> {
+    let resolved = self
+      .builder
+      .resolve_target(&self.executor, &self.config)
+      .await?;
+    let (program, args, env) = self.builder.build_port_forward_command(&resolved);
+    let (handle, output) = self.executor.spawn(&program, &args, &env).await?;
+    let process = KillOnDrop(handle.pid);
+    let (_, output) =
+      custom_command::wait_until_ready(output, &self.ready, dependencies::READY_TIMEOUT)
+        .await
+        .map_err(|e| AppError::PortForward(format!("kubectl {}", e)))?;
+    Ok((process, output))
+  }
+
+  /// Restart kubectl, backing off while it keeps failing, until aborted
+  async fn supervise(
+    self,
+    mut process: KillOnDrop,
+    mut output: mpsc::Receiver<ProcessEvent>,
+    events: mpsc::Sender<ProcessEvent>,
+  ) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+      let started = Instant::now();
+      let reason = watch(&mut output, &events).await;
+      drop(process);
+      if started.elapsed() >= STABLE_AFTER {
+        backoff = Duration::from_secs(1);
+      }
+      let mut message = format!("{}; restarting it", reason);
+      loop {
+        send(&events, format!("{} in {}s", message, backoff.as_secs())).await;
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        match self.start().await {
+          Ok(started) => {
+            (process, output) = started;
+            break;
+          }
+          Err(e) => message = format!("Unable to restart kubectl: {}; retrying", e),
+        }
+      }
+    }
+  }
+}
+
+/// Pass kubectl's errors on until it exits or loses its pod, and say which
+async fn watch(
+  output: &mut mpsc::Receiver<ProcessEvent>,
+  events: &mpsc::Sender<ProcessEvent>,
+) -> String {
+  while let Some(event) = output.recv().await {
+    match event {
+      // Its ports are internal; the proxy reports the local ones
+      ProcessEvent::Stdout(_) => {}
+      ProcessEvent::Stderr(line) => {
+        // kubectl may keep running after the pod is gone
+        let lost = String::from_utf8_lossy(&line).contains("lost connection to pod");
+        let _ = events.send(ProcessEvent::Stderr(line)).await;
+        if lost {
+          return "kubectl lost connection to the pod".to_string();
+        }
+      }
+      ProcessEvent::Error(e) => return format!("kubectl failed: {}", e),
+      ProcessEvent::Terminated { code: Some(code) } => {
+        return format!("kubectl exited with code {}", code)
+      }
+      ProcessEvent::Terminated { code: None } => break,
+    }
+  }
+  "kubectl exited".to_string()
+}
+
+/// Relay a client to kubectl, waiting out a restart in progress
+async fn relay(mut socket: Connection, backend: Backend, events: mpsc::Sender<ProcessEvent>) {
+  let deadline = Instant::now() + RECONNECT_WAIT;
+  let mut upstream = loop {
+    match TcpStream::connect((Ipv4Addr::LOCALHOST, backend.port)).await {
+      Ok(upstream) => break upstream,
+      Err(_) if Instant::now() < deadline => {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+      }
+      Err(e) => {
+        let line = format!("kubectl is not accepting connections: {}", e);
+        let _ = events.send(ProcessEvent::Stderr(line.into_bytes())).await;
+        return;
+      }
+    }
+  };
+  let _ = tokio::io::copy_bidirectional(&mut socket, &mut upstream).await;
+}
+
+/// A loopback port nothing listens on right now, for kubectl to bind
+async fn free_port() -> Result<u16> {
+  Ok(
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+      .await?
+      .local_addr()?
+      .port(),
+  )
+}
+
+async fn send(events: &mpsc::Sender<ProcessEvent>, line: String) {
+  let _ = events.send(ProcessEvent::Stderr(line.into_bytes())).await;
+}
+
+/// Stops the supervisor and relays along with the forward's task
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+  fn drop(&mut self) {
+    self.0.abort();
+  }
+}
+
+/// Kills kubectl when it is replaced or the forward is stopped
+struct KillOnDrop(u32);
+
+impl Drop for KillOnDrop {
+  fn drop(&mut self) {
+    // `kill 0` would signal our whole process group
+    if self.0 != 0 {
+      let _ = ProcessManager::kill_process(self.0);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::traits::{ProcessHandle, ProcessOutput};
+  use async_trait::async_trait;
+  use std::sync::{Arc, Mutex};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+  /// A started fake kubectl: its output channel and echo server
+  type Started = (mpsc::Sender<ProcessEvent>, AbortHandle);
+
+  /// Stands in for kubectl with an echo server on the internal port
+  #[derive(Clone, Default)]
+  struct FakeKubectl(Arc<Mutex<Vec<Started>>>);
+
+  #[async_trait]
+  impl CommandExecutor for FakeKubectl {
+    async fn execute(
+      &self,
+      _: &str,
+      _: &[String],
+      _: &[(String, String)],
+    ) -> Result<ProcessOutput> {
+      Err(AppError::Kubectl("not a selector".to_string()))
+    }
+
+    async fn spawn(
+      &self,
+      _: &str,
+      args: &[String],
+      _: &[(String, String)],
+    ) -> Result<(ProcessHandle, mpsc::Receiver<ProcessEvent>)> {
+      let port = args
+        .iter()
+        .find_map(|arg| arg.strip_suffix(":80")?.parse::<u16>().ok())
+        .ok_or_else(|| AppError::InvalidInput("no internal port".to_string()))?;
+      let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+      let echo = tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+          tokio::spawn(async move {
+            let (mut reader, mut writer) = socket.split();
+            let _ = tokio::io::copy(&mut reader, &mut writer).await;
+          });
+        }
+      });
+      // A real process, so killing the replaced kubectl is harmless
+      let child = tokio::process::Command::new("sleep")
+        .arg("30")
+        .kill_on_drop(true)
+        .spawn()?;
+      let pid = child.id().unwrap_or_default();
+      let (tx, rx) = mpsc::channel(10);
+      let line = format!("Forwarding from 127.0.0.1:{} -> 80", port);
+      let _ = tx.send(ProcessEvent::Stdout(line.into_bytes())).await;
+      tokio::spawn(async move {
+        let mut child = child;
+        let _ = child.wait().await;
+      });
+      self
+        .0
+        .lock()
+        .map_err(|_| AppError::System("poisoned".to_string()))?
+        .push((tx, echo.abort_handle()));
+      Ok((ProcessHandle { pid }, rx))
+    }
+
+    fn spawn_task<F>(&self, future: F)
+    where
+      F: std::future::Future<Output = ()> + Send + 'static,
+    {
+      tokio::spawn(future);
+    }
+  }
+
+  async fn echo(port: u16, message: &[u8]) -> Result<Vec<u8>> {
+    let mut client = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await?;
+    client.write_all(message).await?;
+    let mut reply = vec![0; message.len()];
+    client.read_exact(&mut reply).await?;
+    Ok(reply)
+  }
+
+  #[tokio::test]
+  async fn keeps_the_local_port_while_kubectl_restarts() -> Result<()> {
+    let local = free_port().await?;
+    let config = PortForwardConfig {
+      name: "web".to_string(),
+      context: "dev".to_string(),
+      namespace: "default".to_string(),
+      service: "svc/web".to_string(),
+      ports: vec![format!("{}:80", local)],
+      proxy: true,
+      ..Default::default()
+    };
+    let kubectl = FakeKubectl::default();
+    let builder = KubectlCommandBuilder::new("kubectl".to_string(), None);
+    let forward = start(kubectl.clone(), builder, &config).await?;
+    assert_eq!(echo(local, b"ping").await?, b"ping");
+
+    let crashed = kubectl
+      .0
+      .lock()
+      .ok()
+      .and_then(|started| started.first().cloned());
+    let (output, server) = crashed.ok_or_else(|| AppError::System("not started".to_string()))?;
+    server.abort();
+    let _ = output
+      .send(ProcessEvent::Terminated { code: Some(1) })
+      .await;
+
+    // Accepted straight away and relayed once kubectl is back
+    assert_eq!(echo(local, b"pong").await?, b"pong");
+    assert_eq!(kubectl.0.lock().map(|started| started.len()).ok(), Some(2));
+    forward.task.abort();
+    Ok(())
+  }
+}
@@ -1,7 +1,8 @@
 use crate::error::{AppError, Result};
+use crate::services::FileLock;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -38,18 +39,34 @@ impl LastActiveSet {
     let Some(ref path) = self.file_path else {
       return Ok(());
     };
-    if !path.exists() {
-      return Ok(());
-    }
-    let json = std::fs::read_to_string(path)
-      .map_err(|e| AppError::System(format!("Failed to read last-active file: {}", e)))?;
-    let state: LastActiveState = serde_json::from_str(&json)
-      .map_err(|e| AppError::System(format!("Failed to parse last-active file: {}", e)))?;
+    let names = read_names(path)?;
     let mut guard = self
       .inner
       .lock()
       .map_err(|_| AppError::System("Failed to acquire last-active lock".to_string()))?;
-    *guard = state.names;
+    *guard = names;
+    Ok(())
+  }
+
+  /// Apply `change` in memory and, if it changed anything, to the file's current
+  /// contents under the file lock, keeping names other frontends saved meanwhile
+  fn update(&self, change: impl Fn(&mut BTreeSet<String>) -> bool) -> Result<()> {
+    let mut guard = self
+      .inner
+      .lock()
+      .map_err(|_| AppError::System("Failed to acquire last-active lock".to_string()))?;
+    if !change(&mut guard) {
+      return Ok(());
+    }
+    let Some(ref path) = self.file_path else {
+      return Ok(());
+    };
+
+    let _lock = FileLock::acquire(path)?;
+    let mut names = read_names(path)?;
+    change(&mut names);
+    self.save_locked(&names)?;
+    *guard = names;
     Ok(())
   }
 
@@ -57,10 +74,6 @@ impl LastActiveSet {
     let Some(ref path) = self.file_path else {
       return Ok(());
     };
-    if let Some(parent) = path.parent() {
-      std::fs::create_dir_all(parent)
-        .map_err(|e| AppError::System(format!("Failed to create state dir: {}", e)))?;
-    }
     let state = LastActiveState {
       names: names.clone(),
     };
@@ -75,37 +88,21 @@ impl LastActiveSet {
   }
 
   pub fn add(&self, name: &str) -> Result<()> {
-    let mut guard = self
-      .inner
-      .lock()
-      .map_err(|_| AppError::System("Failed to acquire last-active lock".to_string()))?;
-    if guard.insert(name.to_string()) {
-      self.save_locked(&guard)?;
-    }
-    Ok(())
+    self.update(|names| names.insert(name.to_string()))
   }
 
   pub fn remove(&self, name: &str) -> Result<()> {
-    let mut guard = self
-      .inner
-      .lock()
-      .map_err(|_| AppError::System("Failed to acquire last-active lock".to_string()))?;
-    if guard.remove(name) {
-      self.save_locked(&guard)?;
-    }
-    Ok(())
+    self.update(|names| names.remove(name))
   }
 
   pub fn rename(&self, old: &str, new: &str) -> Result<()> {
-    let mut guard = self
-      .inner
-      .lock()
-      .map_err(|_| AppError::System("Failed to acquire last-active lock".to_string()))?;
-    if guard.remove(old) {
-      guard.insert(new.to_string());
-      self.save_locked(&guard)?;
-    }
-    Ok(())
+    self.update(|names| {
+      let renamed = names.remove(old);
+      if renamed {
+        names.insert(new.to_string());
+      }
+      renamed
+    })
   }
 
   pub fn names(&self) -> Result<Vec<String>> {
@@ -117,6 +114,17 @@ impl LastActiveSet {
   }
 }
 
+fn read_names(path: &Path) -> Result<BTreeSet<String>> {
+  if !path.exists() {
+    return Ok(BTreeSet::new());
+  }
+  let json = std::fs::read_to_string(path)
+    .map_err(|e| AppError::System(format!("Failed to read last-active file: {}", e)))?;
+  let state: LastActiveState = serde_json::from_str(&json)
+    .map_err(|e| AppError::System(format!("Failed to parse last-active file: {}", e)))?;
+  Ok(state.names)
+}
+
 impl Default for LastActiveSet {
   fn default() -> Self {
     Self::new()
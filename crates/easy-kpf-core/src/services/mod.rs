@@ -1,15 +1,49 @@
 pub mod command_builder;
 pub mod config_cache;
+pub mod config_migrations;
 pub mod config_service;
+pub mod config_watcher;
+pub mod custom_command;
+pub mod dependencies;
+pub mod exporter;
+pub mod file_lock;
+pub mod history;
+pub mod host_keys;
+pub mod importer;
+pub mod in_process;
 pub mod interface;
+pub mod kubectl_proxy;
 pub mod last_active;
+#[cfg(feature = "native")]
+pub mod native_forward;
+pub mod port_check;
+pub mod port_owner;
 pub mod process_detector;
 pub mod process_manager;
+pub mod profiles;
+pub mod ssh_master;
+pub mod tcp_relay;
+pub mod template;
+pub mod traffic;
+pub mod validation;
 
 pub use command_builder::{KubectlCommandBuilder, SshCommandBuilder};
 pub use config_cache::ConfigCache;
-pub use config_service::ConfigService;
+pub use config_service::{ConfigService, LoadedConfigs};
+pub use config_watcher::{ConfigWatcher, ConfigsChanged};
+pub use exporter::ExportFormat;
+pub use file_lock::FileLock;
+pub use history::{ConfigHistory, Snapshot, SnapshotDiff, SnapshotInfo};
+pub use host_keys::{HostKey, HostKeyStore};
+pub use importer::{CommandImporter, ImportReport};
+pub use in_process::ForwardTask;
 pub use interface::{InterfaceManager, SystemInterfaceManager};
 pub use last_active::LastActiveSet;
+pub use port_check::{PortReassignment, PreparedPorts};
+pub use port_owner::{OwnerKind, PortConflict, PortOwner};
 pub use process_detector::ProcessDetector;
 pub use process_manager::ProcessManager;
+pub use profiles::{ProfileStatus, Profiles};
+pub use template::Variables;
+pub use traffic::{Traffic, TrafficStats};
+pub use validation::{ConfigField, FieldError, ValidationErrors};
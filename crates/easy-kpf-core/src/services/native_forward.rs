@@ -0,0 +1,348 @@
+use crate::error::{AppError, Result};
+use crate::services::in_process::{self, ForwardTask};
+use crate::services::traffic::Connection;
+use crate::services::KubectlCommandBuilder;
+use crate::traits::ProcessEvent;
+use crate::types::{ForwardTarget, PortForwardConfig};
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use k8s_openapi::api::core::v1::{Pod, Service};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::api::{Api, ListParams};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::core::Selector;
+use kube::Client;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+/// API client for `context` (the current one when empty) from `kubeconfig`, which
+/// like `$KUBECONFIG` may list several files, or the default locations
+pub async fn client(kubeconfig: Option<&str>, context: &str) -> Result<Client> {
+  let mut merged: Option<Kubeconfig> = None;
+  for path in kubeconfig.map(std::env::split_paths).into_iter().flatten() {
+    let next = Kubeconfig::read_from(&path).map_err(|e| kubeconfig_error(&e))?;
+    merged = Some(match merged {
+      Some(config) => config.merge(next).map_err(|e| kubeconfig_error(&e))?,
+      None => next,
+    });
+  }
+  let mut kubeconfig = match merged {
+    Some(config) => config,
+    None => Kubeconfig::read().map_err(|e| kubeconfig_error(&e))?,
+  };
+
+  // Credential plugins run with our environment, which lacks the user's shell
+  // PATH when launched from Finder
+  let path = KubectlCommandBuilder::build_path_env();
+  let exec_configs = kubeconfig
+    .auth_infos
+    .iter_mut()
+    .filter_map(|user| user.auth_info.as_mut()?.exec.as_mut());
+  for exec in exec_configs {
+    let env = exec.env.get_or_insert_with(Vec::new);
+    if !env
+      .iter()
+      .any(|var| var.get("name").is_some_and(|n| n == "PATH"))
+    {
+      env.push(HashMap::from([
+        ("name".to_string(), "PATH".to_string()),
+        ("value".to_string(), path.clone()),
+      ]));
+    }
+  }
+
+  let options = KubeConfigOptions {
+    context: (!context.is_empty()).then(|| context.to_string()),
+    ..Default::default()
+  };
+  let config = kube::Config::from_custom_kubeconfig(kubeconfig, &options)
+    .await
+    .map_err(|e| kubeconfig_error(&e))?;
+  Client::try_from(config).map_err(|e| api_error(&e))
+}
+
+/// Resolve `config`'s target to a pod, listen on its local ports and forward each
+/// accepted connection over its own portforward stream, as kubectl does
+pub async fn start(client: Client, config: &PortForwardConfig) -> Result<ForwardTask> {
+  let pods: Api<Pod> = Api::namespaced(client.clone(), &config.namespace);
+  let (pod, service) = resolve_pod(&client, &pods, config).await?;
+  let pod_name = pod.metadata.name.clone().unwrap_or_default();
+
+  let mut listeners = Vec::new();
+  for mapping in &config.ports {
+    let (local, remote) = mapping.split_once(':').unwrap_or((mapping, mapping));
+    let remote = remote_port(remote, service.as_ref(), &pod)?;
+    listeners.push((in_process::listen(config, local).await?, remote));
+  }
+
+  Ok(in_process::serve(listeners, move |socket, port, events| {
+    forward_connection(socket, pods.clone(), pod_name.clone(), port, events)
+  }))
+}
+
+async fn forward_connection(
+  mut socket: Connection,
+  pods: Api<Pod>,
+  pod: String,
+  port: u16,
+  events: mpsc::Sender<ProcessEvent>,
+) {
+  let forwarded = async {
+    let mut forwarder = pods
+      .portforward(&pod, &[port])
+      .await
+      .map_err(|e| api_error(&e))?;
+    let mut upstream = forwarder
+      .take_stream(port)
+      .ok_or_else(|| AppError::PortForward("No stream for the port".to_string()))?;
+    let error = forwarder.take_error(port);
+    tokio::select! {
+      copied = tokio::io::copy_bidirectional(&mut socket, &mut upstream) => {
+        copied?;
+      }
+      Some(Some(message)) = async { Some(error?.await) } => {
+        return Err(AppError::PortForward(message));
+      }
+    }
+    drop(upstream);
+    forwarder
+      .join()
+      .await
+      .map_err(|e| AppError::PortForward(e.to_string()))
+  };
+  if let Err(e) = forwarded.await {
+    // Worded like kubectl's, so frontends recognise it as a broken forward
+    let line = format!("error forwarding port {} to pod {}: {}", port, pod, e);
+    let _ = events.send(ProcessEvent::Stderr(line.into_bytes())).await;
+  }
+}
+
+/// The pod to forward to, picked like kubectl does, with the service whose ports
+/// the mappings refer to
+async fn resolve_pod(
+  client: &Client,
+  pods: &Api<Pod>,
+  config: &PortForwardConfig,
+) -> Result<(Pod, Option<Service>)> {
+  let namespace = &config.namespace;
+  let (selector, service) = match config.target() {
+    ForwardTarget::Pod(name) => {
+      return Ok((pods.get(&name).await.map_err(|e| api_error(&e))?, None))
+    }
+    ForwardTarget::Selector(selector) => (selector, None),
+    ForwardTarget::Service(name) => {
+      let service = Api::<Service>::namespaced(client.clone(), namespace)
+        .get(&name)
+        .await
+        .map_err(|e| api_error(&e))?;
+      let match_labels = service.spec.as_ref().and_then(|s| s.selector.clone());
+      let selector = selector_string(LabelSelector {
+        match_labels,
+        ..Default::default()
+      })?;
+      (selector, Some(service))
+    }
+    ForwardTarget::Deployment(name) => {
+      let deployment = Api::<Deployment>::namespaced(client.clone(), namespace)
+        .get(&name)
+        .await
+        .map_err(|e| api_error(&e))?;
+      let selector = deployment.spec.map(|s| s.selector).unwrap_or_default();
+      (selector_string(selector)?, None)
+    }
+    ForwardTarget::StatefulSet(name) => {
+      let stateful_set = Api::<StatefulSet>::namespaced(client.clone(), namespace)
+        .get(&name)
+        .await
+        .map_err(|e| api_error(&e))?;
+      let selector = stateful_set.spec.map(|s| s.selector).unwrap_or_default();
+      (selector_string(selector)?, None)
+    }
+    ForwardTarget::Other(target) => {
+      return Err(AppError::InvalidInput(format!(
+        "Native forwards don't support {}; use svc/, pod/, deploy/, sts/ or selector/",
+        target
+      )))
+    }
+  };
+  // An empty selector lists every pod in the namespace
+  if selector.trim().is_empty() {
+    return Err(AppError::PortForward(format!(
+      "{} has no pod selector",
+      config.target()
+    )));
+  }
+
+  let candidates = pods
+    .list(&ListParams::default().labels(&selector))
+    .await
+    .map_err(|e| api_error(&e))?;
+  let pod = candidates.items.into_iter().find(is_ready).ok_or_else(|| {
+    AppError::PortForward(format!(
+      "No ready pod for {} in {}",
+      config.target(),
+      namespace
+    ))
+  })?;
+  Ok((pod, service))
+}
+
+/// Port on the pod a mapping's remote side refers to: a service port is
+/// translated to its target port, and names are looked up on the pod
+fn remote_port(remote: &str, service: Option<&Service>, pod: &Pod) -> Result<u16> {
+  let service_port = service
+    .and_then(|s| s.spec.as_ref()?.ports.as_ref())
+    .and_then(|ports| {
+      ports
+        .iter()
+        .find(|p| p.port.to_string() == remote || p.name.as_deref() == Some(remote))
+    });
+  let target = match service_port {
+    Some(port) => match port.target_port {
+      Some(IntOrString::Int(target)) => return to_port(target),
+      Some(IntOrString::String(ref name)) => name.clone(),
+      None => return to_port(port.port),
+    },
+    None => remote.to_string(),
+  };
+  if let Ok(port) = target.parse() {
+    return Ok(port);
+  }
+
+  let container_port = pod
+    .spec
+    .iter()
+    .flat_map(|spec| &spec.containers)
+    .flat_map(|container| container.ports.iter().flatten())
+    .find(|port| port.name.as_deref() == Some(target.as_str()))
+    .ok_or_else(|| AppError::PortForward(format!("Pod has no port named '{}'", target)))?;
+  to_port(container_port.container_port)
+}
+
+fn to_port(port: i32) -> Result<u16> {
+  u16::try_from(port).map_err(|_| AppError::PortForward(format!("Invalid port {}", port)))
+}
+
+fn is_ready(pod: &Pod) -> bool {
+  pod
+    .status
+    .iter()
+    .flat_map(|status| status.conditions.iter().flatten())
+    .any(|c| c.type_ == "Ready" && c.status == "True")
+}
+
+/// `matchLabels` and `matchExpressions` in kubectl's `-l` syntax
+fn selector_string(selector: LabelSelector) -> Result<String> {
+  Selector::try_from(selector)
+    .map(|selector| selector.to_string())
+    .map_err(|e| AppError::PortForward(format!("Unsupported pod selector: {}", e)))
+}
+
+fn api_error(e: &kube::Error) -> AppError {
+  AppError::Kubernetes(e.to_string())
+}
+
+fn kubeconfig_error(e: &kube::config::KubeconfigError) -> AppError {
+  AppError::Config(format!("Failed to load kubeconfig: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures::{SinkExt, StreamExt};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::{TcpListener, TcpStream};
+  use tokio_tungstenite::tungstenite::{handshake::server, http::HeaderValue, Message};
+
+  /// Serves pod `web` and accepts its portforward upgrades like the API server,
+  /// echoing what the client sends on the data channel
+  async fn fake_api_server(listener: TcpListener, port: u16) {
+    while let Ok((mut socket, _)) = listener.accept().await {
+      tokio::spawn(async move {
+        let mut head = [0; 4096];
+        let Ok(read) = socket.peek(&mut head).await else {
+          return;
+        };
+        let request = String::from_utf8_lossy(&head[..read]).to_ascii_lowercase();
+        if !request.contains("upgrade: websocket") {
+          let _ = socket.read(&mut head).await;
+          let pod = r#"{"apiVersion":"v1","kind":"Pod","metadata":{"name":"web"}}"#;
+          let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            pod.len(),
+            pod
+          );
+          let _ = socket.write_all(response.as_bytes()).await;
+          return;
+        }
+
+        // The error type is tungstenite's to pick
+        #[allow(clippy::result_large_err)]
+        let accept = |request: &server::Request, mut response: server::Response| {
+          assert!(request.uri().path().ends_with("/pods/web/portforward"));
+          response.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static("v4.channel.k8s.io"),
+          );
+          Ok(response)
+        };
+        let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(socket, accept).await else {
+          return;
+        };
+        // Each channel starts with the port it belongs to
+        let [lo, hi] = port.to_le_bytes();
+        for channel in [0, 1] {
+          let _ = ws.send(Message::binary(vec![channel, lo, hi])).await;
+        }
+        while let Some(Ok(Message::Binary(data))) = ws.next().await {
+          if data.first() == Some(&0) {
+            let _ = ws.send(Message::binary(data.to_vec())).await;
+          }
+        }
+      });
+    }
+  }
+
+  #[tokio::test]
+  async fn forwards_connections_through_the_portforward_subresource() -> Result<()> {
+    let api = TcpListener::bind("127.0.0.1:0").await?;
+    let api_url = format!("http://{}", api.local_addr()?);
+    tokio::spawn(fake_api_server(api, 80));
+    let uri = api_url
+      .parse()
+      .map_err(|_| AppError::System("bad url".to_string()))?;
+    let client = Client::try_from(kube::Config::new(uri)).map_err(|e| api_error(&e))?;
+
+    let free = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let local = free.local_addr()?.port();
+    drop(free);
+    let config = PortForwardConfig {
+      name: "web".to_string(),
+      namespace: "default".to_string(),
+      service: "pod/web".to_string(),
+      ports: vec![format!("{}:80", local)],
+      forward_type: crate::types::ForwardType::Native,
+      ..Default::default()
+    };
+
+    let mut forward = start(client, &config).await?;
+    let mut connection = TcpStream::connect(("127.0.0.1", local)).await?;
+    connection.write_all(b"ping").await?;
+    let mut echoed = [0; 4];
+    connection.read_exact(&mut echoed).await?;
+    assert_eq!(&echoed, b"ping");
+    let Some(ProcessEvent::Stdout(line)) = forward.events.recv().await else {
+      panic!("expected kubectl-style output");
+    };
+    assert_eq!(
+      String::from_utf8_lossy(&line),
+      format!("Forwarding from 127.0.0.1:{} -> 80", local)
+    );
+
+    forward.task.abort();
+    assert!(forward.task.await.is_err_and(|e| e.is_cancelled()));
+    assert!(TcpListener::bind(("127.0.0.1", local)).await.is_ok());
+    Ok(())
+  }
+}
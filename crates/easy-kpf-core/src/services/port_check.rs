@@ -0,0 +1,181 @@
+use crate::error::{AppError, Result};
+use crate::services::port_owner;
+use crate::services::validation::parse_interface;
+use crate::types::{ForwardType, PortForwardConfig, SshDirection};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener};
+
+/// How far past a busy port to look for a free one before asking the OS
+const FALLBACK_SEARCH_RANGE: u16 = 100;
+
+/// A busy local port that was replaced by a free one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortReassignment {
+  pub requested: u16,
+  pub actual: u16,
+}
+
+/// The config to actually start, with any busy ports swapped out
+#[derive(Debug, Clone)]
+pub struct PreparedPorts {
+  pub config: PortForwardConfig,
+  pub reassigned: Vec<PortReassignment>,
+}
+
+impl PreparedPorts {
+  /// e.g. "local port 8081 instead of 8080", empty when nothing changed
+  pub fn summary(&self) -> String {
+    self
+      .reassigned
+      .iter()
+      .map(|r| format!("local port {} instead of {}", r.actual, r.requested))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+}
+
+/// Try binding every local ip:port of `config` before anything is spawned.
+/// A busy port fails the start unless `config.auto_port` is set, in which case the
+/// next free port is used and reported.
+pub fn prepare_local_ports(config: &PortForwardConfig) -> Result<PreparedPorts> {
+  let (ip, port_override) = config
+    .local_interface
+    .as_deref()
+    .and_then(parse_interface)
+    .unwrap_or((IpAddr::V4(Ipv4Addr::LOCALHOST), None));
+  // Without an explicit interface kubectl binds both loopback addresses
+  let check_ipv6 = config.local_interface.is_none();
+
+  let mut prepared = PreparedPorts {
+    config: config.clone(),
+    reassigned: vec![],
+  };
+  let mut taken = Vec::new();
+
+  // Remote forwards bind nothing locally
+  if config.ssh_direction() == Some(SshDirection::Remote) {
+    return Ok(prepared);
+  }
+
+  // The SSH builder uses the port of an `ip:port` interface for every mapping
+  if let (Some(port), ForwardType::Ssh) = (port_override, &config.forward_type) {
+    let actual = ensure_port(config, ip, port, false, &[], &mut prepared.reassigned)?;
+    prepared.config.local_interface = Some(format!("{}:{}", ip, actual));
+    return Ok(prepared);
+  }
+
+  for mapping in prepared.config.ports.iter_mut() {
+    let (local, rest) = match mapping.split_once(':') {
+      Some((local, rest)) => (local.to_string(), Some(rest.to_string())),
+      None => (mapping.clone(), None),
+    };
+    // `:80` asks kubectl for a random local port
+    let Ok(port) = local.parse::<u16>() else {
+      continue;
+    };
+
+    let actual = ensure_port(
+      config,
+      ip,
+      port,
+      check_ipv6,
+      &taken,
+      &mut prepared.reassigned,
+    )?;
+    taken.push(actual);
+    if actual != port {
+      *mapping = format!("{}:{}", actual, rest.unwrap_or(local));
+    }
+  }
+
+  Ok(prepared)
+}
+
+/// Return `port` if it is free, otherwise a replacement (with `auto_port`) or an error
+fn ensure_port(
+  config: &PortForwardConfig,
+  ip: IpAddr,
+  port: u16,
+  check_ipv6: bool,
+  taken: &[u16],
+  reassigned: &mut Vec<PortReassignment>,
+) -> Result<u16> {
+  if is_free(ip, port, check_ipv6) {
+    return Ok(port);
+  }
+  if !config.auto_port {
+    let owner = port_owner::describe_listener(ip, port)
+      .map(|owner| format!(" by {}", owner))
+      .unwrap_or_default();
+    return Err(AppError::PortForward(format!(
+      "Local port {}:{} is already in use{}. Stop whatever is listening there, change the port, or enable free-port fallback for '{}'",
+      ip, port, owner, config.name
+    )));
+  }
+
+  let actual = find_free_port(ip, port, check_ipv6, taken)?;
+  log::info!(
+    "{}: local port {} is busy, using {} instead",
+    config.name,
+    port,
+    actual
+  );
+  reassigned.push(PortReassignment {
+    requested: port,
+    actual,
+  });
+  Ok(actual)
+}
+
+fn find_free_port(ip: IpAddr, busy: u16, check_ipv6: bool, taken: &[u16]) -> Result<u16> {
+  let nearby = (1..=FALLBACK_SEARCH_RANGE)
+    .filter_map(|offset| busy.checked_add(offset))
+    .find(|port| !taken.contains(port) && is_free(ip, *port, check_ipv6));
+  if let Some(port) = nearby {
+    return Ok(port);
+  }
+
+  // Let the OS pick an ephemeral port
+  let port = TcpListener::bind((ip, 0))?.local_addr()?.port();
+  Ok(port)
+}
+
+/// Only `AddrInUse` counts as busy: missing interface aliases or privileged
+/// ports are reported by the forwarding tool itself
+fn is_free(ip: IpAddr, port: u16, check_ipv6: bool) -> bool {
+  let bindable = |ip: IpAddr| match TcpListener::bind((ip, port)) {
+    Ok(_) => true,
+    Err(e) => e.kind() != ErrorKind::AddrInUse,
+  };
+  bindable(ip) && (!check_ipv6 || bindable(IpAddr::V6(Ipv6Addr::LOCALHOST)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rejects_or_replaces_busy_ports() -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let busy = listener.local_addr()?.port();
+
+    let mut config = PortForwardConfig {
+      name: "api".to_string(),
+      ports: vec![format!("{}:80", busy), ":9090".to_string()],
+      local_interface: Some("127.0.0.1".to_string()),
+      ..Default::default()
+    };
+    assert!(prepare_local_ports(&config).is_err());
+
+    config.auto_port = true;
+    let prepared = prepare_local_ports(&config)?;
+    let actual = prepared.reassigned[0].actual;
+    assert_ne!(actual, busy);
+    assert_eq!(
+      prepared.config.ports,
+      vec![format!("{}:80", actual), ":9090".to_string()]
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,377 @@
+use crate::error::{AppError, Result};
+use crate::services::validation::local_endpoints;
+use crate::services::{ProcessDetector, ProcessManager};
+use crate::types::PortForwardConfig;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// What a process listening on one of our ports is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OwnerKind {
+  /// A forward EasyKpf started and still tracks
+  Managed {
+    name: String,
+  },
+  /// A kubectl port-forward matching one of our configs that nothing tracks,
+  /// e.g. left behind by a crash or started by hand
+  Orphan {
+    name: String,
+  },
+  Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortOwner {
+  pub pid: u32,
+  pub command: String,
+  pub kind: OwnerKind,
+}
+
+/// A local address of a config that another process is listening on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortConflict {
+  pub address: String,
+  pub owner: PortOwner,
+}
+
+impl PortConflict {
+  /// e.g. "127.0.0.1:8080 is used by pid 4242 (orphaned forward for 'api'): kubectl ..."
+  pub fn describe(&self) -> String {
+    let kind = match &self.owner.kind {
+      OwnerKind::Managed { name } => format!(" (EasyKpf forward '{}')", name),
+      OwnerKind::Orphan { name } => format!(" (orphaned forward for '{}')", name),
+      OwnerKind::Other => String::new(),
+    };
+    format!(
+      "{} is used by pid {}{}: {}",
+      self.address, self.owner.pid, kind, self.owner.command
+    )
+  }
+}
+
+/// Processes listening on the local addresses `config` would bind, other than
+/// the forward for `config` itself
+pub fn find_conflicts(
+  config: &PortForwardConfig,
+  configs: &[PortForwardConfig],
+  process_manager: &ProcessManager,
+) -> Result<Vec<PortConflict>> {
+  let managed = process_manager.get_running_services_with_pids()?;
+  let tasks = process_manager.task_addresses()?;
+  let detector = ProcessDetector::new();
+  let mut conflicts: Vec<PortConflict> = Vec::new();
+
+  for (ip, port) in local_endpoints(config) {
+    let Some(pid) = find_listener(ip, port)? else {
+      continue;
+    };
+    // In-process forwards all run under our own pid; the listener's address
+    // tells which of them holds the port
+    let managed_name = if pid == std::process::id() {
+      tasks
+        .iter()
+        .find(|(_, addresses)| {
+          addresses
+            .iter()
+            .any(|a| a.port() == port && addresses_overlap(ip, a.ip()))
+        })
+        .map(|(name, _)| name)
+    } else {
+      managed
+        .iter()
+        .find(|(_, p)| *p == pid)
+        .map(|(name, _)| name)
+    };
+    if managed_name == Some(&config.name) {
+      continue;
+    }
+
+    let command = command_line(pid);
+    let kind = if let Some(name) = managed_name {
+      OwnerKind::Managed { name: name.clone() }
+    } else if let Some(orphan) = configs
+      .iter()
+      .find(|c| detector.matches_command(&command, c))
+    {
+      OwnerKind::Orphan {
+        name: orphan.name.clone(),
+      }
+    } else {
+      OwnerKind::Other
+    };
+    if conflicts
+      .iter()
+      .any(|c| c.owner.pid == pid && c.owner.kind == kind)
+    {
+      continue;
+    }
+
+    conflicts.push(PortConflict {
+      address: format!("{}:{}", ip, port),
+      owner: PortOwner { pid, command, kind },
+    });
+  }
+
+  Ok(conflicts)
+}
+
+/// Track an orphaned forward for `config` as if EasyKpf had started it.
+/// Returns the adopted pid.
+pub fn adopt(
+  config: &PortForwardConfig,
+  conflicts: &[PortConflict],
+  process_manager: &ProcessManager,
+) -> Result<u32> {
+  let orphan = OwnerKind::Orphan {
+    name: config.name.clone(),
+  };
+  let owner = conflicts
+    .iter()
+    .map(|c| &c.owner)
+    .find(|o| o.kind == orphan)
+    .ok_or_else(|| {
+      AppError::InvalidInput(format!(
+        "No orphaned forward for '{}' to adopt",
+        config.name
+      ))
+    })?;
+
+  process_manager.add_process(config.name.clone(), owner.pid, config.clone())?;
+  Ok(owner.pid)
+}
+
+/// Kill every owner in `conflicts`, forgetting managed forwards first so they are
+/// not reported as crashed. Returns the killed pids.
+pub fn kill_owners(
+  conflicts: &[PortConflict],
+  process_manager: &ProcessManager,
+) -> Result<Vec<u32>> {
+  let mut killed = Vec::new();
+  for conflict in conflicts {
+    if let OwnerKind::Managed { name } = &conflict.owner.kind {
+      process_manager.remove_process(name)?;
+    }
+    // An in-process forward listens in this very process; removing it stopped it
+    if conflict.owner.pid != std::process::id() {
+      ProcessManager::kill_process(conflict.owner.pid)?;
+    }
+    killed.push(conflict.owner.pid);
+  }
+  Ok(killed)
+}
+
+/// Short "pid N: command" description of whatever listens on ip:port, for error messages
+pub fn describe_listener(ip: IpAddr, port: u16) -> Option<String> {
+  let pid = find_listener(ip, port).ok()??;
+  let mut command = command_line(pid);
+  if command.chars().count() > 60 {
+    command = format!("{}...", command.chars().take(57).collect::<String>());
+  }
+  Some(format!("pid {}: {}", pid, command))
+}
+
+/// Pid of the process with a TCP socket listening on ip:port (or on an address
+/// overlapping it, like 0.0.0.0). None when nothing listens or the owner is not
+/// visible to us, e.g. it belongs to another user.
+#[cfg(target_os = "linux")]
+pub fn find_listener(ip: IpAddr, port: u16) -> Result<Option<u32>> {
+  const TCP_LISTEN: &str = "0A";
+
+  let mut inodes = Vec::new();
+  for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+    let Ok(content) = std::fs::read_to_string(table) else {
+      continue;
+    };
+    for line in content.lines().skip(1) {
+      let fields: Vec<&str> = line.split_whitespace().collect();
+      let (Some(local), Some(state), Some(inode)) = (fields.get(1), fields.get(3), fields.get(9))
+      else {
+        continue;
+      };
+      let Some((listen_ip, listen_port)) = parse_proc_address(local) else {
+        continue;
+      };
+      if *state == TCP_LISTEN && listen_port == port && addresses_overlap(ip, listen_ip) {
+        inodes.push(format!("socket:[{}]", inode));
+      }
+    }
+  }
+  if inodes.is_empty() {
+    return Ok(None);
+  }
+
+  for entry in std::fs::read_dir("/proc")?.flatten() {
+    let Some(pid) = entry
+      .file_name()
+      .to_str()
+      .and_then(|n| n.parse::<u32>().ok())
+    else {
+      continue;
+    };
+    let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+      continue;
+    };
+    let owns_socket = fds.flatten().any(|fd| {
+      std::fs::read_link(fd.path())
+        .is_ok_and(|target| inodes.iter().any(|i| target.as_os_str() == i.as_str()))
+    });
+    if owns_socket {
+      return Ok(Some(pid));
+    }
+  }
+  Ok(None)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn find_listener(ip: IpAddr, port: u16) -> Result<Option<u32>> {
+  let output = std::process::Command::new("lsof")
+    .args(["-nP", "-sTCP:LISTEN", "-Fpn"])
+    .arg(format!("-iTCP:{}", port))
+    .output()
+    .map_err(|e| AppError::System(format!("Failed to run lsof: {}", e)))?;
+
+  // -F output: a `p<pid>` line followed by `n<address>:<port>` lines
+  let mut pid = None;
+  for line in String::from_utf8_lossy(&output.stdout).lines() {
+    if let Some(p) = line.strip_prefix('p') {
+      pid = p.parse::<u32>().ok();
+    } else if let Some(address) = line.strip_prefix('n') {
+      let host = address
+        .rsplit_once(':')
+        .map_or(address, |(host, _)| host)
+        .trim_matches(['[', ']']);
+      let listen_ip = match host {
+        "*" => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        _ => host.parse().unwrap_or(ip),
+      };
+      if addresses_overlap(ip, listen_ip) {
+        return Ok(pid);
+      }
+    }
+  }
+  Ok(None)
+}
+
+#[cfg(not(unix))]
+pub fn find_listener(_ip: IpAddr, _port: u16) -> Result<Option<u32>> {
+  Ok(None)
+}
+
+/// Full command line of `pid`, or an empty string if it is gone
+fn command_line(pid: u32) -> String {
+  #[cfg(target_os = "linux")]
+  {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+      .map(|raw| {
+        raw
+          .split(|b| *b == 0)
+          .filter(|arg| !arg.is_empty())
+          .map(|arg| String::from_utf8_lossy(arg).into_owned())
+          .collect::<Vec<_>>()
+          .join(" ")
+      })
+      .unwrap_or_default()
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  {
+    std::process::Command::new("ps")
+      .args(["-o", "command=", "-p", &pid.to_string()])
+      .output()
+      .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+      .unwrap_or_default()
+  }
+}
+
+/// `0100007F:1F90` style addresses from /proc/net/tcp{,6}, stored as host-order words
+#[cfg(target_os = "linux")]
+fn parse_proc_address(value: &str) -> Option<(IpAddr, u16)> {
+  let (address, port) = value.split_once(':')?;
+  let port = u16::from_str_radix(port, 16).ok()?;
+
+  let mut bytes = Vec::with_capacity(16);
+  for word in 0..address.len() / 8 {
+    let word = u32::from_str_radix(address.get(word * 8..word * 8 + 8)?, 16).ok()?;
+    bytes.extend_from_slice(&word.to_ne_bytes());
+  }
+
+  let ip = match bytes.len() {
+    4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+    16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+    _ => return None,
+  };
+  Some((ip, port))
+}
+
+/// Whether a listener on `b` takes the port away from `a`
+fn addresses_overlap(a: IpAddr, b: IpAddr) -> bool {
+  let normalize = |ip: IpAddr| match ip {
+    IpAddr::V6(v6) if v6 == Ipv6Addr::LOCALHOST => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+    IpAddr::V4(_) => ip,
+  };
+  a.is_unspecified() || b.is_unspecified() || normalize(a) == normalize(b)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use super::*;
+  use crate::services::in_process;
+  use std::net::TcpListener;
+
+  #[test]
+  fn finds_our_own_listener() -> Result<()> {
+    assert_eq!(
+      parse_proc_address(&format!("{:08X}:1F90", u32::from_ne_bytes([127, 0, 0, 1]))),
+      Some((IpAddr::V4(Ipv4Addr::LOCALHOST), 8080))
+    );
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    assert_eq!(
+      find_listener(IpAddr::V4(Ipv4Addr::LOCALHOST), port)?,
+      Some(std::process::id())
+    );
+    assert_eq!(
+      find_listener(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), port)?,
+      None
+    );
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn tells_in_process_forwards_apart_by_address() -> Result<()> {
+    let process_manager = ProcessManager::new();
+    let mut ports = Vec::new();
+    for name in ["first", "second"] {
+      let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+      ports.push(listener.local_addr()?.port());
+      let forward = in_process::serve(
+        vec![(listener, "remote".to_string())],
+        |_, _: String, _| async {},
+      );
+      let pid = process_manager.attach_task(name, &forward)?;
+      process_manager.add_process(name.to_string(), pid, PortForwardConfig::default())?;
+    }
+
+    let config = PortForwardConfig {
+      name: "new".to_string(),
+      ports: vec![format!("{}:80", ports[1])],
+      ..Default::default()
+    };
+    let conflicts = find_conflicts(&config, &[], &process_manager)?;
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(
+      conflicts[0].owner.kind,
+      OwnerKind::Managed {
+        name: "second".to_string()
+      }
+    );
+
+    kill_owners(&conflicts, &process_manager)?;
+    assert!(process_manager.contains_process("first")?);
+    assert!(!process_manager.contains_process("second")?);
+    Ok(())
+  }
+}
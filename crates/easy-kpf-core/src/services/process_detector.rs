@@ -1,5 +1,6 @@
 use crate::error::{AppError, Result};
-use crate::types::{ForwardType, PortForwardConfig};
+use crate::services::{custom_command, importer, SshCommandBuilder};
+use crate::types::{ForwardTarget, ForwardType, PortForwardConfig};
 use std::process::Command;
 
 pub struct ProcessDetector;
@@ -15,7 +16,11 @@ impl ProcessDetector {
     }
 
     let process_lines = self.get_process_list()?;
-    Ok(self.find_matching_line(&process_lines, config).is_some())
+    Ok(
+      process_lines
+        .lines()
+        .any(|line| self.matches_kubectl_command(line, config)),
+    )
   }
 
   pub fn find_kubectl_process_pid(&self, config: &PortForwardConfig) -> Result<Option<u32>> {
@@ -25,13 +30,14 @@ impl ProcessDetector {
 
     let process_lines = self.get_process_list()?;
     Ok(
-      self
-        .find_matching_line(&process_lines, config)
+      process_lines
+        .lines()
+        .find(|line| self.matches_kubectl_command(line, config))
         .and_then(|line| self.extract_pid_from_ps_line(line)),
     )
   }
 
-  /// Detect all running kubectl/ssh processes matching configs in a single `ps aux` call.
+  /// Detect all running kubectl/ssh/command processes matching configs in a single `ps aux` call.
   /// Returns a vec of (config_name, pid) for each matched process.
   pub fn detect_running_processes(
     &self,
@@ -41,9 +47,6 @@ impl ProcessDetector {
     let mut results = Vec::new();
 
     for config in configs {
-      if config.forward_type != ForwardType::Kubectl {
-        continue;
-      }
       if let Some(line) = self.find_matching_line(&process_lines, config) {
         if let Some(pid) = self.extract_pid_from_ps_line(line) {
           results.push((config.name.clone(), pid));
@@ -61,7 +64,21 @@ impl ProcessDetector {
   ) -> Option<&'a str> {
     process_lines
       .lines()
-      .find(|line| self.matches_kubectl_command(line, config))
+      .find(|line| self.matches_command(line, config))
+  }
+
+  /// Whether a `ps` line or command line is the forward process for `config`
+  pub fn matches_command(&self, process_line: &str, config: &PortForwardConfig) -> bool {
+    match config.forward_type {
+      // A proxied forward's kubectl belongs to the EasyKpf task serving it
+      ForwardType::Kubectl => {
+        !config.is_proxied() && self.matches_kubectl_command(process_line, config)
+      }
+      ForwardType::Ssh => self.matches_ssh_command(process_line, config),
+      ForwardType::Command => self.matches_custom_command(process_line, config),
+      // Runs inside an EasyKpf process rather than a command of its own
+      ForwardType::Native | ForwardType::Tcp => false,
+    }
   }
 
   pub fn is_process_actually_running(&self, pid: u32) -> Result<bool> {
@@ -106,7 +123,8 @@ impl ProcessDetector {
     }
   }
 
-  fn matches_kubectl_command(&self, process_line: &str, config: &PortForwardConfig) -> bool {
+  /// Whether a `ps` line or command line is a kubectl port-forward for `config`
+  pub fn matches_kubectl_command(&self, process_line: &str, config: &PortForwardConfig) -> bool {
     // Check if this is a kubectl port-forward command
     if !process_line.contains("kubectl") || !process_line.contains("port-forward") {
       return false;
@@ -117,8 +135,8 @@ impl ProcessDetector {
       return false;
     }
 
-    // Check service name
-    if !process_line.contains(&config.service) {
+    // Check target under any spelling kubectl accepts (svc/x, service/x, ...)
+    if !self.matches_target(process_line, &config.target()) {
       return false;
     }
 
@@ -126,12 +144,58 @@ impl ProcessDetector {
     self.matches_any_port(process_line, &config.ports)
   }
 
+  /// Whether a `ps` line or command line is an ssh forward for `config`, in the
+  /// same direction with the same mappings
+  pub fn matches_ssh_command(&self, process_line: &str, config: &PortForwardConfig) -> bool {
+    let mut args = process_line.split_whitespace();
+    if !args.any(|arg| arg == "ssh" || arg.ends_with("/ssh")) {
+      return false;
+    }
+    let args: Vec<&str> = args.collect();
+    if !args.contains(&config.service.as_str()) {
+      return false;
+    }
+
+    let forwards = SshCommandBuilder::forward_args(config);
+    !forwards.is_empty()
+      && forwards
+        .chunks(2)
+        .all(|option| args.windows(2).any(|w| w == option))
+  }
+
+  /// Whether a `ps` line or command line runs `config`'s command with its args
+  pub fn matches_custom_command(&self, process_line: &str, config: &PortForwardConfig) -> bool {
+    if config.command.program.is_empty() {
+      return false;
+    }
+    let (program, args, _) = custom_command::build_command(config);
+    let name = custom_command::program_name(&program);
+    let suffix = format!("/{}", name);
+    let mut words = process_line.split_whitespace();
+    // Scripts show up behind their interpreter, e.g. `python3 /usr/local/bin/aws`
+    if !words.any(|word| word == name || word.ends_with(&suffix)) {
+      return false;
+    }
+    let rest = words.collect::<Vec<_>>().join(" ");
+    args.iter().all(|arg| rest.contains(arg.as_str()))
+  }
+
   fn matches_namespace(&self, process_line: &str, namespace: &str) -> bool {
     process_line.contains(&format!("-n {}", namespace))
       || process_line.contains(&format!("--namespace={}", namespace))
       || process_line.contains(&format!("--namespace {}", namespace))
   }
 
+  /// Selector targets are resolved to a pod at start time, so the original
+  /// selector never appears in the command line and cannot be matched.
+  fn matches_target(&self, process_line: &str, target: &ForwardTarget) -> bool {
+    let args: Vec<&str> = process_line.split_whitespace().collect();
+    let Some(actual) = importer::kubectl_target(&args) else {
+      return false;
+    };
+    target.command_aliases().iter().any(|alias| alias == actual)
+  }
+
   fn matches_any_port(&self, process_line: &str, ports: &[String]) -> bool {
     ports.iter().any(|port| process_line.contains(port))
   }
@@ -178,3 +242,26 @@ impl Default for ProcessDetector {
     Self::new()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_pods_by_the_port_forward_target_only() {
+    let config = PortForwardConfig {
+      name: "api".to_string(),
+      namespace: "api".to_string(),
+      service: "pod/api".to_string(),
+      ports: vec!["8080:80".to_string()],
+      ..Default::default()
+    };
+    let detector = ProcessDetector::new();
+
+    let own = "me 42 kubectl --context dev -n api port-forward api 8080:80";
+    assert!(detector.matches_kubectl_command(own, &config));
+    let other =
+      "me 43 kubectl --context api -n api port-forward --address 127.0.0.1 pod/web 8080:80";
+    assert!(!detector.matches_kubectl_command(other, &config));
+  }
+}
@@ -1,14 +1,27 @@
 use crate::error::{AppError, Result};
-use crate::services::ProcessDetector;
-use crate::types::{PortForwardConfig, ProcessInfo, ProcessManagerState, SerializableProcessInfo};
+use crate::services::{ssh_master, FileLock, ForwardTask, ProcessDetector, Traffic, TrafficStats};
+use crate::types::{
+  PortForwardConfig, ProcessInfo, ProcessManagerState, SerializableProcessInfo, SshMaster,
+};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use tokio::task::AbortHandle;
+
+/// A forward running as a task inside this process
+struct Task {
+  abort: AbortHandle,
+  traffic: Arc<Traffic>,
+  addresses: Vec<SocketAddr>,
+}
 
 #[derive(Clone)]
 pub struct ProcessManager {
   processes: Arc<Mutex<HashMap<String, ProcessInfo>>>,
+  masters: Arc<Mutex<HashMap<String, SshMaster>>>,
+  tasks: Arc<Mutex<HashMap<String, Task>>>,
   state_file_path: Option<PathBuf>,
 }
 
@@ -16,6 +29,8 @@ impl ProcessManager {
   pub fn new() -> Self {
     Self {
       processes: Arc::new(Mutex::new(HashMap::new())),
+      masters: Arc::new(Mutex::new(HashMap::new())),
+      tasks: Arc::new(Mutex::new(HashMap::new())),
       state_file_path: None,
     }
   }
@@ -23,6 +38,8 @@ impl ProcessManager {
   pub fn with_state_file(state_file_path: PathBuf) -> Self {
     Self {
       processes: Arc::new(Mutex::new(HashMap::new())),
+      masters: Arc::new(Mutex::new(HashMap::new())),
+      tasks: Arc::new(Mutex::new(HashMap::new())),
       state_file_path: Some(state_file_path),
     }
   }
@@ -35,34 +52,79 @@ impl ProcessManager {
     }
   }
 
-  fn save_state(&self) -> Result<()> {
+  /// Write our entries for `names` to the state file under the file lock. Entries
+  /// other frontends sharing the file wrote are kept as they are.
+  fn save_state(&self, names: &[&str]) -> Result<()> {
     if let Some(ref path) = self.state_file_path {
-      let processes = self
-        .processes
-        .lock()
-        .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
-
-      let state = ProcessManagerState {
-        processes: processes
-          .iter()
-          .map(|(name, info)| (name.clone(), SerializableProcessInfo::from(info)))
-          .collect(),
-      };
+      let _lock = FileLock::acquire(path)?;
+      let mut state = read_state(path).unwrap_or_else(|e| {
+        log::warn!("Replacing unreadable state file: {}", e);
+        ProcessManagerState::default()
+      });
+
+      {
+        let processes = self
+          .processes
+          .lock()
+          .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
+        for name in names {
+          match processes.get(*name) {
+            Some(info) => {
+              state
+                .processes
+                .insert(name.to_string(), SerializableProcessInfo::from(info));
+            }
+            None => {
+              state.processes.remove(*name);
+            }
+          }
+        }
+      }
 
-      let json = serde_json::to_string_pretty(&state)
-        .map_err(|e| AppError::System(format!("Failed to serialize state: {}", e)))?;
+      write_state(path, &state)?;
+    }
+    Ok(())
+  }
 
-      // Atomic write: write to temp file then rename
-      let temp_path = path.with_extension("tmp");
-      std::fs::write(&temp_path, json)
-        .map_err(|e| AppError::System(format!("Failed to write temp state file: {}", e)))?;
+  /// Apply `update` to the master `key` as last saved, which other frontends may
+  /// have changed, and keep the result in memory and the state file
+  fn update_master(
+    &self,
+    key: &str,
+    update: impl FnOnce(&mut Option<SshMaster>),
+  ) -> Result<Option<SshMaster>> {
+    let lock = match self.state_file_path {
+      Some(ref path) => Some((FileLock::acquire(path)?, path)),
+      None => None,
+    };
+    let mut masters = self
+      .masters
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
 
-      std::fs::rename(&temp_path, path)
-        .map_err(|e| AppError::System(format!("Failed to rename state file: {}", e)))?;
+    let Some((_lock, path)) = lock else {
+      let mut master = masters.remove(key);
+      update(&mut master);
+      if let Some(ref master) = master {
+        masters.insert(key.to_string(), master.clone());
+      }
+      return Ok(master);
+    };
 
-      log::debug!("Saved process manager state to {:?}", path);
+    let mut state = read_state(path).unwrap_or_default();
+    let mut master = state.masters.remove(key);
+    update(&mut master);
+    match master {
+      Some(ref master) => {
+        state.masters.insert(key.to_string(), master.clone());
+        masters.insert(key.to_string(), master.clone());
+      }
+      None => {
+        masters.remove(key);
+      }
     }
-    Ok(())
+    write_state(path, &state)?;
+    Ok(master)
   }
 
   fn load_state(&self) -> Result<()> {
@@ -72,11 +134,7 @@ impl ProcessManager {
         return Ok(());
       }
 
-      let json = std::fs::read_to_string(path)
-        .map_err(|e| AppError::System(format!("Failed to read state file: {}", e)))?;
-
-      let state: ProcessManagerState = serde_json::from_str(&json)
-        .map_err(|e| AppError::System(format!("Failed to deserialize state: {}", e)))?;
+      let state = read_state(path)?;
 
       let mut processes = self
         .processes
@@ -111,11 +169,57 @@ impl ProcessManager {
         loaded_count,
         skipped_count
       );
+
+      let mut masters = self
+        .masters
+        .lock()
+        .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
+      for (key, master) in state.masters {
+        if process_detector
+          .is_process_actually_running(master.pid)
+          .unwrap_or(false)
+        {
+          masters.insert(key, master);
+        }
+      }
     }
     Ok(())
   }
 
+  /// Claim `name` for starting; hold the returned lock until the process is added.
+  /// Fails while another frontend is starting `name` or already runs it.
+  pub fn claim_start(&self, name: &str) -> Result<Option<FileLock>> {
+    let Some(ref path) = self.state_file_path else {
+      return Ok(None);
+    };
+    let Some(lock) = FileLock::try_acquire(&path.with_file_name("locks").join(lock_name(name)))?
+    else {
+      return Err(AppError::PortForward(format!(
+        "{} is already being started elsewhere",
+        name
+      )));
+    };
+
+    let ours = self.get_process_pid(name)?;
+    let theirs = read_state(path)
+      .ok()
+      .and_then(|state| state.processes.get(name).map(|info| info.pid));
+    if let Some(pid) = theirs.filter(|pid| Some(*pid) != ours) {
+      if ProcessDetector::new()
+        .is_process_actually_running(pid)
+        .unwrap_or(false)
+      {
+        return Err(AppError::PortForward(format!(
+          "{} is already running (PID {}) in another EasyKpf instance",
+          name, pid
+        )));
+      }
+    }
+    Ok(Some(lock))
+  }
+
   pub fn add_process(&self, name: String, pid: u32, config: PortForwardConfig) -> Result<()> {
+    let key = name.clone();
     {
       let mut processes = self
         .processes
@@ -131,25 +235,189 @@ impl ProcessManager {
       processes.insert(name, process_info);
     }
 
-    self.save_state()?;
+    self.save_state(&[&key])?;
     Ok(())
   }
 
+  /// Record that `name` runs as `forward`'s task inside this process, which is
+  /// aborted when the forward is removed. Returns the pid to register it under,
+  /// which is this process's own.
+  pub fn attach_task(&self, name: &str, forward: &ForwardTask) -> Result<u32> {
+    let task = Task {
+      abort: forward.task.abort_handle(),
+      traffic: forward.traffic.clone(),
+      addresses: forward.addresses.clone(),
+    };
+    self
+      .tasks
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?
+      .insert(name.to_string(), task);
+    Ok(std::process::id())
+  }
+
+  /// Local addresses of the forwards running as tasks, by name. They all share
+  /// this process's pid, so this is how their listeners are told apart.
+  pub fn task_addresses(&self) -> Result<Vec<(String, Vec<SocketAddr>)>> {
+    Ok(
+      self
+        .tasks
+        .lock()
+        .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?
+        .iter()
+        .map(|(name, task)| (name.clone(), task.addresses.clone()))
+        .collect(),
+    )
+  }
+
+  /// Connection counters of `name` when its connections pass through this
+  /// process, as they do for forwards running as tasks
+  pub fn traffic(&self, name: &str) -> Result<Option<TrafficStats>> {
+    Ok(
+      self
+        .tasks
+        .lock()
+        .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?
+        .get(name)
+        .map(|task| task.traffic.snapshot()),
+    )
+  }
+
+  /// Forget `name`. A task is aborted, and a multiplexed forward is taken off its
+  /// SSH master, which is closed once no forward uses it.
   pub fn remove_process(&self, name: &str) -> Result<Option<u32>> {
-    let result = {
+    let removed = {
       let mut processes = self
         .processes
         .lock()
         .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
 
-      processes.remove(name).map(|info| info.pid)
+      processes.remove(name)
+    };
+    let task = self
+      .tasks
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?
+      .remove(name);
+    if let Some(task) = task {
+      task.abort.abort();
+    }
+
+    self.save_state(&[name])?;
+    if let Some(ref info) = removed {
+      if info.config.is_multiplexed() {
+        self.release_master(name, &info.config)?;
+      }
+    }
+    Ok(removed.map(|info| info.pid))
+  }
+
+  /// Forget `name` and stop it: a task is aborted, a multiplexed forward is
+  /// cancelled on its SSH master, anything else is killed
+  pub fn stop_process(&self, name: &str) -> Result<Option<u32>> {
+    let Some(config) = self.get_process_config(name)? else {
+      return Ok(None);
     };
+    if config.runs_in_process() {
+      let ours = self
+        .tasks
+        .lock()
+        .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?
+        .contains_key(name);
+      if !ours {
+        // Killing its pid would take down the whole EasyKpf instance
+        return Err(AppError::Process(format!(
+          "{} runs inside another EasyKpf instance (PID {}); stop it there",
+          name,
+          self.get_process_pid(name)?.unwrap_or_default()
+        )));
+      }
+      return self.remove_process(name);
+    }
+    if !config.is_multiplexed() {
+      let pid = self.remove_process(name)?;
+      if let Some(pid) = pid {
+        Self::kill_process(pid)?;
+      }
+      return Ok(pid);
+    }
+
+    if let Some(master) = self.get_master(&ssh_master::master_key(&config))? {
+      // A master that already died took the forwards with it
+      if let Err(e) = ssh_master::cancel(&config, &master) {
+        log::warn!("[{}] {}", name, e);
+      }
+    }
+    self.remove_process(name)
+  }
+
+  /// Record that `name`'s forwards are open on the master `key`
+  pub fn attach_to_master(
+    &self,
+    key: &str,
+    pid: u32,
+    control_path: PathBuf,
+    name: &str,
+  ) -> Result<()> {
+    self.update_master(key, |master| {
+      // A different pid means the old master died and its forwards with it
+      let master = match master {
+        Some(master) if master.pid == pid => master,
+        _ => master.insert(SshMaster {
+          pid,
+          control_path,
+          forwards: Vec::new(),
+        }),
+      };
+      if !master.forwards.iter().any(|f| f == name) {
+        master.forwards.push(name.to_string());
+      }
+    })?;
+    Ok(())
+  }
+
+  /// Take `name` off its master, closing the master if it was the last forward
+  fn release_master(&self, name: &str, config: &PortForwardConfig) -> Result<()> {
+    let mut unused = None;
+    self.update_master(&ssh_master::master_key(config), |master| {
+      if let Some(m) = master {
+        m.forwards.retain(|f| f != name);
+        if m.forwards.is_empty() {
+          unused = master.take();
+        }
+      }
+    })?;
+    if let Some(master) = unused {
+      log::info!("Closing unused SSH master (PID: {})", master.pid);
+      let _ = Self::kill_process(master.pid);
+    }
+    Ok(())
+  }
+
+  pub fn get_master(&self, key: &str) -> Result<Option<SshMaster>> {
+    let masters = self
+      .masters
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
 
-    self.save_state()?;
-    Ok(result)
+    Ok(masters.get(key).cloned())
+  }
+
+  /// Shared SSH connections with the forwards open on them
+  pub fn get_masters(&self) -> Result<Vec<(String, SshMaster)>> {
+    let masters = self
+      .masters
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
+
+    let mut masters: Vec<_> = masters
+      .iter()
+      .map(|(key, master)| (key.clone(), master.clone()))
+      .collect();
+    masters.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(masters)
   }
 
-  #[allow(dead_code)]
   pub fn get_process_pid(&self, name: &str) -> Result<Option<u32>> {
     let processes = self
       .processes
@@ -159,6 +427,16 @@ impl ProcessManager {
     Ok(processes.get(name).map(|info| info.pid))
   }
 
+  /// The config a process was started with, including any reassigned local ports
+  pub fn get_process_config(&self, name: &str) -> Result<Option<PortForwardConfig>> {
+    let processes = self
+      .processes
+      .lock()
+      .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
+
+    Ok(processes.get(name).map(|info| info.config.clone()))
+  }
+
   pub fn contains_process(&self, name: &str) -> Result<bool> {
     let processes = self
       .processes
@@ -192,35 +470,65 @@ impl ProcessManager {
   }
 
   pub fn update_process_name(&self, old_name: &str, new_name: String) -> Result<()> {
-    {
+    let renamed = new_name.clone();
+    let multiplexed = {
       let mut processes = self
         .processes
         .lock()
         .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
 
-      if let Some(mut process_info) = processes.remove(old_name) {
+      processes.remove(old_name).map(|mut process_info| {
         process_info.config.name = new_name.clone();
+        let config = process_info.config.clone();
         processes.insert(new_name, process_info);
-      }
+        config
+      })
+    }
+    .filter(PortForwardConfig::is_multiplexed);
+
+    self.save_state(&[old_name, &renamed])?;
+    if let Some(config) = multiplexed {
+      self.update_master(&ssh_master::master_key(&config), |master| {
+        if let Some(master) = master {
+          for forward in master.forwards.iter_mut().filter(|f| *f == old_name) {
+            forward.clone_from(&renamed);
+          }
+        }
+      })?;
     }
-
-    self.save_state()?;
     Ok(())
   }
 
+  /// Forget every process and return the pids to kill. Tasks and multiplexed
+  /// forwards are stopped here instead: their pid is this process or a master
+  /// other frontends' forwards may share.
   pub fn cleanup_all(&self) -> Result<Vec<u32>> {
-    let pids = {
+    let (names, pids, shared) = {
       let mut processes = self
         .processes
         .lock()
         .map_err(|_| AppError::Process("Failed to acquire lock".to_string()))?;
 
-      let pids: Vec<u32> = processes.values().map(|info| info.pid).collect();
-      processes.clear();
-      pids
+      let (shared, names): (Vec<String>, Vec<String>) = processes
+        .keys()
+        .cloned()
+        .partition(|name| !has_own_process(&processes[name].config));
+      let pids: Vec<u32> = names
+        .iter()
+        .filter_map(|name| processes.remove(name))
+        .map(|info| info.pid)
+        .collect();
+      (names, pids, shared)
     };
 
-    self.save_state()?;
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    self.save_state(&names)?;
+    for name in shared {
+      if let Err(e) = self.stop_process(&name) {
+        log::warn!("[{}] {}", name, e);
+        self.remove_process(&name)?;
+      }
+    }
     Ok(pids)
   }
 
@@ -249,8 +557,85 @@ impl ProcessManager {
   }
 }
 
+/// Whether the forward's pid is a process of its own that may be killed
+fn has_own_process(config: &PortForwardConfig) -> bool {
+  !config.runs_in_process() && !config.is_multiplexed()
+}
+
+/// Atomic write: write to temp file then rename
+fn write_state(path: &Path, state: &ProcessManagerState) -> Result<()> {
+  let json = serde_json::to_string_pretty(state)
+    .map_err(|e| AppError::System(format!("Failed to serialize state: {}", e)))?;
+
+  let temp_path = path.with_extension("tmp");
+  std::fs::write(&temp_path, json)
+    .map_err(|e| AppError::System(format!("Failed to write temp state file: {}", e)))?;
+
+  std::fs::rename(&temp_path, path)
+    .map_err(|e| AppError::System(format!("Failed to rename state file: {}", e)))?;
+
+  log::debug!("Saved process manager state to {:?}", path);
+  Ok(())
+}
+
+fn read_state(path: &Path) -> Result<ProcessManagerState> {
+  if !path.exists() {
+    return Ok(ProcessManagerState::default());
+  }
+  let json = std::fs::read_to_string(path)
+    .map_err(|e| AppError::System(format!("Failed to read state file: {}", e)))?;
+  serde_json::from_str(&json)
+    .map_err(|e| AppError::System(format!("Failed to deserialize state: {}", e)))
+}
+
+/// Forward names are free-form; keep lock file names portable
+fn lock_name(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
+
 impl Default for ProcessManager {
   fn default() -> Self {
     Self::new()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn shares_the_state_file_and_refuses_double_starts() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("easykpf-state-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let state_file = dir.join("process-state.json");
+    let tui = ProcessManager::with_state_file(state_file.clone());
+    let app = ProcessManager::with_state_file(state_file.clone());
+
+    let pid = std::process::id();
+    tui.add_process("api".to_string(), pid, PortForwardConfig::default())?;
+    app.add_process("db".to_string(), pid, PortForwardConfig::default())?;
+    let mut saved: Vec<_> = read_state(&state_file)?.processes.into_keys().collect();
+    saved.sort();
+    assert_eq!(saved, vec!["api", "db"]);
+
+    assert!(app.claim_start("api").is_err());
+    let claim = tui.claim_start("api")?;
+    assert!(claim.is_some());
+    assert!(app.claim_start("api").is_err());
+    drop(claim);
+
+    tui.remove_process("api")?;
+    assert!(app.claim_start("api")?.is_some());
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
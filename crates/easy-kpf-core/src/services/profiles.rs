@@ -0,0 +1,148 @@
+use crate::error::{AppError, Result};
+use crate::types::PortForwardConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `profiles:` map of a config file: profile name to config names or `tag:<tag>` queries
+pub type Profiles = BTreeMap<String, Vec<String>>;
+
+/// Prefix of a profile entry that selects every config carrying a tag
+pub const TAG_QUERY_PREFIX: &str = "tag:";
+
+/// Which forwards of a profile are running
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileStatus {
+  pub name: String,
+  pub running: Vec<String>,
+  pub stopped: Vec<String>,
+}
+
+impl ProfileStatus {
+  pub fn is_running(&self) -> bool {
+    !self.running.is_empty() && self.stopped.is_empty()
+  }
+}
+
+/// Configs selected by profile `name`, in config-file order without duplicates
+pub fn resolve_profile(
+  profiles: &Profiles,
+  name: &str,
+  configs: &[PortForwardConfig],
+) -> Result<Vec<PortForwardConfig>> {
+  let entries = profiles
+    .get(name)
+    .ok_or_else(|| AppError::NotFound(format!("Profile '{}' not found", name)))?;
+
+  if let Some(missing) = entries
+    .iter()
+    .filter(|e| !e.starts_with(TAG_QUERY_PREFIX))
+    .find(|e| !configs.iter().any(|c| &c.name == *e))
+  {
+    return Err(AppError::NotFound(format!(
+      "Profile '{}' refers to unknown config '{}'",
+      name, missing
+    )));
+  }
+
+  Ok(
+    configs
+      .iter()
+      .filter(|c| entries.iter().any(|entry| selects(entry, c)))
+      .cloned()
+      .collect(),
+  )
+}
+
+/// Configs carrying `tag`, in config-file order
+pub fn tagged(configs: &[PortForwardConfig], tag: &str) -> Result<Vec<PortForwardConfig>> {
+  let members: Vec<PortForwardConfig> =
+    configs.iter().filter(|c| c.has_tag(tag)).cloned().collect();
+  if members.is_empty() {
+    return Err(AppError::NotFound(format!("No config is tagged '{}'", tag)));
+  }
+  Ok(members)
+}
+
+/// Status of every profile, given the names of running forwards
+pub fn profile_statuses(
+  profiles: &Profiles,
+  configs: &[PortForwardConfig],
+  running: &[String],
+) -> Vec<ProfileStatus> {
+  profiles
+    .keys()
+    .map(|name| {
+      let members = resolve_profile(profiles, name, configs).unwrap_or_default();
+      let (running, stopped): (Vec<_>, Vec<_>) = members
+        .into_iter()
+        .map(|c| c.name)
+        .partition(|n| running.contains(n));
+      ProfileStatus {
+        name: name.clone(),
+        running,
+        stopped,
+      }
+    })
+    .collect()
+}
+
+/// Profile names that refer to configs which do not exist, for load-time warnings
+pub fn check_profiles(profiles: &Profiles, configs: &[PortForwardConfig]) -> Vec<String> {
+  profiles
+    .keys()
+    .filter_map(|name| resolve_profile(profiles, name, configs).err())
+    .map(|e| e.to_string())
+    .collect()
+}
+
+fn selects(entry: &str, config: &PortForwardConfig) -> bool {
+  match entry.strip_prefix(TAG_QUERY_PREFIX) {
+    Some(tag) => config.has_tag(tag),
+    None => config.name == entry,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(name: &str, tags: &[&str]) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      tags: tags.iter().copied().map(String::from).collect(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn resolves_names_and_tag_queries() -> Result<()> {
+    let configs = vec![
+      config("api", &[]),
+      config("postgres", &["db"]),
+      config("redis", &["db", "cache"]),
+      config("grafana", &["observability"]),
+    ];
+    let profiles = Profiles::from([
+      (
+        "backend-dev".to_string(),
+        vec!["tag:db".to_string(), "api".to_string(), "redis".to_string()],
+      ),
+      ("broken".to_string(), vec!["missing".to_string()]),
+    ]);
+
+    let names: Vec<String> = resolve_profile(&profiles, "backend-dev", &configs)?
+      .into_iter()
+      .map(|c| c.name)
+      .collect();
+    assert_eq!(names, vec!["api", "postgres", "redis"]);
+    assert_eq!(tagged(&configs, "DB")?.len(), 2);
+    assert!(tagged(&configs, "queue").is_err());
+    assert!(resolve_profile(&profiles, "nope", &configs).is_err());
+    assert_eq!(check_profiles(&profiles, &configs).len(), 1);
+
+    let statuses = profile_statuses(&profiles, &configs, &["api".to_string()]);
+    assert_eq!(statuses[0].running, vec!["api"]);
+    assert_eq!(statuses[0].stopped, vec!["postgres", "redis"]);
+    Ok(())
+  }
+}
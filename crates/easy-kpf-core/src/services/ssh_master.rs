@@ -0,0 +1,254 @@
+use crate::error::{AppError, Result};
+use crate::services::{host_keys, FileLock, ProcessManager, SshCommandBuilder};
+use crate::types::{HostKeyPolicy, PortForwardConfig, SshMaster};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a new master gets to connect, jump hosts included
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Multiplexed forwards with the same key share a master: `[user@]host:port`,
+/// followed by the jump hosts, identity file and host key policy when set, since
+/// the master connects with the settings of whichever forward started it
+pub fn master_key(config: &PortForwardConfig) -> String {
+  let (host, port) = host_keys::ssh_target(config);
+  let user = config
+    .ssh
+    .user
+    .as_deref()
+    .or_else(|| config.service.rsplit_once('@').map(|(user, _)| user));
+  let mut key = match user {
+    Some(user) => format!("{}@{}:{}", user, host, port),
+    None => format!("{}:{}", host, port),
+  };
+
+  let ssh = &config.ssh;
+  if !ssh.jump_hosts.is_empty() {
+    key.push_str(&format!(" via {}", ssh.jump_hosts.join(",")));
+  }
+  if let Some(ref identity) = ssh.identity_file {
+    key.push_str(&format!(" as {}", identity));
+  }
+  let policy = ssh.host_key_policy.unwrap_or_default();
+  if policy != HostKeyPolicy::default() {
+    key.push_str(&format!(" (StrictHostKeyChecking={})", policy.ssh_option()));
+  }
+  key
+}
+
+/// Open `config`'s forwards on the master for its host, connecting one first when
+/// none is up. Returns the master's pid, which the forward is registered under.
+/// Blocks while connecting.
+pub fn attach(
+  builder: &SshCommandBuilder,
+  process_manager: &ProcessManager,
+  config: &PortForwardConfig,
+) -> Result<u32> {
+  let key = master_key(config);
+  create_control_dir(builder.control_dir())?;
+  let control_path = control_path(builder.control_dir(), &key);
+  // Forwards to the same host starting at once must not both connect a master
+  let _lock = FileLock::acquire(&control_path)?;
+
+  let running = check(config, &control_path);
+  let pid = match running {
+    Some(pid) => pid,
+    None => connect(builder, config, &control_path)?,
+  };
+  if let Err(e) = run(config, &control_path, "forward") {
+    if running.is_none() {
+      let _ = ProcessManager::kill_process(pid);
+    }
+    return Err(e);
+  }
+  process_manager.attach_to_master(&key, pid, control_path, &config.name)?;
+  Ok(pid)
+}
+
+/// Remove `config`'s forwards from `master`, leaving the connection up
+pub fn cancel(config: &PortForwardConfig, master: &SshMaster) -> Result<()> {
+  run(config, &master.control_path, "cancel")
+}
+
+/// Start a master and wait until its control socket accepts commands
+fn connect(
+  builder: &SshCommandBuilder,
+  config: &PortForwardConfig,
+  control_path: &Path,
+) -> Result<u32> {
+  // A master that died leaves its socket behind, and ssh won't replace it
+  let _ = fs::remove_file(control_path);
+  let log_path = control_path.with_extension("log");
+  let log = fs::File::create(&log_path)?;
+
+  let (program, args) = builder.build_master_command(config, control_path);
+  log::debug!("Starting SSH master: {} {}", program, args.join(" "));
+  let mut child = Command::new(program)
+    .args(args)
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(log)
+    .spawn()
+    .map_err(|e| AppError::PortForward(format!("Failed to start SSH: {}", e)))?;
+
+  let deadline = Instant::now() + CONNECT_TIMEOUT;
+  while check(config, control_path).is_none() {
+    if let Some(status) = child.try_wait()? {
+      let stderr = fs::read_to_string(&log_path).unwrap_or_default();
+      return Err(host_keys::classify_ssh_error(&stderr).unwrap_or_else(|| {
+        AppError::PortForward(format!(
+          "SSH connection to {} failed ({}): {}",
+          config.service,
+          status,
+          stderr.trim()
+        ))
+      }));
+    }
+    if Instant::now() >= deadline {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(AppError::PortForward(format!(
+        "SSH connection to {} timed out",
+        config.service
+      )));
+    }
+    std::thread::sleep(Duration::from_millis(200));
+  }
+
+  let pid = child.id();
+  // Reap the master once it exits so it doesn't linger as a zombie
+  std::thread::spawn(move || child.wait());
+  Ok(pid)
+}
+
+/// Pid of the master listening on `control_path`, if one is up
+fn check(config: &PortForwardConfig, control_path: &Path) -> Option<u32> {
+  let (program, args) = SshCommandBuilder::build_control_command(config, control_path, "check");
+  let output = Command::new(program).args(args).output().ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  // "Master running (pid=1234)"
+  String::from_utf8_lossy(&output.stderr)
+    .split("pid=")
+    .nth(1)?
+    .split(')')
+    .next()?
+    .trim()
+    .parse()
+    .ok()
+}
+
+fn run(config: &PortForwardConfig, control_path: &Path, operation: &str) -> Result<()> {
+  let (program, args) = SshCommandBuilder::build_control_command(config, control_path, operation);
+  let output = Command::new(program)
+    .args(args)
+    .output()
+    .map_err(|e| AppError::PortForward(format!("Failed to run ssh: {}", e)))?;
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(AppError::PortForward(format!(
+      "ssh -O {} failed: {}",
+      operation,
+      String::from_utf8_lossy(&output.stderr).trim()
+    )))
+  }
+}
+
+/// The sockets give access to the connections, so only the user may reach them
+fn create_control_dir(dir: &Path) -> Result<()> {
+  fs::create_dir_all(dir)?;
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+  }
+  Ok(())
+}
+
+/// Socket paths are limited to about 100 bytes, so the key is hashed (FNV-1a,
+/// stable across builds since every frontend must find the same socket)
+fn control_path(dir: &Path, key: &str) -> PathBuf {
+  let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+    (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+  });
+  dir.join(format!("{:016x}", hash))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::{ForwardType, SshSettings};
+
+  fn forward(name: &str, service: &str, port: &str) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      service: service.to_string(),
+      ports: vec![port.to_string()],
+      forward_type: ForwardType::Ssh,
+      ssh: SshSettings {
+        multiplex: true,
+        ..Default::default()
+      },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn shares_a_master_per_host_and_closes_it_with_the_last_forward() -> Result<()> {
+    let api = forward("api", "deploy@box", "8080");
+    let db = forward("db", "box", "5432");
+    assert_eq!(master_key(&api), "deploy@box:22");
+    assert_ne!(master_key(&api), master_key(&db));
+
+    // A forward reaching the host another way needs its own master
+    let mut via_bastion = api.clone();
+    via_bastion.ssh.jump_hosts = vec!["bastion".to_string()];
+    assert_eq!(master_key(&via_bastion), "deploy@box:22 via bastion");
+    let mut other_key = api.clone();
+    other_key.ssh.identity_file = Some("~/.ssh/ops".to_string());
+    other_key.ssh.host_key_policy = Some(HostKeyPolicy::Strict);
+    assert_eq!(
+      master_key(&other_key),
+      "deploy@box:22 as ~/.ssh/ops (StrictHostKeyChecking=yes)"
+    );
+    other_key.ssh.identity_file = None;
+    other_key.ssh.host_key_policy = Some(HostKeyPolicy::AcceptNew);
+    assert_eq!(master_key(&other_key), master_key(&api));
+
+    let dir = std::env::temp_dir().join(format!("easykpf-masters-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let path = control_path(&dir, &master_key(&api));
+    let (_, args) = SshCommandBuilder::build_control_command(&api, &path, "cancel");
+    assert!(args
+      .join(" ")
+      .ends_with("-O cancel -L 127.0.0.1:8080:localhost:8080 deploy@box"));
+
+    // Stand-in for the master: forwards are registered under its pid
+    let mut master = Command::new("sleep").arg("30").spawn()?;
+    let manager = ProcessManager::with_state_file(dir.join("process-state.json"));
+    let db = PortForwardConfig {
+      name: "db".to_string(),
+      ..api.clone()
+    };
+    for config in [&api, &db] {
+      manager.attach_to_master(&master_key(config), master.id(), path.clone(), &config.name)?;
+      manager.add_process(config.name.clone(), master.id(), config.clone())?;
+    }
+    let masters = manager.get_masters()?;
+    assert_eq!(masters.len(), 1);
+    assert_eq!(masters[0].1.forwards, vec!["api", "db"]);
+
+    manager.remove_process("api")?;
+    assert!(master.try_wait()?.is_none());
+    manager.remove_process("db")?;
+    assert!(manager.get_masters()?.is_empty());
+    assert!(master.wait()?.code().is_none());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+  }
+}
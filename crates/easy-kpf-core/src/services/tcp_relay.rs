@@ -0,0 +1,130 @@
+use crate::error::{AppError, Result};
+use crate::services::in_process::{self, ForwardTask};
+use crate::services::traffic::Connection;
+use crate::traits::ProcessEvent;
+use crate::types::PortForwardConfig;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// How long the remote host gets to accept a relayed connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listen on `config`'s local ports and relay each accepted connection to the
+/// mapping's remote port on the `service` host
+pub async fn start(config: &PortForwardConfig) -> Result<ForwardTask> {
+  let host = config.service.trim();
+  let mut listeners = Vec::new();
+  for mapping in &config.ports {
+    let (local, remote) = mapping.split_once(':').unwrap_or((mapping, mapping));
+    let port: u16 = remote
+      .parse()
+      .map_err(|_| AppError::InvalidInput(format!("Invalid remote port '{}'", remote)))?;
+    listeners.push((in_process::listen(config, local).await?, target(host, port)));
+  }
+  Ok(in_process::serve(listeners, relay))
+}
+
+/// `host:port`, bracketing IPv6 addresses
+pub(crate) fn target(host: &str, port: u16) -> String {
+  if host.contains(':') {
+    format!("[{}]:{}", host, port)
+  } else {
+    format!("{}:{}", host, port)
+  }
+}
+
+async fn relay(mut socket: Connection, target: String, events: mpsc::Sender<ProcessEvent>) {
+  let relayed = async {
+    let mut upstream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&target))
+      .await
+      .map_err(|_| AppError::PortForward("connection timed out".to_string()))??;
+    tokio::io::copy_bidirectional(&mut socket, &mut upstream).await?;
+    Ok::<_, AppError>(())
+  };
+  if let Err(e) = relayed.await {
+    let line = format!("error relaying to {}: {}", target, e);
+    let _ = events.send(ProcessEvent::Stderr(line.into_bytes())).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::ForwardType;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+
+  async fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port())
+  }
+
+  #[tokio::test]
+  async fn relays_connections_and_reports_unreachable_hosts() -> Result<()> {
+    let echo = TcpListener::bind("127.0.0.1:0").await?;
+    let echo_port = echo.local_addr()?.port();
+    tokio::spawn(async move {
+      while let Ok((mut socket, _)) = echo.accept().await {
+        tokio::spawn(async move {
+          let (mut reader, mut writer) = socket.split();
+          let _ = tokio::io::copy(&mut reader, &mut writer).await;
+        });
+      }
+    });
+    let closed = free_port().await?;
+
+    let local = free_port().await?;
+    let unreachable = free_port().await?;
+    let config = PortForwardConfig {
+      name: "db".to_string(),
+      service: "127.0.0.1".to_string(),
+      ports: vec![
+        format!("{}:{}", local, echo_port),
+        format!("{}:{}", unreachable, closed),
+      ],
+      forward_type: ForwardType::Tcp,
+      ..Default::default()
+    };
+
+    let mut relay = start(&config).await?;
+    let mut connection = TcpStream::connect(("127.0.0.1", local)).await?;
+    connection.write_all(b"ping").await?;
+    let mut echoed = [0; 4];
+    connection.read_exact(&mut echoed).await?;
+    assert_eq!(&echoed, b"ping");
+
+    let mut refused = TcpStream::connect(("127.0.0.1", unreachable)).await?;
+    assert_eq!(refused.read(&mut echoed).await?, 0);
+    let error = loop {
+      match relay.events.recv().await {
+        Some(ProcessEvent::Stderr(line)) => break String::from_utf8_lossy(&line).to_string(),
+        Some(_) => continue,
+        None => panic!("expected a relay error"),
+      }
+    };
+    assert!(error.starts_with(&format!("error relaying to 127.0.0.1:{}", closed)));
+
+    drop(connection);
+    let logged = loop {
+      match relay.events.recv().await {
+        Some(ProcessEvent::Stdout(line)) if line.ends_with(b"4 B in, 4 B out") => {
+          break String::from_utf8_lossy(&line).to_string()
+        }
+        Some(_) => continue,
+        None => panic!("expected the closed connection to be logged"),
+      }
+    };
+    assert!(logged.starts_with("Connection from 127.0.0.1:"));
+    let traffic = relay.traffic.snapshot();
+    assert_eq!(
+      (traffic.active_connections, traffic.total_connections),
+      (0, 2)
+    );
+    assert_eq!((traffic.bytes_in, traffic.bytes_out), (4, 4));
+
+    relay.task.abort();
+    assert!(relay.task.await.is_err_and(|e| e.is_cancelled()));
+    assert!(TcpListener::bind(("127.0.0.1", local)).await.is_ok());
+    Ok(())
+  }
+}
@@ -0,0 +1,241 @@
+use crate::services::validation::{ConfigField, FieldError, ValidationErrors};
+use crate::types::{CommandSettings, PortForwardConfig, SshSettings};
+use std::collections::BTreeMap;
+
+/// The `variables:` map of a config file
+pub type Variables = BTreeMap<String, String>;
+
+/// Whether any templated field of `config` contains a `${...}` placeholder
+pub fn has_placeholders(config: &PortForwardConfig) -> bool {
+  templated_fields(config)
+    .iter()
+    .any(|(_, value)| value.contains("${"))
+}
+
+/// Resolve `${VAR}` and `${VAR:-default}` in `context`, `namespace`, `service`,
+/// `ports`, `local_interface`, the SSH settings other than the port and the
+/// command's program, args and env values. The environment wins over
+/// `variables`, and an empty value counts as unset, like in the shell.
+pub fn expand_config(
+  config: &PortForwardConfig,
+  variables: &Variables,
+) -> std::result::Result<PortForwardConfig, ValidationErrors> {
+  if !has_placeholders(config) {
+    return Ok(config.clone());
+  }
+
+  let mut errors = Vec::new();
+  let mut expand_field = |field: ConfigField, value: &str| {
+    expand(value, variables).unwrap_or_else(|message| {
+      errors.push(FieldError::new(field, message));
+      value.to_string()
+    })
+  };
+
+  let expanded = PortForwardConfig {
+    context: expand_field(ConfigField::Context, &config.context),
+    namespace: expand_field(ConfigField::Namespace, &config.namespace),
+    service: expand_field(ConfigField::Service, &config.service),
+    ports: config
+      .ports
+      .iter()
+      .map(|port| expand_field(ConfigField::Ports, port))
+      .collect(),
+    local_interface: config
+      .local_interface
+      .as_deref()
+      .map(|interface| expand_field(ConfigField::LocalInterface, interface)),
+    ssh: SshSettings {
+      user: config
+        .ssh
+        .user
+        .as_deref()
+        .map(|user| expand_field(ConfigField::SshUser, user)),
+      identity_file: config
+        .ssh
+        .identity_file
+        .as_deref()
+        .map(|path| expand_field(ConfigField::IdentityFile, path)),
+      jump_hosts: config
+        .ssh
+        .jump_hosts
+        .iter()
+        .map(|host| expand_field(ConfigField::JumpHosts, host))
+        .collect(),
+      remote_host: config
+        .ssh
+        .remote_host
+        .as_deref()
+        .map(|host| expand_field(ConfigField::RemoteHost, host)),
+      ..config.ssh.clone()
+    },
+    command: CommandSettings {
+      program: expand_field(ConfigField::Command, &config.command.program),
+      args: config
+        .command
+        .args
+        .iter()
+        .map(|arg| expand_field(ConfigField::Command, arg))
+        .collect(),
+      env: config
+        .command
+        .env
+        .iter()
+        .map(|(key, value)| (key.clone(), expand_field(ConfigField::Command, value)))
+        .collect(),
+      ..config.command.clone()
+    },
+    ..config.clone()
+  };
+
+  if errors.is_empty() {
+    Ok(expanded)
+  } else {
+    Err(ValidationErrors(errors))
+  }
+}
+
+/// Expand every config, keeping unresolvable ones as they are. For matching
+/// running processes and clash checks, where one bad entry must not hide the rest.
+pub fn expand_configs_lenient(
+  configs: &[PortForwardConfig],
+  variables: &Variables,
+) -> Vec<PortForwardConfig> {
+  configs
+    .iter()
+    .map(|c| expand_config(c, variables).unwrap_or_else(|_| c.clone()))
+    .collect()
+}
+
+/// Expand placeholders in one string
+pub fn expand(value: &str, variables: &Variables) -> std::result::Result<String, String> {
+  let mut result = String::with_capacity(value.len());
+  let mut rest = value;
+
+  while let Some(start) = rest.find("${") {
+    result.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let end = after
+      .find('}')
+      .ok_or_else(|| format!("Unclosed '${{' in '{}'", value))?;
+    let body = &after[..end];
+
+    let (name, default) = match body.split_once(":-") {
+      Some((name, default)) => (name, Some(default)),
+      None => (body, None),
+    };
+    if !is_variable_name(name) {
+      return Err(format!("'${{{}}}' is not a valid placeholder", body));
+    }
+
+    let resolved = lookup(name, variables)
+      .or_else(|| default.map(str::to_string))
+      .ok_or_else(|| format!("Variable '{}' is not set and has no default", name))?;
+    result.push_str(&resolved);
+    rest = &after[end + 1..];
+  }
+
+  result.push_str(rest);
+  Ok(result)
+}
+
+fn lookup(name: &str, variables: &Variables) -> Option<String> {
+  std::env::var(name)
+    .ok()
+    .filter(|v| !v.is_empty())
+    .or_else(|| variables.get(name).filter(|v| !v.is_empty()).cloned())
+}
+
+fn is_variable_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn templated_fields(config: &PortForwardConfig) -> Vec<(ConfigField, &str)> {
+  let mut fields = vec![
+    (ConfigField::Context, config.context.as_str()),
+    (ConfigField::Namespace, config.namespace.as_str()),
+    (ConfigField::Service, config.service.as_str()),
+  ];
+  fields.extend(
+    config
+      .ports
+      .iter()
+      .map(|p| (ConfigField::Ports, p.as_str())),
+  );
+  if let Some(ref interface) = config.local_interface {
+    fields.push((ConfigField::LocalInterface, interface.as_str()));
+  }
+  let ssh = &config.ssh;
+  fields.extend(ssh.user.as_deref().map(|v| (ConfigField::SshUser, v)));
+  fields.extend(
+    ssh
+      .identity_file
+      .as_deref()
+      .map(|v| (ConfigField::IdentityFile, v)),
+  );
+  fields.extend(
+    ssh
+      .jump_hosts
+      .iter()
+      .map(|h| (ConfigField::JumpHosts, h.as_str())),
+  );
+  fields.extend(
+    ssh
+      .remote_host
+      .as_deref()
+      .map(|v| (ConfigField::RemoteHost, v)),
+  );
+  let command = &config.command;
+  fields.push((ConfigField::Command, command.program.as_str()));
+  fields.extend(
+    command
+      .args
+      .iter()
+      .chain(command.env.values())
+      .map(|v| (ConfigField::Command, v.as_str())),
+  );
+  fields
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn expands_variables_and_defaults() {
+    let variables = Variables::from([
+      ("EASYKPF_TEST_NS".to_string(), "dev-alice".to_string()),
+      ("EASYKPF_TEST_EMPTY".to_string(), String::new()),
+    ]);
+
+    let config = PortForwardConfig {
+      name: "api".to_string(),
+      context: "dev".to_string(),
+      namespace: "${EASYKPF_TEST_NS}".to_string(),
+      service: "svc/api-${EASYKPF_TEST_EMPTY:-main}".to_string(),
+      ports: vec!["${EASYKPF_TEST_PORT:-8080}:80".to_string()],
+      ..Default::default()
+    };
+    let expanded = expand_config(&config, &variables).unwrap_or_default();
+    assert_eq!(expanded.namespace, "dev-alice");
+    assert_eq!(expanded.service, "svc/api-main");
+    assert_eq!(expanded.ports, vec!["8080:80".to_string()]);
+
+    let missing = PortForwardConfig {
+      namespace: "${EASYKPF_TEST_MISSING}".to_string(),
+      ..config
+    };
+    let errors = expand_config(&missing, &variables)
+      .err()
+      .unwrap_or_default();
+    assert_eq!(
+      errors.for_field(ConfigField::Namespace),
+      Some("Variable 'EASYKPF_TEST_MISSING' is not set and has no default")
+    );
+    assert!(expand("${1BAD}", &variables).is_err());
+  }
+}
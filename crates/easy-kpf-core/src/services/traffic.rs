@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+/// Connection counters of a forward whose connections pass through EasyKpf
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrafficStats {
+  pub active_connections: u64,
+  pub total_connections: u64,
+  /// Received from clients
+  pub bytes_in: u64,
+  /// Sent to clients
+  pub bytes_out: u64,
+  /// Unix time in seconds of the last connection or transfer
+  pub last_activity: Option<u64>,
+}
+
+impl TrafficStats {
+  /// Time since the last connection or transfer
+  pub fn idle(&self) -> Option<Duration> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(now.saturating_sub(Duration::from_secs(self.last_activity?)))
+  }
+}
+
+impl fmt::Display for TrafficStats {
+  /// e.g. "2 open, 15 total, 1.2 KiB in, 40.0 KiB out"
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} open, {} total, {} in, {} out",
+      self.active_connections,
+      self.total_connections,
+      format_bytes(self.bytes_in),
+      format_bytes(self.bytes_out)
+    )
+  }
+}
+
+/// Live counters shared by a forward's connections
+#[derive(Debug, Default)]
+pub struct Traffic {
+  active: AtomicU64,
+  total: AtomicU64,
+  bytes: Bytes,
+  last_activity: AtomicU64,
+}
+
+impl Traffic {
+  pub fn snapshot(&self) -> TrafficStats {
+    let last_activity = self.last_activity.load(Ordering::Relaxed);
+    TrafficStats {
+      active_connections: self.active.load(Ordering::Relaxed),
+      total_connections: self.total.load(Ordering::Relaxed),
+      bytes_in: self.bytes.received.load(Ordering::Relaxed),
+      bytes_out: self.bytes.sent.load(Ordering::Relaxed),
+      last_activity: (last_activity > 0).then_some(last_activity),
+    }
+  }
+
+  /// Count a newly accepted connection, which counts its bytes here and in
+  /// `bytes`
+  pub(crate) fn open(self: &Arc<Self>, stream: TcpStream, bytes: Arc<Bytes>) -> Connection {
+    self.active.fetch_add(1, Ordering::Relaxed);
+    self.total.fetch_add(1, Ordering::Relaxed);
+    self.touch();
+    Connection {
+      stream,
+      traffic: self.clone(),
+      bytes,
+    }
+  }
+
+  pub(crate) fn close(&self) {
+    self.active.fetch_sub(1, Ordering::Relaxed);
+    self.touch();
+  }
+
+  fn touch(&self) {
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_secs());
+    self.last_activity.store(now, Ordering::Relaxed);
+  }
+}
+
+/// Bytes received from and sent to a client
+#[derive(Debug, Default)]
+pub(crate) struct Bytes {
+  received: AtomicU64,
+  sent: AtomicU64,
+}
+
+impl Bytes {
+  /// "1.2 KiB in, 40.0 KiB out"
+  pub(crate) fn summary(&self) -> String {
+    format!(
+      "{} in, {} out",
+      format_bytes(self.received.load(Ordering::Relaxed)),
+      format_bytes(self.sent.load(Ordering::Relaxed))
+    )
+  }
+}
+
+/// An accepted client connection counting what passes through it
+pub(crate) struct Connection {
+  stream: TcpStream,
+  traffic: Arc<Traffic>,
+  bytes: Arc<Bytes>,
+}
+
+impl Connection {
+  fn record(&self, count: usize, pick: fn(&Bytes) -> &AtomicU64) {
+    if count > 0 {
+      pick(&self.bytes).fetch_add(count as u64, Ordering::Relaxed);
+      pick(&self.traffic.bytes).fetch_add(count as u64, Ordering::Relaxed);
+      self.traffic.touch();
+    }
+  }
+}
+
+impl AsyncRead for Connection {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    let before = buf.filled().len();
+    let polled = Pin::new(&mut this.stream).poll_read(cx, buf);
+    this.record(buf.filled().len() - before, |b| &b.received);
+    polled
+  }
+}
+
+impl AsyncWrite for Connection {
+  fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    let polled = Pin::new(&mut this.stream).poll_write(cx, buf);
+    if let Poll::Ready(Ok(written)) = polled {
+      this.record(written, |b| &b.sent);
+    }
+    polled
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+  }
+
+  fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+    Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+  }
+}
+
+/// e.g. "512 B", "1.2 KiB", "3.4 MiB"
+pub fn format_bytes(bytes: u64) -> String {
+  const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+  if bytes < 1024 {
+    return format!("{} B", bytes);
+  }
+  let mut value = bytes as f64 / 1024.0;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// e.g. "850ms", "4.2s", "3m 12s"
+pub fn format_duration(duration: Duration) -> String {
+  if duration < Duration::from_secs(1) {
+    format!("{}ms", duration.as_millis())
+  } else if duration < Duration::from_secs(60) {
+    format!("{:.1}s", duration.as_secs_f64())
+  } else {
+    format!("{}m {}s", duration.as_secs() / 60, duration.as_secs() % 60)
+  }
+}
@@ -0,0 +1,431 @@
+use crate::services::template::{self, Variables};
+use crate::types::{CommandSettings, ForwardType, PortForwardConfig, SshDirection, SshSettings};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// Config field a validation error refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigField {
+  Name,
+  Context,
+  Namespace,
+  Service,
+  Ports,
+  LocalInterface,
+  SshUser,
+  SshPort,
+  IdentityFile,
+  JumpHosts,
+  RemoteHost,
+  /// `command.program`, `command.args` and `command.env`
+  Command,
+  ReadyPattern,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+  pub field: ConfigField,
+  pub message: String,
+}
+
+impl FieldError {
+  pub(crate) fn new(field: ConfigField, message: impl Into<String>) -> Self {
+    Self {
+      field,
+      message: message.into(),
+    }
+  }
+}
+
+/// All problems found in one config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl ValidationErrors {
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// First error for `field`, for showing next to a form input
+  pub fn for_field(&self, field: ConfigField) -> Option<&str> {
+    self
+      .0
+      .iter()
+      .find(|e| e.field == field)
+      .map(|e| e.message.as_str())
+  }
+}
+
+impl fmt::Display for ValidationErrors {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let messages: Vec<&str> = self.0.iter().map(|e| e.message.as_str()).collect();
+    write!(f, "{}", messages.join("; "))
+  }
+}
+
+/// Check a config on its own: required fields, port and interface syntax
+pub fn validate_config(config: &PortForwardConfig) -> ValidationErrors {
+  let mut errors = Vec::new();
+
+  if config.name.trim().is_empty() {
+    errors.push(FieldError::new(ConfigField::Name, "Name is required"));
+  }
+
+  match config.forward_type {
+    ForwardType::Kubectl | ForwardType::Native => {
+      if config.namespace.trim().is_empty() {
+        errors.push(FieldError::new(
+          ConfigField::Namespace,
+          "Namespace is required",
+        ));
+      }
+      if config.target().name().trim().is_empty() {
+        errors.push(FieldError::new(ConfigField::Service, "Target is required"));
+      }
+    }
+    ForwardType::Ssh => {
+      if config.service.trim().is_empty() {
+        errors.push(FieldError::new(
+          ConfigField::Service,
+          "SSH host is required",
+        ));
+      }
+      check_ssh_settings(&config.ssh, &mut errors);
+    }
+    ForwardType::Tcp => {
+      if config.service.trim().is_empty() {
+        errors.push(FieldError::new(ConfigField::Service, "Host is required"));
+      }
+    }
+    ForwardType::Command => {
+      check_command_settings(&config.command, &mut errors);
+      // The placeholders only carry one mapping
+      if config.ports.len() > 1 {
+        errors.push(FieldError::new(
+          ConfigField::Ports,
+          "Command forwards take a single port mapping",
+        ));
+      }
+    }
+  }
+
+  if config.ports.is_empty() {
+    errors.push(FieldError::new(
+      ConfigField::Ports,
+      "At least one port is required",
+    ));
+  }
+  for port in &config.ports {
+    if let Err(message) = check_port_mapping(port, config) {
+      errors.push(FieldError::new(ConfigField::Ports, message));
+    }
+  }
+
+  if let Some(ref interface) = config.local_interface {
+    if parse_interface(interface).is_none() {
+      errors.push(FieldError::new(
+        ConfigField::LocalInterface,
+        format!("'{}' is not an IP address or ip:port", interface),
+      ));
+    }
+  }
+
+  if errors.is_empty() {
+    if let Some(port) = duplicate(&local_endpoints(config)) {
+      errors.push(FieldError::new(
+        ConfigField::Ports,
+        format!("Local port {} is used twice", port.1),
+      ));
+    }
+  }
+
+  ValidationErrors(errors)
+}
+
+/// Check a config against the rest of the list, after resolving `${VAR}`
+/// placeholders. `replacing` is the name of the entry being edited, which is
+/// ignored for duplicate checks.
+pub fn validate_against(
+  config: &PortForwardConfig,
+  others: &[PortForwardConfig],
+  replacing: Option<&str>,
+  variables: &Variables,
+) -> ValidationErrors {
+  let expanded = match template::expand_config(config, variables) {
+    Ok(expanded) => expanded,
+    Err(errors) => return errors,
+  };
+  let config = &expanded;
+  let others = template::expand_configs_lenient(others, variables);
+
+  let mut errors = validate_config(config);
+  let others: Vec<&PortForwardConfig> = others
+    .iter()
+    .filter(|c| Some(c.name.as_str()) != replacing)
+    .collect();
+
+  if others.iter().any(|c| c.name == config.name) {
+    errors.0.push(FieldError::new(
+      ConfigField::Name,
+      format!("A config named '{}' already exists", config.name),
+    ));
+  }
+
+  let endpoints = local_endpoints(config);
+  for other in others {
+    let clash = local_endpoints(other)
+      .into_iter()
+      .find(|theirs| endpoints.iter().any(|ours| endpoints_clash(ours, theirs)));
+    if let Some((ip, port)) = clash {
+      errors.0.push(FieldError::new(
+        ConfigField::Ports,
+        format!("{}:{} is already used by '{}'", ip, port, other.name),
+      ));
+      break;
+    }
+  }
+
+  errors
+}
+
+/// Validate every config in a list, e.g. after loading hand-edited files
+pub fn validate_configs(
+  configs: &[PortForwardConfig],
+  variables: &Variables,
+) -> Vec<(String, ValidationErrors)> {
+  configs
+    .iter()
+    .enumerate()
+    .filter_map(|(i, config)| {
+      // Only compare with earlier entries so each clash is reported once
+      let errors = validate_against(config, &configs[..i], None, variables);
+      (!errors.is_empty()).then(|| (config.name.clone(), errors))
+    })
+    .collect()
+}
+
+fn check_command_settings(command: &CommandSettings, errors: &mut Vec<FieldError>) {
+  if command.program.trim().is_empty() {
+    errors.push(FieldError::new(ConfigField::Command, "Program is required"));
+  }
+  if let Some(ref pattern) = command.ready_pattern {
+    if let Err(e) = regex::Regex::new(pattern) {
+      errors.push(FieldError::new(
+        ConfigField::ReadyPattern,
+        format!("Invalid ready pattern: {}", e),
+      ));
+    }
+  }
+}
+
+fn check_ssh_settings(ssh: &SshSettings, errors: &mut Vec<FieldError>) {
+  // Each value ends up as a single ssh argument
+  let is_word = |value: &str| !value.is_empty() && !value.contains(char::is_whitespace);
+
+  if let Some(ref user) = ssh.user {
+    if !is_word(user) || user.contains('@') {
+      errors.push(FieldError::new(
+        ConfigField::SshUser,
+        format!("'{}' is not a valid user name", user),
+      ));
+    }
+  }
+  if ssh.port == Some(0) {
+    errors.push(FieldError::new(
+      ConfigField::SshPort,
+      "SSH port must be 1-65535",
+    ));
+  }
+  if let Some(ref identity_file) = ssh.identity_file {
+    if identity_file.trim().is_empty() {
+      errors.push(FieldError::new(
+        ConfigField::IdentityFile,
+        "Identity file path is empty",
+      ));
+    }
+  }
+  if let Some(host) = ssh
+    .jump_hosts
+    .iter()
+    .find(|h| !is_word(h) || h.contains(','))
+  {
+    errors.push(FieldError::new(
+      ConfigField::JumpHosts,
+      format!("'{}' is not a valid jump host", host),
+    ));
+  }
+  if let Some(ref host) = ssh.remote_host {
+    if !is_word(host) {
+      errors.push(FieldError::new(
+        ConfigField::RemoteHost,
+        format!("'{}' is not a valid host", host),
+      ));
+    }
+  }
+}
+
+/// `local[:remote]` for kubectl (local may be empty for a random port, remote may
+/// be a named port), plus `local:host:remote` for local SSH forwards. Dynamic SSH
+/// forwards take a single port.
+fn check_port_mapping(mapping: &str, config: &PortForwardConfig) -> Result<(), String> {
+  let parts: Vec<&str> = mapping.split(':').collect();
+  let invalid = || format!("'{}' is not a valid port mapping", mapping);
+  let forward_type = &config.forward_type;
+
+  let (local, remote) = match (parts.as_slice(), config.ssh_direction()) {
+    ([port], _) => (*port, *port),
+    ([local, remote], None) => (*local, *remote),
+    ([local, remote], Some(SshDirection::Local | SshDirection::Remote)) if !local.is_empty() => {
+      (*local, *remote)
+    }
+    ([local, host, remote], Some(SshDirection::Local)) if !host.is_empty() => (*local, *remote),
+    _ => return Err(invalid()),
+  };
+
+  if !local.is_empty() && parse_port(local).is_none() {
+    return Err(format!("{}: local port must be 1-65535", invalid()));
+  }
+  let named_remote = forward_type.is_kubernetes() && is_port_name(remote);
+  if parse_port(remote).is_none() && !named_remote {
+    return Err(format!("{}: remote port must be 1-65535", invalid()));
+  }
+  Ok(())
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+  value.parse::<u16>().ok().filter(|p| *p != 0)
+}
+
+/// Kubernetes named ports (IANA service names): e.g. `http`, `grpc-web`
+fn is_port_name(value: &str) -> bool {
+  (1..=15).contains(&value.len())
+    && value
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    && value.chars().any(|c| c.is_ascii_lowercase())
+    && !value.starts_with('-')
+    && !value.ends_with('-')
+}
+
+/// `ip` or `ip:port`; `localhost` is accepted like kubectl's `--address`
+pub(crate) fn parse_interface(value: &str) -> Option<(IpAddr, Option<u16>)> {
+  let parse_ip = |ip: &str| match ip {
+    "localhost" => Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+    _ => ip.parse::<IpAddr>().ok(),
+  };
+
+  if let Some(ip) = parse_ip(value) {
+    return Some((ip, None));
+  }
+  let (ip, port) = value.rsplit_once(':')?;
+  Some((parse_ip(ip)?, Some(parse_port(port)?)))
+}
+
+/// Local ip:port pairs a config binds, mirroring the command builders
+pub(crate) fn local_endpoints(config: &PortForwardConfig) -> Vec<(IpAddr, u16)> {
+  let (ip, port_override) = config
+    .local_interface
+    .as_deref()
+    .and_then(parse_interface)
+    .unwrap_or((IpAddr::V4(Ipv4Addr::LOCALHOST), None));
+  // Only the SSH builder applies the port from `ip:port`
+  let port_override = port_override.filter(|_| config.forward_type == ForwardType::Ssh);
+  // Remote forwards listen on the SSH host; locally they only connect out
+  if config.ssh_direction() == Some(SshDirection::Remote) {
+    return Vec::new();
+  }
+
+  config
+    .ports
+    .iter()
+    .filter_map(|mapping| {
+      let local = mapping.split(':').next()?;
+      port_override.or_else(|| parse_port(local))
+    })
+    .map(|port| (ip, port))
+    .collect()
+}
+
+fn endpoints_clash(a: &(IpAddr, u16), b: &(IpAddr, u16)) -> bool {
+  a.1 == b.1 && (a.0 == b.0 || a.0.is_unspecified() || b.0.is_unspecified())
+}
+
+fn duplicate(endpoints: &[(IpAddr, u16)]) -> Option<(IpAddr, u16)> {
+  endpoints
+    .iter()
+    .enumerate()
+    .find(|(i, a)| endpoints[..*i].iter().any(|b| endpoints_clash(a, b)))
+    .map(|(_, a)| *a)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(name: &str, ports: &[&str], interface: Option<&str>) -> PortForwardConfig {
+    PortForwardConfig {
+      name: name.to_string(),
+      context: "dev".to_string(),
+      namespace: "default".to_string(),
+      service: "svc/api".to_string(),
+      ports: ports.iter().copied().map(String::from).collect(),
+      local_interface: interface.map(str::to_string),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn reports_field_errors() {
+    let mut bad = config("api", &["8080:80", "70000", "abc:80"], Some("300.1.1.1"));
+    bad.namespace = String::new();
+
+    let errors = validate_config(&bad);
+    let fields: Vec<ConfigField> = errors.0.iter().map(|e| e.field).collect();
+    assert_eq!(
+      fields,
+      vec![
+        ConfigField::Namespace,
+        ConfigField::Ports,
+        ConfigField::Ports,
+        ConfigField::LocalInterface
+      ]
+    );
+
+    assert!(validate_config(&config("api", &[":80", "9000:http"], Some("127.0.0.2"))).is_empty());
+  }
+
+  #[test]
+  fn detects_duplicate_names_and_local_port_clashes() {
+    let vars = Variables::new();
+    let existing = vec![
+      config("api", &["8080:80"], None),
+      config("db", &["5432"], Some("127.0.0.2")),
+    ];
+
+    let errors = validate_against(&config("api", &["9090"], None), &existing, None, &vars);
+    assert_eq!(errors.for_field(ConfigField::Name).map(|_| ()), Some(()));
+
+    // Renaming an entry in place is not a duplicate
+    assert!(validate_against(
+      &config("web", &["8080:80"], None),
+      &existing,
+      Some("api"),
+      &vars
+    )
+    .is_empty());
+
+    let clash = validate_against(
+      &config("web", &["5432"], Some("0.0.0.0")),
+      &existing,
+      None,
+      &vars,
+    );
+    assert_eq!(
+      clash.for_field(ConfigField::Ports),
+      Some("127.0.0.2:5432 is already used by 'db'")
+    );
+    assert!(validate_against(&config("web", &["5432"], None), &existing, None, &vars).is_empty());
+  }
+}
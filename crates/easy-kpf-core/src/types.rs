@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::time::Instant;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct PortForwardConfig {
   pub name: String,
   pub context: String,
@@ -12,6 +14,62 @@ pub struct PortForwardConfig {
   pub local_interface: Option<String>,
   #[serde(default)]
   pub forward_type: ForwardType,
+  /// Connection settings for `ForwardType::Ssh`
+  #[serde(default, skip_serializing_if = "SshSettings::is_empty")]
+  pub ssh: SshSettings,
+  /// What a `ForwardType::Command` forward runs
+  #[serde(default, skip_serializing_if = "CommandSettings::is_empty")]
+  pub command: CommandSettings,
+  /// Keep a kubectl forward's local ports open in EasyKpf and run kubectl behind
+  /// them on internal ports, so restarting it never unbinds them
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub proxy: bool,
+  /// Use the next free local port instead of failing when a port is busy
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub auto_port: bool,
+  /// Free-form labels, selectable as `tag:<tag>` in profiles
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<String>,
+  /// Forwards that must be up before this one starts
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub depends_on: Vec<String>,
+  /// Which file the entry was loaded from. Never written to config files.
+  #[serde(default, skip_serializing_if = "ConfigSource::is_global")]
+  pub source: ConfigSource,
+}
+
+impl PortForwardConfig {
+  /// Parse the `service` field into a typed kubectl target.
+  /// Only meaningful for Kubernetes forwards; SSH and TCP configs store the host there.
+  pub fn target(&self) -> ForwardTarget {
+    ForwardTarget::parse(&self.service)
+  }
+
+  /// Direction of an SSH forward, `None` for kubectl
+  pub fn ssh_direction(&self) -> Option<SshDirection> {
+    (self.forward_type == ForwardType::Ssh).then_some(self.ssh.direction)
+  }
+
+  /// Whether this is an SSH forward opened on a shared master connection
+  pub fn is_multiplexed(&self) -> bool {
+    self.forward_type == ForwardType::Ssh && self.ssh.multiplex
+  }
+
+  /// Whether this is a kubectl forward behind EasyKpf's own listener
+  pub fn is_proxied(&self) -> bool {
+    self.forward_type == ForwardType::Kubectl && self.proxy
+  }
+
+  /// Whether the forward is a task inside EasyKpf rather than a process of its
+  /// own; a proxied forward's kubectl belongs to that task
+  pub fn runs_in_process(&self) -> bool {
+    self.forward_type.runs_in_process() || self.is_proxied()
+  }
+
+  /// Exact, case-insensitive tag match
+  pub fn has_tag(&self, tag: &str) -> bool {
+    self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -19,12 +77,296 @@ pub enum ForwardType {
   #[default]
   Kubectl,
   Ssh,
+  /// Kubernetes port-forward run inside EasyKpf through the API server, without
+  /// spawning kubectl
+  Native,
+  /// Plain TCP relay run inside EasyKpf from a local port to a port on the
+  /// `service` host
+  Tcp,
+  /// Any tunnelling tool, e.g. cloud-sql-proxy or `aws ssm start-session`, run
+  /// from `command`
+  Command,
+}
+
+impl ForwardType {
+  /// Whether the forward reaches a Kubernetes target through a context and namespace
+  pub fn is_kubernetes(&self) -> bool {
+    matches!(self, ForwardType::Kubectl | ForwardType::Native)
+  }
+
+  /// Whether the forward runs as a task inside EasyKpf rather than as a process
+  pub fn runs_in_process(&self) -> bool {
+    matches!(self, ForwardType::Native | ForwardType::Tcp)
+  }
+}
+
+/// Which way an SSH forward carries connections
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SshDirection {
+  /// `ssh -L`: local ports reach `remote_host` through the SSH host
+  #[default]
+  Local,
+  /// `ssh -R`: ports on the SSH host reach a local service
+  Remote,
+  /// `ssh -D`: a local SOCKS proxy into the SSH host's network
+  Dynamic,
+}
+
+impl SshDirection {
+  pub const ALL: [SshDirection; 3] = [Self::Local, Self::Remote, Self::Dynamic];
+
+  pub fn flag(self) -> &'static str {
+    match self {
+      Self::Local => "-L",
+      Self::Remote => "-R",
+      Self::Dynamic => "-D",
+    }
+  }
+
+  pub fn is_local(&self) -> bool {
+    *self == Self::Local
+  }
+}
+
+impl fmt::Display for SshDirection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::Local => "local",
+      Self::Remote => "remote",
+      Self::Dynamic => "dynamic",
+    })
+  }
+}
+
+/// How ssh treats host keys missing from or differing from EasyKpf's known_hosts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyPolicy {
+  /// Only connect to hosts whose key was pinned
+  Strict,
+  /// Pin the key of a host seen for the first time, refuse changed keys
+  #[default]
+  AcceptNew,
+  /// Don't check host keys
+  Off,
+}
+
+impl HostKeyPolicy {
+  /// Value for ssh's `StrictHostKeyChecking` option
+  pub fn ssh_option(self) -> &'static str {
+    match self {
+      Self::Strict => "yes",
+      Self::AcceptNew => "accept-new",
+      Self::Off => "no",
+    }
+  }
+}
+
+/// How to reach an SSH forward's host, which itself is stored in `service`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SshSettings {
+  #[serde(default, skip_serializing_if = "SshDirection::is_local")]
+  pub direction: SshDirection,
+  /// Login user (`ssh -l`); `user@host` in `service` works too
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub user: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub port: Option<u16>,
+  /// Private key (`ssh -i`)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub identity_file: Option<String>,
+  /// Bastions to hop through in order (`ssh -J`), each `[user@]host[:port]`
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub jump_hosts: Vec<String>,
+  /// Local forwards: where connections go, as seen from the SSH host. Defaults
+  /// to `localhost`; a `local:host:remote` port mapping overrides it per mapping.
+  /// Remote forwards: the address the SSH host listens on.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub remote_host: Option<String>,
+  /// Overrides `ssh_host_key_policy` from `app-config.yaml`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub host_key_policy: Option<HostKeyPolicy>,
+  /// Run the forwards over one master connection shared with every other
+  /// multiplexed forward to the same user, host and port
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub multiplex: bool,
+}
+
+impl SshSettings {
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+}
+
+/// Program a `ForwardType::Command` forward runs. `{local_port}`, `{remote_port}`
+/// and `{interface}` in the program, args and env values are replaced with the
+/// forward's port mapping and local interface.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CommandSettings {
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub program: String,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub args: Vec<String>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub env: BTreeMap<String, String>,
+  /// Regex matched against each output line; the forward counts as started only
+  /// once a line matches
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ready_pattern: Option<String>,
+}
+
+impl CommandSettings {
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+}
+
+/// Origin of a config entry
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(tag = "kind", content = "path", rename_all = "lowercase")]
+pub enum ConfigSource {
+  /// The user's `port-forwards.yaml` in the EasyKpf config dir
+  #[default]
+  Global,
+  /// A `.easykpf.yaml` found by walking up from the working directory
+  Project(std::path::PathBuf),
+  /// A shared fragment pulled in by an `include:` directive; never written back
+  Include(std::path::PathBuf),
+}
+
+impl ConfigSource {
+  pub fn is_global(&self) -> bool {
+    matches!(self, ConfigSource::Global)
+  }
+
+  pub fn is_read_only(&self) -> bool {
+    matches!(self, ConfigSource::Include(_))
+  }
+}
+
+/// Resource a kubectl port forward points at.
+///
+/// Stored in `PortForwardConfig::service` using kubectl's `kind/name` syntax so
+/// existing config files keep working, plus a `selector/` form for label selectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardTarget {
+  Service(String),
+  Pod(String),
+  Deployment(String),
+  StatefulSet(String),
+  /// First ready pod matching a label selector, resolved right before starting
+  Selector(String),
+  /// Any other `kind/name` (e.g. `rs/web`, `service.v1/api`), passed to kubectl as written
+  Other(String),
+}
+
+impl ForwardTarget {
+  /// Prefixes offered in suggestions, in display order
+  pub const PREFIXES: [&'static str; 5] = ["svc/", "pod/", "deploy/", "sts/", "selector/"];
+
+  /// Parse a target string. Bare names are pods, matching kubectl's behaviour.
+  pub fn parse(value: &str) -> Self {
+    let value = value.trim();
+    let Some((kind, name)) = value.split_once('/') else {
+      return ForwardTarget::Pod(value.to_string());
+    };
+    let name = name.to_string();
+
+    match kind.to_ascii_lowercase().as_str() {
+      "svc" | "service" | "services" => ForwardTarget::Service(name),
+      "po" | "pod" | "pods" => ForwardTarget::Pod(name),
+      "deploy" | "deployment" | "deployments" => ForwardTarget::Deployment(name),
+      "sts" | "statefulset" | "statefulsets" => ForwardTarget::StatefulSet(name),
+      "selector" | "label" | "l" => ForwardTarget::Selector(name),
+      _ => ForwardTarget::Other(value.to_string()),
+    }
+  }
+
+  pub fn name(&self) -> &str {
+    match self {
+      ForwardTarget::Service(name)
+      | ForwardTarget::Pod(name)
+      | ForwardTarget::Deployment(name)
+      | ForwardTarget::StatefulSet(name)
+      | ForwardTarget::Selector(name) => name,
+      ForwardTarget::Other(value) => value.split_once('/').map_or(value, |(_, name)| name),
+    }
+  }
+
+  /// Short kind label used in the UI
+  pub fn kind_label(&self) -> &'static str {
+    match self {
+      ForwardTarget::Service(_) => "svc",
+      ForwardTarget::Pod(_) => "pod",
+      ForwardTarget::Deployment(_) => "deploy",
+      ForwardTarget::StatefulSet(_) => "sts",
+      ForwardTarget::Selector(_) => "selector",
+      ForwardTarget::Other(_) => "other",
+    }
+  }
+
+  /// Resource type name as accepted by `kubectl get`
+  pub fn resource_type(&self) -> Option<&'static str> {
+    match self {
+      ForwardTarget::Service(_) => Some("service"),
+      ForwardTarget::Pod(_) => Some("pod"),
+      ForwardTarget::Deployment(_) => Some("deployment"),
+      ForwardTarget::StatefulSet(_) => Some("statefulset"),
+      ForwardTarget::Selector(_) | ForwardTarget::Other(_) => None,
+    }
+  }
+
+  /// `kubectl get <resource_type> <name> -o <jsonpath>` listing the ports the target exposes
+  pub fn ports_jsonpath(&self) -> Option<&'static str> {
+    match self {
+      ForwardTarget::Service(_) => Some("jsonpath={.spec.ports[*].port}"),
+      ForwardTarget::Pod(_) => Some("jsonpath={.spec.containers[*].ports[*].containerPort}"),
+      ForwardTarget::Deployment(_) | ForwardTarget::StatefulSet(_) => {
+        Some("jsonpath={.spec.template.spec.containers[*].ports[*].containerPort}")
+      }
+      ForwardTarget::Selector(_) | ForwardTarget::Other(_) => None,
+    }
+  }
+
+  /// Every spelling kubectl accepts for this target, used to match running processes
+  pub fn command_aliases(&self) -> Vec<String> {
+    let kinds: &[&str] = match self {
+      ForwardTarget::Service(_) => &["svc", "service", "services"],
+      ForwardTarget::Pod(_) => &["pod", "pods", "po"],
+      ForwardTarget::Deployment(_) => &["deploy", "deployment", "deployments"],
+      ForwardTarget::StatefulSet(_) => &["sts", "statefulset", "statefulsets"],
+      ForwardTarget::Selector(_) => return vec![],
+      ForwardTarget::Other(value) => return vec![value.clone()],
+    };
+    let name = self.name();
+    let mut aliases: Vec<String> = kinds.iter().map(|k| format!("{}/{}", k, name)).collect();
+    if matches!(self, ForwardTarget::Pod(_)) {
+      aliases.push(name.to_string());
+    }
+    aliases
+  }
+}
+
+impl fmt::Display for ForwardTarget {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ForwardTarget::Other(value) => f.write_str(value),
+      _ => write!(f, "{}/{}", self.kind_label(), self.name()),
+    }
+  }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AppConfig {
   pub kubectl_path: Option<String>,
   pub kubeconfig_path: Option<String>,
+  /// Config snapshots to keep; 0 turns them off
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub history_limit: Option<usize>,
+  /// Host key policy for SSH forwards that don't set their own
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ssh_host_key_policy: Option<HostKeyPolicy>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,12 +402,78 @@ impl From<SerializableProcessInfo> for ProcessInfo {
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct PortForwardConfigs {
+  /// Schema version, see `services::config_migrations`
+  #[serde(default)]
+  pub version: u32,
+  /// Other config files whose entries are merged in, relative to this file.
+  /// Entries defined here override included ones with the same name.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub include: Vec<String>,
+  /// Values for `${VAR}` placeholders; the environment takes precedence
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub variables: BTreeMap<String, String>,
+  /// Named sets of forwards: config names or `tag:<tag>` queries
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub profiles: BTreeMap<String, Vec<String>>,
+  #[serde(default)]
   pub configs: Vec<PortForwardConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ProcessManagerState {
   pub processes: std::collections::HashMap<String, SerializableProcessInfo>,
+  /// Shared SSH connections, by `services::ssh_master::master_key`
+  #[serde(default)]
+  pub masters: std::collections::HashMap<String, SshMaster>,
+}
+
+/// An SSH ControlMaster connection multiplexed forwards are added to and removed
+/// from. Their process entries carry its pid.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SshMaster {
+  pub pid: u32,
+  pub control_path: std::path::PathBuf,
+  /// Names of the forwards currently open on it
+  pub forwards: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_kubectl_target_aliases() {
+    assert_eq!(
+      ForwardTarget::parse("svc/api"),
+      ForwardTarget::Service("api".to_string())
+    );
+    assert_eq!(
+      ForwardTarget::parse("deployment/web"),
+      ForwardTarget::Deployment("web".to_string())
+    );
+    assert_eq!(
+      ForwardTarget::parse("sts/db"),
+      ForwardTarget::StatefulSet("db".to_string())
+    );
+    assert_eq!(
+      ForwardTarget::parse("web-7c9d"),
+      ForwardTarget::Pod("web-7c9d".to_string())
+    );
+    let other = ForwardTarget::parse("service.v1/api");
+    assert_eq!(other, ForwardTarget::Other("service.v1/api".to_string()));
+    assert_eq!(other.to_string(), "service.v1/api");
+    assert_eq!(other.name(), "api");
+  }
+
+  #[test]
+  fn keeps_slashes_inside_selectors() {
+    let target = ForwardTarget::parse("selector/app.kubernetes.io/name=web");
+    assert_eq!(
+      target,
+      ForwardTarget::Selector("app.kubernetes.io/name=web".to_string())
+    );
+    assert_eq!(target.to_string(), "selector/app.kubernetes.io/name=web");
+  }
 }
@@ -1,5 +1,6 @@
 use easy_kpf_core::ipc::protocol::{Request, Response, ResponseData};
 use easy_kpf_core::ipc::socket_path::default_socket_path;
+use easy_kpf_core::ForwardType;
 use std::process;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
@@ -67,7 +68,40 @@ fn print_data(data: ResponseData) {
     ResponseData::Services(services) => {
       for s in services {
         let state = if s.running { "running" } else { "stopped" };
-        println!("{:30} {}", s.name, state);
+        let kind = match (s.ssh_direction, s.forward_type) {
+          (Some(d), _) => format!("ssh {}", d.flag()),
+          (None, ForwardType::Native) => "native".to_string(),
+          (None, ForwardType::Tcp) => "tcp".to_string(),
+          (None, ForwardType::Command) => "command".to_string(),
+          (None, _) => "kubectl".to_string(),
+        };
+        let traffic = s
+          .traffic
+          .map(|traffic| format!("  ({})", traffic))
+          .unwrap_or_default();
+        if s.ports.is_empty() {
+          println!("{:30} {:8} {}", s.name, kind, state);
+        } else {
+          println!(
+            "{:30} {:8} {:8} {}{}",
+            s.name,
+            kind,
+            state,
+            s.ports.join(", "),
+            traffic
+          );
+        }
+      }
+    }
+    ResponseData::PortOwners(conflicts) => {
+      for conflict in conflicts {
+        println!("{}", conflict.describe());
+      }
+    }
+    ResponseData::Profiles(profiles) => {
+      for p in profiles {
+        let total = p.running.len() + p.stopped.len();
+        println!("{:30} {}/{} running", p.name, p.running.len(), total);
       }
     }
     ResponseData::Reconnected(names) => {
@@ -79,7 +113,7 @@ fn print_data(data: ResponseData) {
         }
       }
     }
-    ResponseData::Text(msg) => println!("{}", msg),
+    ResponseData::Text(msg) => println!("{}", msg.trim_end()),
     ResponseData::Empty => {}
   }
 }
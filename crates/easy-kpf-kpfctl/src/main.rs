@@ -1,8 +1,9 @@
 mod client;
 
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
-use easy_kpf_core::ipc::protocol::Request;
+use easy_kpf_core::ipc::protocol::{OwnerAction, Request};
+use easy_kpf_core::services::ExportFormat;
 use std::io;
 
 #[derive(Parser)]
@@ -16,16 +17,50 @@ struct Cli {
 enum Command {
   #[command(about = "Start services from the last-active set that aren't running")]
   ReconnectAll,
-  #[command(about = "Start a port forward by config name")]
-  Start { name: String },
-  #[command(about = "Stop a port forward by config name")]
-  Stop { name: String },
+  #[command(about = "Start a port forward by config name, or every config with a tag")]
+  Start {
+    #[arg(required_unless_present = "tag")]
+    name: Option<String>,
+    #[arg(long, conflicts_with = "name")]
+    tag: Option<String>,
+  },
+  #[command(about = "Stop a port forward by config name, or every config with a tag")]
+  Stop {
+    #[arg(required_unless_present = "tag")]
+    name: Option<String>,
+    #[arg(long, conflicts_with = "name")]
+    tag: Option<String>,
+  },
   #[command(about = "List all configured port forwards and their state")]
   List,
   #[command(about = "Show status of all port forwards")]
   Status,
   #[command(about = "Bring the EasyKpf window to focus")]
   Show,
+  #[command(about = "Print configs as a shell script or an importable bundle")]
+  Export {
+    /// Config names; all configs of --context when omitted
+    names: Vec<String>,
+    #[arg(long)]
+    context: Option<String>,
+    #[arg(long, value_enum, default_value_t = Format::Script)]
+    format: Format,
+  },
+  #[command(about = "Show which processes hold a config's local ports")]
+  Owner {
+    name: String,
+    /// Track an orphaned kubectl forward for this config instead of starting a new one
+    #[arg(long, conflicts_with = "kill")]
+    adopt: bool,
+    /// Kill the processes holding the ports
+    #[arg(long)]
+    kill: bool,
+  },
+  #[command(about = "Start, stop or list named sets of port forwards")]
+  Profile {
+    #[command(subcommand)]
+    command: ProfileCommand,
+  },
   #[command(about = "Print shell completion script to stdout")]
   Completions {
     #[arg(value_enum)]
@@ -33,17 +68,74 @@ enum Command {
   },
 }
 
+#[derive(Subcommand)]
+enum ProfileCommand {
+  #[command(about = "Start every forward of a profile that isn't running")]
+  Start { name: String },
+  #[command(about = "Stop every running forward of a profile")]
+  Stop { name: String },
+  #[command(about = "List profiles and how many of their forwards are running")]
+  List,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+  Script,
+  Bundle,
+}
+
 #[tokio::main]
 async fn main() {
   let cli = Cli::parse();
 
   let request = match cli.command {
     Command::ReconnectAll => Request::ReconnectAll,
-    Command::Start { name } => Request::Start { name },
-    Command::Stop { name } => Request::Stop { name },
+    Command::Start { name, tag } => match (name, tag) {
+      (_, Some(tag)) => Request::StartTag { tag },
+      (name, None) => Request::Start {
+        name: name.unwrap_or_default(),
+      },
+    },
+    Command::Stop { name, tag } => match (name, tag) {
+      (_, Some(tag)) => Request::StopTag { tag },
+      (name, None) => Request::Stop {
+        name: name.unwrap_or_default(),
+      },
+    },
     Command::List => Request::List,
     Command::Status => Request::Status,
     Command::Show => Request::Show,
+    Command::Export {
+      names,
+      context,
+      format,
+    } => {
+      if names.is_empty() && context.is_none() {
+        eprintln!("error: pass config names or --context");
+        std::process::exit(2);
+      }
+      Request::Export {
+        names,
+        context,
+        format: match format {
+          Format::Script => ExportFormat::Script,
+          Format::Bundle => ExportFormat::Bundle,
+        },
+      }
+    }
+    Command::Owner { name, adopt, kill } => Request::PortOwners {
+      name,
+      action: match (adopt, kill) {
+        (true, _) => Some(OwnerAction::Adopt),
+        (_, true) => Some(OwnerAction::Kill),
+        _ => None,
+      },
+    },
+    Command::Profile { command } => match command {
+      ProfileCommand::Start { name } => Request::StartProfile { name },
+      ProfileCommand::Stop { name } => Request::StopProfile { name },
+      ProfileCommand::List => Request::Profiles,
+    },
     Command::Completions { shell } => {
       let mut cmd = Cli::command();
       let bin_name = cmd.get_name().to_string();
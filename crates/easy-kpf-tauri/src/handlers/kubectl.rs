@@ -24,6 +24,18 @@ pub async fn get_services(
     .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_forward_targets(
+  context: String,
+  namespace: String,
+  kubectl_service: State<'_, KubectlService>,
+) -> Result<Vec<String>, String> {
+  kubectl_service
+    .get_forward_targets(&context, &namespace)
+    .await
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_service_ports(
   context: String,
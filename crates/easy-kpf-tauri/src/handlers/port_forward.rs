@@ -1,8 +1,33 @@
 use crate::reconnect;
-use crate::services::{KubectlService, PortForwardService};
-use easy_kpf_core::types::PortForwardConfig;
+use crate::services::{KubectlOperations, KubectlService, PortForwardService};
+use easy_kpf_core::error::AppError;
+use easy_kpf_core::services::{
+  importer, FieldError, HostKey, ImportReport, PortConflict, ProfileStatus, SnapshotDiff,
+  SnapshotInfo, TrafficStats,
+};
+use easy_kpf_core::types::{PortForwardConfig, SshSettings};
+use serde::Serialize;
+use std::collections::HashMap;
 use tauri::State;
 
+/// Why a config wasn't saved: per-field problems shown next to the form fields,
+/// or any other error as a message
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SaveConfigError {
+  Fields(Vec<FieldError>),
+  Message(String),
+}
+
+impl From<AppError> for SaveConfigError {
+  fn from(error: AppError) -> Self {
+    match error {
+      AppError::Validation(errors) => SaveConfigError::Fields(errors.0),
+      error => SaveConfigError::Message(error.to_string()),
+    }
+  }
+}
+
 #[tauri::command]
 pub fn get_port_forward_configs(
   port_forward_service: State<'_, PortForwardService>,
@@ -12,13 +37,45 @@ pub fn get_port_forward_configs(
     .map_err(|e| e.to_string())
 }
 
+/// Why the configs came back empty, if the config file had to be moved aside
+#[tauri::command]
+pub fn take_config_recovery_notice(
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Option<String>, String> {
+  port_forward_service
+    .take_recovery_notice()
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn add_port_forward_config(
   config: PortForwardConfig,
   port_forward_service: State<'_, PortForwardService>,
-) -> Result<(), String> {
+) -> Result<(), SaveConfigError> {
   port_forward_service
     .add_config(config)
+    .map_err(SaveConfigError::from)
+}
+
+/// Import forwards from pasted commands, or from shell history when `text` is empty
+#[tauri::command]
+pub async fn import_port_forwards(
+  text: Option<String>,
+  dry_run: bool,
+  port_forward_service: State<'_, PortForwardService>,
+  kubectl_service: State<'_, KubectlService>,
+) -> Result<ImportReport, String> {
+  let text = match text.filter(|t| !t.trim().is_empty()) {
+    Some(text) => text,
+    None => importer::read_shell_history().map_err(|e| e.to_string())?,
+  };
+  let context = kubectl_service
+    .get_current_context()
+    .await
+    .unwrap_or_default();
+
+  port_forward_service
+    .import_configs(&text, &context, dry_run)
     .map_err(|e| e.to_string())
 }
 
@@ -32,15 +89,39 @@ pub fn remove_port_forward_config(
     .map_err(|e| e.to_string())
 }
 
+/// Per-field problems with `config`; `old_service_key` is set when editing
+#[tauri::command]
+pub fn validate_port_forward_config(
+  config: PortForwardConfig,
+  old_service_key: Option<String>,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Vec<FieldError>, String> {
+  port_forward_service
+    .validate_config(&config, old_service_key.as_deref())
+    .map(|errors| errors.0)
+    .map_err(|e| e.to_string())
+}
+
+/// `config` with `${VAR}` placeholders resolved, for previewing in the form
+#[tauri::command]
+pub fn resolve_port_forward_config(
+  config: PortForwardConfig,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<PortForwardConfig, String> {
+  port_forward_service
+    .expand_config(&config)
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn update_port_forward_config(
   old_service_key: String,
   new_config: PortForwardConfig,
   port_forward_service: State<'_, PortForwardService>,
-) -> Result<(), String> {
+) -> Result<(), SaveConfigError> {
   port_forward_service
     .update_config(&old_service_key, new_config)
-    .map_err(|e| e.to_string())
+    .map_err(SaveConfigError::from)
 }
 
 #[tauri::command]
@@ -65,6 +146,59 @@ pub fn reorder_port_forward_group(
     .map_err(|e| e.to_string())
 }
 
+/// Saved config snapshots, newest first
+#[tauri::command]
+pub fn list_config_snapshots(
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Vec<SnapshotInfo>, String> {
+  port_forward_service
+    .list_snapshots()
+    .map_err(|e| e.to_string())
+}
+
+/// What restoring snapshot `id` would change
+#[tauri::command]
+pub fn diff_config_snapshot(
+  id: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<SnapshotDiff, String> {
+  port_forward_service
+    .diff_snapshot(&id)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_config_snapshot(
+  id: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<(), String> {
+  port_forward_service
+    .restore_snapshot(&id)
+    .map_err(|e| e.to_string())
+}
+
+/// Keys offered by the host of an SSH config, to show before pinning
+#[tauri::command]
+pub async fn fetch_ssh_host_keys(
+  config: PortForwardConfig,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Vec<HostKey>, String> {
+  port_forward_service
+    .fetch_host_keys(&config)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn pin_ssh_host_keys(
+  keys: Vec<HostKey>,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<(), String> {
+  port_forward_service
+    .pin_host_keys(&keys)
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn start_port_forward_by_key(
   service_key: String,
@@ -87,6 +221,90 @@ pub fn stop_port_forward(
     .map_err(|e| e.to_string())
 }
 
+/// Processes holding the local ports of a config
+#[tauri::command]
+pub fn get_port_conflicts(
+  service_name: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Vec<PortConflict>, String> {
+  port_forward_service
+    .find_port_conflicts(&service_name)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn adopt_port_owner(
+  service_name: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<String, String> {
+  port_forward_service
+    .adopt_port_owner(&service_name)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn kill_port_owners(
+  service_name: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<String, String> {
+  port_forward_service
+    .kill_port_owners(&service_name)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_profiles(
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<Vec<ProfileStatus>, String> {
+  port_forward_service
+    .get_profiles()
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn start_profile(
+  profile: String,
+  port_forward_service: State<'_, PortForwardService>,
+  kubectl_service: State<'_, KubectlService>,
+) -> Result<String, String> {
+  port_forward_service
+    .start_profile(kubectl_service.inner(), &profile)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_profile(
+  profile: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<String, String> {
+  port_forward_service
+    .stop_profile(&profile)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn start_tagged(
+  tag: String,
+  port_forward_service: State<'_, PortForwardService>,
+  kubectl_service: State<'_, KubectlService>,
+) -> Result<String, String> {
+  port_forward_service
+    .start_tagged(kubectl_service.inner(), &tag)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_tagged(
+  tag: String,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<String, String> {
+  port_forward_service
+    .stop_tagged(&tag)
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_running_services(
   port_forward_service: State<'_, PortForwardService>,
@@ -96,6 +314,15 @@ pub fn get_running_services(
     .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_service_traffic(
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<HashMap<String, TrafficStats>, String> {
+  port_forward_service
+    .get_service_traffic()
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn verify_port_forwards(
   port_forward_service: State<'_, PortForwardService>,
@@ -143,23 +370,24 @@ pub async fn reconnect_all_services(
 }
 
 #[tauri::command]
-pub async fn test_ssh_connection(ssh_host: String) -> Result<String, String> {
+pub async fn test_ssh_connection(
+  ssh_host: String,
+  ssh: Option<SshSettings>,
+  port_forward_service: State<'_, PortForwardService>,
+) -> Result<String, String> {
+  use easy_kpf_core::services::{host_keys, SshCommandBuilder};
   use std::process::Command;
   use std::time::Duration;
   use tokio::time::timeout;
 
+  let ssh = ssh.unwrap_or_default();
+  let host_key_args = port_forward_service.ssh_host_key_args(&ssh);
   let test_command = async {
     let output = Command::new("ssh")
-      .args([
-        "-o",
-        "ConnectTimeout=5",
-        "-o",
-        "BatchMode=yes",
-        "-o",
-        "StrictHostKeyChecking=no",
-        &ssh_host,
-        "exit",
-      ])
+      .args(["-o", "ConnectTimeout=5", "-o", "BatchMode=yes"])
+      .args(host_key_args)
+      .args(SshCommandBuilder::connection_args(&ssh))
+      .args([&ssh_host, "exit"])
       .output()
       .map_err(|e| format!("Failed to execute SSH command: {}", e))?;
 
@@ -167,7 +395,10 @@ pub async fn test_ssh_connection(ssh_host: String) -> Result<String, String> {
       Ok("SSH connection successful".to_string())
     } else {
       let stderr = String::from_utf8_lossy(&output.stderr);
-      Err(format!("SSH connection failed: {}", stderr))
+      match host_keys::classify_ssh_error(&stderr) {
+        Some(e) => Err(e.to_string()),
+        None => Err(format!("SSH connection failed: {}", stderr)),
+      }
     }
   };
 
@@ -4,10 +4,12 @@ pub async fn spawn(_app_handle: tauri::AppHandle) {
 }
 
 #[cfg(unix)]
-use easy_kpf_core::ipc::protocol::{Request, Response, ResponseData, ServiceStatus};
+use easy_kpf_core::ipc::protocol::{OwnerAction, Request, Response, ResponseData, ServiceStatus};
 #[cfg(unix)]
 use easy_kpf_core::ipc::socket_path::default_socket_path;
 #[cfg(unix)]
+use easy_kpf_core::services::exporter;
+#[cfg(unix)]
 use tauri::Manager;
 #[cfg(unix)]
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -119,9 +121,21 @@ async fn dispatch(request: Request, app_handle: &tauri::AppHandle) -> Response {
         .into_iter()
         .map(|c| {
           let running = running.contains(&c.name);
+          let ports = if running {
+            pf.get_active_ports(&c.name)
+              .ok()
+              .flatten()
+              .unwrap_or(c.ports)
+          } else {
+            vec![]
+          };
           ServiceStatus {
+            traffic: pf.get_traffic(&c.name).ok().flatten(),
+            ssh_direction: c.ssh_direction(),
+            forward_type: c.forward_type,
             name: c.name,
             running,
+            ports,
           }
         })
         .collect();
@@ -148,6 +162,24 @@ async fn dispatch(request: Request, app_handle: &tauri::AppHandle) -> Response {
       },
     },
 
+    Request::StartTag { tag } => match pf.start_tagged(kc.inner(), &tag).await {
+      Ok(msg) => Response::Ok {
+        data: ResponseData::Text(msg),
+      },
+      Err(e) => Response::Err {
+        message: e.to_string(),
+      },
+    },
+
+    Request::StopTag { tag } => match pf.stop_tagged(&tag) {
+      Ok(msg) => Response::Ok {
+        data: ResponseData::Text(msg),
+      },
+      Err(e) => Response::Err {
+        message: e.to_string(),
+      },
+    },
+
     Request::ReconnectAll => match crate::reconnect::reconnect_all(pf.inner(), kc.inner()).await {
       Ok(names) => Response::Ok {
         data: ResponseData::Reconnected(names),
@@ -157,6 +189,76 @@ async fn dispatch(request: Request, app_handle: &tauri::AppHandle) -> Response {
       },
     },
 
+    Request::Export {
+      names,
+      context,
+      format,
+    } => {
+      // Exports are snapshots with placeholders resolved
+      let exported = pf
+        .get_expanded_configs()
+        .and_then(|configs| exporter::select_for_export(&configs, &names, context.as_deref()))
+        .and_then(|configs| exporter::export_configs(&configs, format));
+      match exported {
+        Ok(content) => Response::Ok {
+          data: ResponseData::Text(content),
+        },
+        Err(e) => Response::Err {
+          message: e.to_string(),
+        },
+      }
+    }
+
+    Request::PortOwners { name, action } => {
+      let result = match action {
+        None => pf.find_port_conflicts(&name).map(|conflicts| {
+          if conflicts.is_empty() {
+            ResponseData::Text(format!("Nothing else is using the ports of {}", name))
+          } else {
+            ResponseData::PortOwners(conflicts)
+          }
+        }),
+        Some(OwnerAction::Adopt) => pf.adopt_port_owner(&name).map(ResponseData::Text),
+        Some(OwnerAction::Kill) => pf.kill_port_owners(&name).map(ResponseData::Text),
+      };
+      match result {
+        Ok(data) => Response::Ok { data },
+        Err(e) => Response::Err {
+          message: e.to_string(),
+        },
+      }
+    }
+
+    Request::Profiles => match pf.get_profiles() {
+      Ok(profiles) if profiles.is_empty() => Response::Ok {
+        data: ResponseData::Text("No profiles defined".to_string()),
+      },
+      Ok(profiles) => Response::Ok {
+        data: ResponseData::Profiles(profiles),
+      },
+      Err(e) => Response::Err {
+        message: e.to_string(),
+      },
+    },
+
+    Request::StartProfile { name } => match pf.start_profile(kc.inner(), &name).await {
+      Ok(msg) => Response::Ok {
+        data: ResponseData::Text(msg),
+      },
+      Err(e) => Response::Err {
+        message: e.to_string(),
+      },
+    },
+
+    Request::StopProfile { name } => match pf.stop_profile(&name) {
+      Ok(msg) => Response::Ok {
+        data: ResponseData::Text(msg),
+      },
+      Err(e) => Response::Err {
+        message: e.to_string(),
+      },
+    },
+
     Request::Show => {
       crate::window::activate_and_show_window(app_handle);
       Response::Ok {
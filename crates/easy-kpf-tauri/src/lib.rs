@@ -1,4 +1,4 @@
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 mod handlers;
 mod ipc_server;
@@ -11,11 +11,18 @@ pub use easy_kpf_core::error;
 pub use easy_kpf_core::types;
 
 use easy_kpf_core::ipc::socket_path::default_socket_path;
+use easy_kpf_core::services::ConfigWatcher;
 use easy_kpf_core::{ConfigService, LastActiveSet, ProcessManager};
 use handlers::*;
 use services::{KubectlService, PortForwardService};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use utils::init_logging;
 
+/// How often the frontend gets fresh connection counters
+const TRAFFIC_INTERVAL: Duration = Duration::from_secs(2);
+
 fn cleanup_all_port_forwards(port_forward_service: &PortForwardService) -> Result<(), String> {
   port_forward_service
     .cleanup_all_port_forwards()
@@ -62,12 +69,47 @@ pub fn run() {
         last_active.clone(),
       );
 
+      // Edits from the TUI or an editor reach the cache and the frontend right away
+      match ConfigWatcher::start(config_service.clone()) {
+        Ok(watcher) => {
+          if let Err(e) = port_forward_service.watch_configs(&watcher) {
+            log::warn!("Failed to watch config files: {}", e);
+          }
+          let mut events = watcher.subscribe();
+          let events_handle = app_handle.clone();
+          tauri::async_runtime::spawn(async move {
+            while !matches!(events.recv().await, Err(RecvError::Closed)) {
+              let _ = events_handle.emit("configs-changed", ());
+            }
+          });
+          app.manage(watcher);
+        }
+        Err(e) => log::warn!("Config files will not be watched: {}", e),
+      }
+
       app.manage(config_service);
       app.manage(kubectl_service);
       app.manage(port_forward_service);
       app.manage(process_manager.clone());
       app.manage(last_active);
 
+      // Connection counters of forwards running in the app, sent when they change
+      let traffic_handle = app_handle.clone();
+      tauri::async_runtime::spawn(async move {
+        let mut sent = HashMap::new();
+        loop {
+          tokio::time::sleep(TRAFFIC_INTERVAL).await;
+          let service = traffic_handle.state::<PortForwardService>();
+          let Ok(traffic) = service.get_service_traffic() else {
+            continue;
+          };
+          if traffic != sent {
+            let _ = traffic_handle.emit("service-traffic", &traffic);
+            sent = traffic;
+          }
+        }
+      });
+
       tauri::async_runtime::spawn_blocking(move || {
         process_manager.restore_state();
       });
@@ -81,14 +123,32 @@ pub fn run() {
     })
     .invoke_handler(tauri::generate_handler![
       get_port_forward_configs,
+      take_config_recovery_notice,
       add_port_forward_config,
+      import_port_forwards,
       update_port_forward_config,
+      validate_port_forward_config,
+      resolve_port_forward_config,
       remove_port_forward_config,
       reorder_port_forward_config,
       reorder_port_forward_group,
+      list_config_snapshots,
+      diff_config_snapshot,
+      restore_config_snapshot,
+      fetch_ssh_host_keys,
+      pin_ssh_host_keys,
       start_port_forward_by_key,
       stop_port_forward,
+      get_port_conflicts,
+      adopt_port_owner,
+      kill_port_owners,
+      get_profiles,
+      start_profile,
+      stop_profile,
+      start_tagged,
+      stop_tagged,
       get_running_services,
+      get_service_traffic,
       verify_port_forwards,
       verify_and_update_port_forwards,
       detect_existing_port_forwards,
@@ -99,6 +159,7 @@ pub fn run() {
       set_kubectl_context,
       get_namespaces,
       get_services,
+      get_forward_targets,
       get_service_ports,
       get_kubeconfig_env,
       set_kubeconfig_env,
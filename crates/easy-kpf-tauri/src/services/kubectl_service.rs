@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use easy_kpf_core::error::{AppError, Result};
 use easy_kpf_core::services::ConfigService;
+use easy_kpf_core::types::ForwardTarget;
 use std::path::Path;
 use tauri_plugin_shell::ShellExt;
 
@@ -17,6 +18,7 @@ const KUBECTL_DETECTION_PATHS: &[&str] = &[
 pub trait KubectlOperations {
   async fn get_namespaces(&self, context: &str) -> Result<Vec<String>>;
   async fn get_services(&self, context: &str, namespace: &str) -> Result<Vec<String>>;
+  async fn get_forward_targets(&self, context: &str, namespace: &str) -> Result<Vec<String>>;
   async fn get_service_ports(
     &self,
     context: &str,
@@ -215,6 +217,54 @@ impl KubectlOperations for KubectlService {
     }
   }
 
+  async fn get_forward_targets(&self, context: &str, namespace: &str) -> Result<Vec<String>> {
+    log::debug!(
+      "Getting forward targets for context: {}, namespace: {}",
+      context,
+      namespace
+    );
+
+    let mut targets = self.get_services(context, namespace).await?;
+
+    for (resource, prefix) in [
+      ("deployments", "deploy"),
+      ("statefulsets", "sts"),
+      ("pods", "pod"),
+    ] {
+      let output = self
+        .create_command()
+        .args([
+          "--context",
+          context,
+          "-n",
+          namespace,
+          "get",
+          resource,
+          "-o",
+          "jsonpath={.items[*].metadata.name}",
+        ])
+        .output()
+        .await
+        .map_err(|e| AppError::Kubectl(e.to_string()))?;
+
+      if output.status.success() {
+        targets.extend(
+          String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .map(|name| format!("{}/{}", prefix, name)),
+        );
+      } else {
+        log::debug!(
+          "Failed to list {}: {}",
+          resource,
+          String::from_utf8_lossy(&output.stderr)
+        );
+      }
+    }
+
+    Ok(targets)
+  }
+
   async fn get_service_ports(
     &self,
     context: &str,
@@ -228,7 +278,10 @@ impl KubectlOperations for KubectlService {
       service
     );
 
-    let service_name = service.strip_prefix("svc/").unwrap_or(service);
+    let target = ForwardTarget::parse(service);
+    let (Some(resource), Some(jsonpath)) = (target.resource_type(), target.ports_jsonpath()) else {
+      return Ok(vec![]);
+    };
 
     let output = self
       .create_command()
@@ -238,10 +291,10 @@ impl KubectlOperations for KubectlService {
         "-n",
         namespace,
         "get",
-        "service",
-        service_name,
+        resource,
+        target.name(),
         "-o",
-        "jsonpath={.spec.ports[*].port}",
+        jsonpath,
       ])
       .output()
       .await
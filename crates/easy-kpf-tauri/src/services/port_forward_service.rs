@@ -1,13 +1,20 @@
+use async_trait::async_trait;
 use easy_kpf_core::error::{AppError, Result};
+use easy_kpf_core::services::dependencies::{self, ForwardStarter};
 use easy_kpf_core::services::{
-  ConfigCache, ConfigService, InterfaceManager, KubectlCommandBuilder, LastActiveSet,
-  ProcessDetector, ProcessManager, SshCommandBuilder, SystemInterfaceManager,
+  custom_command, host_keys, kubectl_proxy, native_forward, port_check, port_owner, profiles,
+  ssh_master, tcp_relay, CommandImporter, ConfigCache, ConfigService, ConfigWatcher, HostKey,
+  ImportReport, InterfaceManager, KubectlCommandBuilder, LastActiveSet, PortConflict,
+  ProcessDetector, ProcessManager, ProfileStatus, SnapshotDiff, SnapshotInfo,
+  SystemInterfaceManager, TrafficStats, ValidationErrors,
 };
-use easy_kpf_core::types::{ForwardType, PortForwardConfig};
+use easy_kpf_core::traits::ProcessEvent;
+use easy_kpf_core::types::{ForwardType, PortForwardConfig, SshSettings};
 use serde::Serialize;
+use std::collections::HashMap;
 use tauri::Emitter;
 
-use super::KubectlOperations;
+use super::{KubectlOperations, ShellExecutor};
 use tauri_plugin_shell::ShellExt;
 
 #[derive(Clone, Serialize)]
@@ -49,20 +56,80 @@ impl PortForwardService {
     &self.last_active
   }
 
+  /// Reload configs when `watcher` sees the files change
+  pub fn watch_configs(&self, watcher: &ConfigWatcher) -> Result<()> {
+    self.config_cache.invalidate_on_change(watcher)
+  }
+
   pub fn get_configs(&self) -> Result<Vec<PortForwardConfig>> {
     self.config_cache.get_configs()
   }
 
+  /// Set once after an unparseable config file was moved aside on load
+  pub fn take_recovery_notice(&self) -> Result<Option<String>> {
+    Ok(
+      self
+        .config_cache
+        .take_recovered_from()?
+        .map(|path| format!("Config file was invalid and moved to {}", path.display())),
+    )
+  }
+
   pub fn add_config(&self, config: PortForwardConfig) -> Result<()> {
     self.config_cache.add_config(config)
   }
 
+  /// Parse `kubectl port-forward` / `ssh -L` commands and add the new forwards
+  pub fn import_configs(
+    &self,
+    text: &str,
+    default_context: &str,
+    dry_run: bool,
+  ) -> Result<ImportReport> {
+    let mut configs = self.config_cache.get_configs()?;
+    let report = CommandImporter::new(default_context).import(text, &configs);
+
+    if !dry_run && !report.configs.is_empty() {
+      configs.extend(report.configs.iter().cloned());
+      self.config_cache.update_configs(configs)?;
+    }
+    Ok(report)
+  }
+
+  /// `config` with `${VAR}` placeholders resolved, for previews
+  pub fn expand_config(&self, config: &PortForwardConfig) -> Result<PortForwardConfig> {
+    self.config_cache.expand_config(config)
+  }
+
+  /// Configs with placeholders resolved where possible
+  pub fn get_expanded_configs(&self) -> Result<Vec<PortForwardConfig>> {
+    self.config_cache.get_expanded_configs()
+  }
+
+  pub fn validate_config(
+    &self,
+    config: &PortForwardConfig,
+    replacing: Option<&str>,
+  ) -> Result<ValidationErrors> {
+    self.config_cache.validate_config(config, replacing)
+  }
+
   pub fn remove_config(&self, service_key: &str) -> Result<()> {
+    self.config_cache.ensure_writable(service_key)?;
     self.last_active.remove(service_key)?;
     self.config_cache.remove_config(service_key)
   }
 
   pub fn update_config(&self, old_service_key: &str, new_config: PortForwardConfig) -> Result<()> {
+    // Reject included entries and invalid input before touching any running process state
+    self.config_cache.ensure_writable(old_service_key)?;
+    let errors = self
+      .config_cache
+      .validate_config(&new_config, Some(old_service_key))?;
+    if !errors.is_empty() {
+      return Err(AppError::Validation(errors));
+    }
+
     // If the service name changed, update the process manager
     if old_service_key != new_config.name {
       self
@@ -82,6 +149,39 @@ impl PortForwardService {
     self.config_cache.reorder_group(group_key, new_index)
   }
 
+  pub fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+    self.config_service.list_snapshots()
+  }
+
+  pub fn diff_snapshot(&self, id: &str) -> Result<SnapshotDiff> {
+    self.config_service.diff_snapshot(id)
+  }
+
+  pub fn restore_snapshot(&self, id: &str) -> Result<()> {
+    self.config_service.restore_snapshot(id)?;
+    self.config_cache.invalidate()
+  }
+
+  /// Host keys offered by the host of an SSH `config`, for the user to confirm
+  pub async fn fetch_host_keys(&self, config: &PortForwardConfig) -> Result<Vec<HostKey>> {
+    let expanded = self.config_cache.expand_config(config)?;
+    tauri::async_runtime::spawn_blocking(move || host_keys::fetch(&expanded))
+      .await
+      .map_err(|e| AppError::System(e.to_string()))?
+  }
+
+  pub fn pin_host_keys(&self, keys: &[HostKey]) -> Result<()> {
+    self.config_service.host_keys().pin(keys)
+  }
+
+  /// Extra `ssh` options verifying host keys against EasyKpf's known_hosts
+  pub fn ssh_host_key_args(&self, settings: &SshSettings) -> Vec<String> {
+    self
+      .config_service
+      .ssh_command_builder()
+      .host_key_args(settings)
+  }
+
   pub async fn start_port_forward_by_key<K: KubectlOperations>(
     &self,
     kubectl_service: &K,
@@ -113,29 +213,56 @@ impl PortForwardService {
       )));
     }
 
-    // No need to switch contexts - we use --context flag in the kubectl command
-    log::info!(
-      "Starting port forward for {} in context {}",
-      config.name,
-      config.context
-    );
-
-    self.execute_port_forward(&config).await
+    // Bring up `depends_on` first, waiting for each to accept connections
+    let configs = self.get_configs()?;
+    let messages =
+      dependencies::start_with_dependencies(self, &self.process_manager, config, &configs).await?;
+    Ok(messages.join("\n"))
   }
 
   async fn execute_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
-    match config.forward_type {
-      ForwardType::Kubectl => self.execute_kubectl_port_forward(config).await,
-      ForwardType::Ssh => self.execute_ssh_port_forward(config).await,
+    // Held until the process is registered so another frontend can't start it too
+    let _claim = self.process_manager.claim_start(&config.name)?;
+
+    // Resolve `${VAR}` placeholders, then fail (or move to a free port) before
+    // spawning anything
+    let config = self.config_cache.expand_config(config)?;
+    let prepared = port_check::prepare_local_ports(&config)?;
+    let message = match prepared.config.forward_type {
+      ForwardType::Kubectl if prepared.config.is_proxied() => {
+        self
+          .execute_in_process_port_forward(&prepared.config)
+          .await?
+      }
+      ForwardType::Kubectl => self.execute_kubectl_port_forward(&prepared.config).await?,
+      ForwardType::Ssh => self.execute_ssh_port_forward(&prepared.config).await?,
+      ForwardType::Native | ForwardType::Tcp => {
+        self
+          .execute_in_process_port_forward(&prepared.config)
+          .await?
+      }
+      ForwardType::Command => self.execute_command_port_forward(&prepared.config).await?,
+    };
+
+    if prepared.reassigned.is_empty() {
+      Ok(message)
+    } else {
+      Ok(format!("{} on {}", message, prepared.summary()))
     }
   }
 
-  async fn execute_kubectl_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
-    // Create local interface if specified and doesn't exist
-    if let Some(ref interface) = config.local_interface {
-      self.interface_manager.ensure_interface_exists(interface)?;
-    }
+  /// Port mappings a running forward actually bound, which differ from the config
+  /// when a busy port was replaced
+  pub fn get_active_ports(&self, name: &str) -> Result<Option<Vec<String>>> {
+    Ok(
+      self
+        .process_manager
+        .get_process_config(name)?
+        .map(|config| config.ports),
+    )
+  }
 
+  fn kubectl_builder(&self) -> KubectlCommandBuilder {
     let kubectl_path = match self.config_service.load_kubectl_path() {
       Ok(path) => path,
       Err(e) => {
@@ -146,8 +273,35 @@ impl PortForwardService {
 
     let kubeconfig_path = self.config_service.load_kubeconfig_path().ok().flatten();
 
-    let (command, args, env_vars) =
-      KubectlCommandBuilder::new(kubectl_path, kubeconfig_path).build_port_forward_command(config);
+    KubectlCommandBuilder::new(kubectl_path, kubeconfig_path)
+  }
+
+  /// Connection counters of a running forward whose connections pass through
+  /// the app
+  pub fn get_traffic(&self, name: &str) -> Result<Option<TrafficStats>> {
+    self.process_manager.traffic(name)
+  }
+
+  /// Connection counters of every running forward that has them
+  pub fn get_service_traffic(&self) -> Result<HashMap<String, TrafficStats>> {
+    let mut traffic = HashMap::new();
+    for name in self.process_manager.get_running_services()? {
+      if let Some(stats) = self.process_manager.traffic(&name)? {
+        traffic.insert(name, stats);
+      }
+    }
+    Ok(traffic)
+  }
+
+  async fn execute_kubectl_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
+    // Create local interface if specified and doesn't exist
+    if let Some(ref interface) = config.local_interface {
+      self.interface_manager.ensure_interface_exists(interface)?;
+    }
+
+    let builder = self.kubectl_builder();
+    let resolved = self.resolve_target(&builder, config).await?;
+    let (command, args, env_vars) = builder.build_port_forward_command(&resolved);
 
     let shell = self.app_handle.shell();
     let mut command_builder = shell.command(&command);
@@ -252,13 +406,186 @@ impl PortForwardService {
     ))
   }
 
+  /// Run the forward as a task inside the app: a native forward through the
+  /// Kubernetes API without kubectl, a TCP relay, or the proxy in front of a
+  /// restartable kubectl
+  async fn execute_in_process_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
+    if let Some(ref interface) = config.local_interface {
+      self.interface_manager.ensure_interface_exists(interface)?;
+    }
+
+    let forward = match config.forward_type {
+      ForwardType::Tcp => tcp_relay::start(config).await?,
+      ForwardType::Kubectl => {
+        let executor = ShellExecutor::new(self.app_handle.clone());
+        kubectl_proxy::start(executor, self.kubectl_builder(), config).await?
+      }
+      _ => {
+        let kubeconfig_path = self.config_service.load_kubeconfig_path().ok().flatten();
+        let client = native_forward::client(kubeconfig_path.as_deref(), &config.context).await?;
+        native_forward::start(client, config).await?
+      }
+    };
+    let pid = self.process_manager.attach_task(&config.name, &forward)?;
+    self
+      .process_manager
+      .add_process(config.name.clone(), pid, config.clone())?;
+    self.last_active.add(&config.name)?;
+
+    // Connection errors are reported but leave the forward listening
+    let service_name = config.name.clone();
+    let app_handle = self.app_handle.clone();
+    let process_manager = self.process_manager.clone();
+    let mut events = forward.events;
+    tauri::async_runtime::spawn(async move {
+      while let Some(event) = events.recv().await {
+        let (error, fatal) = match event {
+          ProcessEvent::Stdout(line) => {
+            log::info!("[{}] {}", service_name, String::from_utf8_lossy(&line));
+            continue;
+          }
+          ProcessEvent::Stderr(line) => (String::from_utf8_lossy(&line).to_string(), false),
+          ProcessEvent::Error(err) => (err, true),
+          ProcessEvent::Terminated { .. } => {
+            let was_managed = process_manager
+              .remove_process(&service_name)
+              .ok()
+              .flatten()
+              .is_some();
+            if !was_managed {
+              break;
+            }
+            ("Port forward stopped unexpectedly".to_string(), true)
+          }
+        };
+        log::error!("[{}] {}", service_name, error);
+        if fatal {
+          let _ = process_manager.remove_process(&service_name);
+        }
+        let _ = app_handle.emit(
+          "service-error",
+          ServiceErrorEvent {
+            service_name: service_name.clone(),
+            error,
+            fatal,
+          },
+        );
+      }
+    });
+
+    Ok(format!(
+      "{} port forwarding started in-process",
+      config.name
+    ))
+  }
+
+  /// Run a user-defined tunnelling command, waiting for its ready pattern when
+  /// it has one
+  async fn execute_command_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
+    if let Some(ref interface) = config.local_interface {
+      self.interface_manager.ensure_interface_exists(interface)?;
+    }
+
+    let executor = ShellExecutor::new(self.app_handle.clone());
+    let (handle, mut events) = custom_command::spawn(&executor, config).await?;
+    let pid = handle.pid;
+    self
+      .process_manager
+      .add_process(config.name.clone(), pid, config.clone())?;
+    self.last_active.add(&config.name)?;
+
+    // Tools log to stderr as a matter of course, so only exiting counts as failure
+    let service_name = config.name.clone();
+    let app_handle = self.app_handle.clone();
+    let process_manager = self.process_manager.clone();
+    tauri::async_runtime::spawn(async move {
+      while let Some(event) = events.recv().await {
+        let error = match event {
+          ProcessEvent::Stdout(line) | ProcessEvent::Stderr(line) => {
+            log::info!("[{}] {}", service_name, String::from_utf8_lossy(&line));
+            continue;
+          }
+          ProcessEvent::Error(err) => {
+            let _ = ProcessManager::kill_process(pid);
+            format!("Process error: {}", err)
+          }
+          ProcessEvent::Terminated { code } => {
+            log::warn!(
+              "[{}] Process terminated with code: {:?}.",
+              service_name,
+              code
+            );
+            "Port forward stopped unexpectedly".to_string()
+          }
+        };
+        let was_managed = process_manager
+          .remove_process(&service_name)
+          .ok()
+          .flatten()
+          .is_some();
+        if was_managed {
+          log::error!("[{}] {}", service_name, error);
+          let _ = app_handle.emit(
+            "service-error",
+            ServiceErrorEvent {
+              service_name: service_name.clone(),
+              error,
+              fatal: true,
+            },
+          );
+        }
+        break;
+      }
+    });
+
+    Ok(format!(
+      "{} command port forwarding started with PID: {}",
+      config.name, pid
+    ))
+  }
+
+  /// Resolve label selector targets to the first ready pod
+  async fn resolve_target(
+    &self,
+    builder: &KubectlCommandBuilder,
+    config: &PortForwardConfig,
+  ) -> Result<PortForwardConfig> {
+    let Some((command, args, env_vars)) = builder.build_ready_pod_command(config) else {
+      return Ok(config.clone());
+    };
+
+    let mut command_builder = self.app_handle.shell().command(&command);
+    for (key, value) in env_vars {
+      command_builder = command_builder.env(key, value);
+    }
+
+    let output = command_builder
+      .args(args)
+      .output()
+      .await
+      .map_err(|e| AppError::Kubectl(e.to_string()))?;
+
+    if !output.status.success() {
+      let error = String::from_utf8_lossy(&output.stderr);
+      return Err(AppError::Kubectl(error.trim().to_string()));
+    }
+
+    let pod = KubectlCommandBuilder::parse_ready_pod(&String::from_utf8_lossy(&output.stdout));
+    log::info!("[{}] Selector resolved to pod {:?}", config.name, pod);
+    KubectlCommandBuilder::with_resolved_pod(config, pod)
+  }
+
   async fn execute_ssh_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
     // Create local interface if specified and doesn't exist
     if let Some(ref interface) = config.local_interface {
       self.interface_manager.ensure_interface_exists(interface)?;
     }
 
-    let ssh_builder = SshCommandBuilder::new();
+    if config.is_multiplexed() {
+      return self.attach_ssh_port_forward(config).await;
+    }
+
+    let ssh_builder = self.config_service.ssh_command_builder();
     let (command, args) = ssh_builder.build_port_forward_command(config);
 
     log::debug!(
@@ -298,7 +625,9 @@ impl PortForwardService {
           CommandEvent::Stderr(line) => {
             let error_text = String::from_utf8_lossy(&line).to_string();
             log::error!("[{}] {}", service_name, error_text);
-            let fatal = is_fatal_forward_error(&error_text);
+            // Host key failures read as a clear message rather than ssh's banner
+            let host_key_error = host_keys::classify_ssh_error(&error_text);
+            let fatal = host_key_error.is_some() || is_fatal_forward_error(&error_text);
             if !unhealthy && fatal {
               unhealthy = true;
               let _ = process_manager.remove_process(&service_name);
@@ -309,7 +638,7 @@ impl PortForwardService {
               "service-error",
               ServiceErrorEvent {
                 service_name: service_name.clone(),
-                error: error_text,
+                error: host_key_error.map_or(error_text, |e| e.to_string()),
                 fatal,
               },
             );
@@ -364,26 +693,223 @@ impl PortForwardService {
     ))
   }
 
+  /// Open a multiplexed forward on the shared master connection for its host,
+  /// connecting the master first if needed
+  async fn attach_ssh_port_forward(&self, config: &PortForwardConfig) -> Result<String> {
+    let builder = self.config_service.ssh_command_builder();
+    let process_manager = self.process_manager.clone();
+    let attached = config.clone();
+    let pid = tauri::async_runtime::spawn_blocking(move || {
+      ssh_master::attach(&builder, &process_manager, &attached)
+    })
+    .await
+    .map_err(|e| AppError::System(e.to_string()))??;
+
+    self
+      .process_manager
+      .add_process(config.name.clone(), pid, config.clone())?;
+    self.last_active.add(&config.name)?;
+
+    Ok(format!(
+      "{} SSH port forwarding started on the shared connection to {} (PID: {})",
+      config.name,
+      ssh_master::master_key(config),
+      pid
+    ))
+  }
+
+  /// Other processes listening on the local ports of `service_key`
+  pub fn find_port_conflicts(&self, service_key: &str) -> Result<Vec<PortConflict>> {
+    let (config, configs) = self.expanded_config(service_key)?;
+    port_owner::find_conflicts(&config, &configs, &self.process_manager)
+  }
+
+  /// Track an orphaned kubectl forward for `service_key` instead of starting a new one
+  pub fn adopt_port_owner(&self, service_key: &str) -> Result<String> {
+    let (config, configs) = self.expanded_config(service_key)?;
+    let conflicts = port_owner::find_conflicts(&config, &configs, &self.process_manager)?;
+    let pid = port_owner::adopt(&config, &conflicts, &self.process_manager)?;
+    self.last_active.add(service_key)?;
+
+    Ok(format!("Adopted {} (PID: {})", service_key, pid))
+  }
+
+  /// `service_key` and all configs, with placeholders resolved
+  fn expanded_config(
+    &self,
+    service_key: &str,
+  ) -> Result<(PortForwardConfig, Vec<PortForwardConfig>)> {
+    let configs = self.config_cache.get_expanded_configs()?;
+    let config = configs
+      .iter()
+      .find(|c| c.name == service_key)
+      .cloned()
+      .ok_or_else(|| AppError::NotFound(format!("Service '{}' not found", service_key)))?;
+    Ok((config, configs))
+  }
+
+  /// Kill whatever holds the local ports of `service_key`
+  pub fn kill_port_owners(&self, service_key: &str) -> Result<String> {
+    let conflicts = self.find_port_conflicts(service_key)?;
+    if conflicts.is_empty() {
+      return Ok(format!("Nothing is using the ports of {}", service_key));
+    }
+    for conflict in &conflicts {
+      if let port_owner::OwnerKind::Managed { name } = &conflict.owner.kind {
+        self.last_active.remove(name)?;
+      }
+    }
+    let pids = port_owner::kill_owners(&conflicts, &self.process_manager)?;
+
+    Ok(format!(
+      "Killed PID {}",
+      pids
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+    ))
+  }
+
+  /// Every profile with which of its forwards are running
+  pub fn get_profiles(&self) -> Result<Vec<ProfileStatus>> {
+    let running = self.get_running_services()?;
+    Ok(profiles::profile_statuses(
+      &self.config_cache.get_profiles()?,
+      &self.config_cache.get_configs()?,
+      &running,
+    ))
+  }
+
+  /// Start every forward of `profile` that is not running yet
+  pub async fn start_profile<K: KubectlOperations>(
+    &self,
+    kubectl_service: &K,
+    profile: &str,
+  ) -> Result<String> {
+    let members = self.profile_members(profile)?;
+    self
+      .start_group(kubectl_service, &format!("profile '{}'", profile), members)
+      .await
+  }
+
+  /// Stop every running forward of `profile`
+  pub fn stop_profile(&self, profile: &str) -> Result<String> {
+    let members = self.profile_members(profile)?;
+    self.stop_group(&format!("profile '{}'", profile), members)
+  }
+
+  /// Start every forward tagged `tag` that is not running yet
+  pub async fn start_tagged<K: KubectlOperations>(
+    &self,
+    kubectl_service: &K,
+    tag: &str,
+  ) -> Result<String> {
+    let members = profiles::tagged(&self.config_cache.get_configs()?, tag)?;
+    self
+      .start_group(kubectl_service, &format!("tag '{}'", tag), members)
+      .await
+  }
+
+  /// Stop every running forward tagged `tag`
+  pub fn stop_tagged(&self, tag: &str) -> Result<String> {
+    let members = profiles::tagged(&self.config_cache.get_configs()?, tag)?;
+    self.stop_group(&format!("tag '{}'", tag), members)
+  }
+
+  fn profile_members(&self, profile: &str) -> Result<Vec<PortForwardConfig>> {
+    profiles::resolve_profile(
+      &self.config_cache.get_profiles()?,
+      profile,
+      &self.config_cache.get_configs()?,
+    )
+  }
+
+  /// Start `members` one by one. One failure does not stop the rest; failures are
+  /// reported together at the end.
+  async fn start_group<K: KubectlOperations>(
+    &self,
+    kubectl_service: &K,
+    group: &str,
+    members: Vec<PortForwardConfig>,
+  ) -> Result<String> {
+    self.verify_port_forwards()?;
+
+    let mut started = Vec::new();
+    let mut failed = Vec::new();
+    for config in members {
+      // Checked per member: an earlier member may have started it as a dependency
+      if self.process_manager.contains_process(&config.name)? {
+        continue;
+      }
+      let name = config.name.clone();
+      match self
+        .start_port_forward_generic(kubectl_service, config)
+        .await
+      {
+        Ok(_) => started.push(name),
+        Err(e) => failed.push(format!("{}: {}", name, e)),
+      }
+    }
+
+    let summary = group_summary(group, "Started", &started);
+    if failed.is_empty() {
+      Ok(summary)
+    } else {
+      Err(AppError::PortForward(format!(
+        "{}; failed to start {}",
+        summary,
+        failed.join("; ")
+      )))
+    }
+  }
+
+  fn stop_group(&self, group: &str, members: Vec<PortForwardConfig>) -> Result<String> {
+    let running = self.get_running_services()?;
+
+    let mut stopped = Vec::new();
+    for config in members.into_iter().filter(|c| running.contains(&c.name)) {
+      self.stop_port_forward(&config.name)?;
+      stopped.push(config.name);
+    }
+    Ok(group_summary(group, "Stopped", &stopped))
+  }
+
   pub fn stop_port_forward(&self, service_name: &str) -> Result<String> {
+    log::info!("[{}] Stopping port forward", service_name);
+
+    // Kills the process, or cancels a multiplexed forward on its SSH master
     let pid = self
       .process_manager
-      .remove_process(service_name)?
+      .stop_process(service_name)?
       .ok_or_else(|| {
         AppError::NotFound(format!("{} port forwarding is not running", service_name))
       })?;
 
     self.last_active.remove(service_name)?;
 
-    log::info!("[{}] Stopping port forward (PID: {})", service_name, pid);
-
-    ProcessManager::kill_process(pid)?;
-
     log::info!("[{}] Port forward stopped successfully", service_name);
 
-    Ok(format!(
-      "Stopped {} port forwarding (PID: {})",
-      service_name, pid
-    ))
+    let message = format!("Stopped {} port forwarding (PID: {})", service_name, pid);
+    let dependents = dependencies::running_dependents(
+      service_name,
+      &self.get_configs()?,
+      &self.process_manager.get_running_services()?,
+    );
+    if dependents.is_empty() {
+      Ok(message)
+    } else {
+      log::warn!(
+        "[{}] Stopped while {} still depend on it",
+        service_name,
+        dependents.join(", ")
+      );
+      Ok(format!(
+        "{}. Still running and depending on it: {}",
+        message,
+        dependents.join(", ")
+      ))
+    }
   }
 
   pub fn get_running_services(&self) -> Result<Vec<String>> {
@@ -436,7 +962,7 @@ impl PortForwardService {
   }
 
   pub fn detect_existing_port_forwards(&self) -> Result<Vec<String>> {
-    let configs = self.config_cache.get_configs()?;
+    let configs = self.config_cache.get_expanded_configs()?;
     // Single ps aux call to detect all running processes
     let running = self.process_detector.detect_running_processes(&configs)?;
     let mut detected_services = Vec::new();
@@ -451,7 +977,7 @@ impl PortForwardService {
   }
 
   pub fn sync_with_existing_processes(&self) -> Result<Vec<String>> {
-    let configs = self.config_cache.get_configs()?;
+    let configs = self.config_cache.get_expanded_configs()?;
     // Single ps aux call to detect all running processes
     let running = self.process_detector.detect_running_processes(&configs)?;
     let mut synced_services = Vec::new();
@@ -471,6 +997,23 @@ impl PortForwardService {
   }
 }
 
+#[async_trait]
+impl ForwardStarter for PortForwardService {
+  async fn start_forward(&self, config: PortForwardConfig) -> Result<String> {
+    // No need to switch contexts - we use --context flag in the kubectl command
+    log::info!(
+      "Starting port forward for {} in context {}",
+      config.name,
+      config.context
+    );
+    self.execute_port_forward(&config).await
+  }
+
+  fn progress(&self, name: &str, message: &str) {
+    log::info!("[{}] {}", name, message);
+  }
+}
+
 fn is_fatal_forward_error(error: &str) -> bool {
   let error = error.to_ascii_lowercase();
   error.contains("error forwarding port")
@@ -479,6 +1022,21 @@ fn is_fatal_forward_error(error: &str) -> bool {
     || error.contains("administratively prohibited")
 }
 
+/// e.g. "Started 2 forward(s) of profile 'backend-dev': api, postgres"
+fn group_summary(group: &str, verb: &str, names: &[String]) -> String {
+  if names.is_empty() {
+    format!("Nothing to do for {}", group)
+  } else {
+    format!(
+      "{} {} forward(s) of {}: {}",
+      verb,
+      names.len(),
+      group,
+      names.join(", ")
+    )
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::is_fatal_forward_error;
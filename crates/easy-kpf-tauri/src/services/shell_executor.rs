@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use easy_kpf_core::error::{AppError, Result};
+use easy_kpf_core::traits::{CommandExecutor, ProcessEvent, ProcessHandle, ProcessOutput};
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+use tokio::sync::mpsc;
+
+/// Runs commands through the shell plugin, for core services that take a
+/// `CommandExecutor`
+pub struct ShellExecutor {
+  app_handle: tauri::AppHandle,
+}
+
+impl ShellExecutor {
+  pub fn new(app_handle: tauri::AppHandle) -> Self {
+    Self { app_handle }
+  }
+}
+
+#[async_trait]
+impl CommandExecutor for ShellExecutor {
+  async fn execute(
+    &self,
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+  ) -> Result<ProcessOutput> {
+    let output = self
+      .app_handle
+      .shell()
+      .command(program)
+      .args(args)
+      .envs(env.iter().cloned())
+      .output()
+      .await
+      .map_err(|e| AppError::Process(format!("Failed to execute {}: {}", program, e)))?;
+
+    Ok(ProcessOutput {
+      stdout: output.stdout,
+      stderr: output.stderr,
+      success: output.status.success(),
+    })
+  }
+
+  async fn spawn(
+    &self,
+    program: &str,
+    args: &[String],
+    env: &[(String, String)],
+  ) -> Result<(ProcessHandle, mpsc::Receiver<ProcessEvent>)> {
+    let (mut rx, child) = self
+      .app_handle
+      .shell()
+      .command(program)
+      .args(args)
+      .envs(env.iter().cloned())
+      .spawn()
+      .map_err(|e| AppError::Process(format!("Failed to spawn {}: {}", program, e)))?;
+
+    let handle = ProcessHandle { pid: child.pid() };
+    let (tx, events) = mpsc::channel(100);
+    tauri::async_runtime::spawn(async move {
+      while let Some(event) = rx.recv().await {
+        let event = match event {
+          CommandEvent::Stdout(line) => ProcessEvent::Stdout(line),
+          CommandEvent::Stderr(line) => ProcessEvent::Stderr(line),
+          CommandEvent::Error(err) => ProcessEvent::Error(err),
+          CommandEvent::Terminated(payload) => ProcessEvent::Terminated { code: payload.code },
+          _ => continue,
+        };
+        if tx.send(event).await.is_err() {
+          break;
+        }
+      }
+    });
+
+    Ok((handle, events))
+  }
+
+  fn spawn_task<F>(&self, future: F)
+  where
+    F: std::future::Future<Output = ()> + Send + 'static,
+  {
+    tauri::async_runtime::spawn(future);
+  }
+}
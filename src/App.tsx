@@ -21,6 +21,8 @@ import ServiceSettings from "./components/ServiceSettings"
 import AddConfigForm from "./components/AddConfigForm"
 import SetupScreen from "./components/SetupScreen"
 import ContextAccordion from "./components/ContextAccordion"
+import ConfigHistory from "./components/ConfigHistory"
+import ImportConfigs from "./components/ImportConfigs"
 import "./App.css"
 import { PortForwardConfig, useConfigs } from "./hooks/hooks"
 import { getConfigGroupKey, groupConfigsByContext } from "./utils/groupingUtils"
@@ -51,6 +53,8 @@ function App() {
   let [showSettings, setShowSettings] = useState(false)
 
   let [showAddForm, setShowAddForm] = useState(false)
+  let [showHistory, setShowHistory] = useState(false)
+  let [showImport, setShowImport] = useState(false)
   let [activeServiceSettings, setActiveServiceSettings] = useState<string | null>(null)
   let [_, setShowConfigForm] = useState(false)
   let [editingConfig, setEditingConfig] = useState<{
@@ -67,10 +71,14 @@ function App() {
     services,
     loading,
     formError,
+    fieldErrors,
     startPortForward,
     addConfig,
+    importConfigs,
     removeConfig,
+    restoreSnapshot,
     updateConfig,
+    validateConfig,
     reorderConfig,
     reorderGroup,
     stopPortForward,
@@ -130,14 +138,23 @@ function App() {
   let filteredConfigs = useMemo(() => {
     if (!searchQuery.trim()) return configs
 
-    let query = searchQuery.toLowerCase()
-    return configs.filter(
-      (c) =>
-        c.name.toLowerCase().includes(query) ||
-        c.service.toLowerCase().includes(query) ||
-        c.namespace.toLowerCase().includes(query) ||
-        c.context.toLowerCase().includes(query) ||
-        c.ports.some((p) => p.includes(query))
+    // Every whitespace-separated term must match; `tag:db` matches tags only
+    let terms = searchQuery.toLowerCase().split(/\s+/).filter((t) => t.length > 0)
+    return configs.filter((c) =>
+      terms.every((term) => {
+        let tags = (c.tags ?? []).map((t) => t.toLowerCase())
+        if (term.startsWith("tag:")) {
+          return tags.some((t) => t.startsWith(term.slice(4)))
+        }
+        return (
+          c.name.toLowerCase().includes(term) ||
+          c.service.toLowerCase().includes(term) ||
+          c.namespace.toLowerCase().includes(term) ||
+          c.context.toLowerCase().includes(term) ||
+          c.ports.some((p) => p.includes(term)) ||
+          tags.some((t) => t.includes(term))
+        )
+      })
     )
   }, [configs, searchQuery])
 
@@ -271,6 +288,20 @@ function App() {
             </svg>
             <span>Reconnect</span>
           </button>
+          <button
+            onClick={() => setShowHistory(true)}
+            className="toolbar-button"
+            title="Restore an earlier configuration"
+          >
+            <span>History</span>
+          </button>
+          <button
+            onClick={() => setShowImport(true)}
+            className="toolbar-button"
+            title="Import port forwards from commands, shell history or a bundle"
+          >
+            <span>Import</span>
+          </button>
           <button onClick={() => setShowAddForm(true)} className="toolbar-button primary-toolbar-button">
             <svg width="14" height="14" viewBox="0 0 14 14" aria-hidden="true">
               <path d="M7 2v10M2 7h10" fill="none" stroke="currentColor" strokeWidth="1.5" strokeLinecap="round" />
@@ -349,11 +380,11 @@ function App() {
 
                   if (!config) return null
 
-                  // Format display differently for SSH vs Kubectl
-                  let displayInfo = config.forward_type === "Ssh"
+                  // Format display differently for SSH, TCP and commands vs Kubectl
+                  let displayInfo = config.forward_type === "Ssh" || config.forward_type === "Tcp" || config.forward_type === "Command"
                     ? {
                         displayName: config.name,
-                        context: config.service, // SSH host
+                        context: config.command?.program ?? config.service, // Remote host or program
                         namespace: config.forward_type, // Show type instead of namespace
                         ports: `Ports: ${config.ports.join(", ")}`
                       }
@@ -375,6 +406,7 @@ function App() {
                       isRunning={service?.running || false}
                       isLoading={loading === config.name}
                       errors={service?.errors}
+                      traffic={service?.traffic}
                       onStart={() => startPortForward(config.name)}
                       onStop={() => stopPortForward(config.name)}
                       onSettings={() => setActiveServiceSettings(config.name)}
@@ -390,15 +422,25 @@ function App() {
       {showAddForm && (
         <AddConfigForm
           onAdd={addConfig}
+          onValidate={validateConfig}
           onClose={() => {
             setShowAddForm(false)
             clearFormError()
           }}
           error={formError}
+          fieldErrors={fieldErrors}
           onClearError={clearFormError}
         />
       )}
 
+      {showHistory && (
+        <ConfigHistory onRestore={restoreSnapshot} onClose={() => setShowHistory(false)} />
+      )}
+
+      {showImport && (
+        <ImportConfigs onImport={importConfigs} onClose={() => setShowImport(false)} />
+      )}
+
       <ServiceSettings
         config={configs.find((c) => c.name === activeServiceSettings) || null}
         onEdit={(config, index) => {
@@ -413,17 +455,16 @@ function App() {
       {editingConfig && (
         <AddConfigForm
           editingConfig={editingConfig}
-          onAdd={() => {}}
-          onUpdate={(oldName, newConfig) => {
-            updateConfig(oldName, newConfig)
-            setEditingConfig(null)
-          }}
+          onAdd={async () => false}
+          onUpdate={updateConfig}
+          onValidate={validateConfig}
           onClose={() => {
             setShowConfigForm(false)
             setEditingConfig(null)
             clearFormError()
           }}
           error={formError}
+          fieldErrors={fieldErrors}
           onClearError={clearFormError}
         />
       )}
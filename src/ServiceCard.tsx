@@ -1,5 +1,7 @@
 import { useSortable } from "@dnd-kit/sortable"
 import { CSS } from "@dnd-kit/utilities"
+import type { TrafficStats } from "./hooks/hooks"
+import { formatBytes } from "./utils/formatBytes"
 
 type ServiceCardProps = {
   id: string
@@ -11,6 +13,7 @@ type ServiceCardProps = {
   isRunning: boolean
   isLoading: boolean
   errors?: string[]
+  traffic?: TrafficStats
   onStart: () => void
   onStop: () => void
   onSettings: () => void
@@ -26,6 +29,7 @@ let ServiceCard = ({
   isRunning,
   isLoading,
   errors,
+  traffic,
   onStart,
   onStop,
   onSettings,
@@ -53,6 +57,15 @@ let ServiceCard = ({
             <span>{namespace}</span>
             <span>{ports.replace(/^Ports:\s*/, "")}</span>
           </div>
+          {isRunning && traffic && (
+            <div className="service-metadata service-traffic">
+              <span>{traffic.active_connections} open</span>
+              <span>{traffic.total_connections} total</span>
+              <span>
+                {formatBytes(traffic.bytes_in)} in / {formatBytes(traffic.bytes_out)} out
+              </span>
+            </div>
+          )}
         </div>
         <div className="service-status-controls">
           {!isRunning ? (
@@ -1,4 +1,4 @@
-import { PortForwardConfig } from "../hooks/hooks"
+import { FieldError, PortForwardConfig } from "../hooks/hooks"
 import { useKubernetesDataFlow } from "../hooks/useKubernetesDataFlow"
 import { useFormState } from "../hooks/useFormState"
 import { useNameDerivation } from "../hooks/useNameDerivation"
@@ -8,12 +8,18 @@ import { FormActions } from "./FormActions"
 import { NameField } from "./NameField"
 import { KubernetesForm } from "./KubernetesForm"
 import { SshForm } from "./SshForm"
+import { TcpForm } from "./TcpForm"
+import { CommandForm } from "./CommandForm"
+import { FieldErrorsContext } from "./FieldErrorText"
+import { ResolvedValues } from "./ResolvedValues"
 
 type AddConfigFormProps = {
-  onAdd: (config: PortForwardConfig) => void
-  onUpdate?: (oldName: string, newConfig: PortForwardConfig) => void
+  onAdd: (config: PortForwardConfig) => Promise<boolean>
+  onUpdate?: (oldName: string, newConfig: PortForwardConfig) => Promise<boolean>
+  onValidate?: (config: PortForwardConfig, oldName?: string) => void
   onClose: () => void
   error?: string
+  fieldErrors?: FieldError[]
   onClearError: () => void
   editingConfig?: {
     config: PortForwardConfig
@@ -24,8 +30,10 @@ type AddConfigFormProps = {
 let AddConfigForm = ({
   onAdd,
   onUpdate,
+  onValidate,
   onClose,
   error,
+  fieldErrors = [],
   onClearError,
   editingConfig,
 }: AddConfigFormProps) => {
@@ -38,6 +46,8 @@ let AddConfigForm = ({
   let formState = useFormState({
     onAdd,
     onUpdate,
+    onValidate,
+    hasFieldErrors: fieldErrors.length > 0,
     onClose,
     editingConfig,
   })
@@ -57,8 +67,9 @@ let AddConfigForm = ({
     ports,
   } = kubernetesData
 
-  let { handleCancel, isEditing, defaultValues } = formState
+  let { handleCancel, isEditing, defaultValues, resolved } = formState
   let handleFormSubmit = formState.handleSubmit(selectedContext, selectedNamespace, selectedService)
+  let handleFormChange = formState.handleChange(selectedContext, selectedNamespace, selectedService)
 
   let {
     connectionType,
@@ -113,54 +124,104 @@ let AddConfigForm = ({
             >
               SSH
             </button>
+            <button
+              type="button"
+              className={connectionType === "tcp" ? "tab-button active" : "tab-button"}
+              onClick={() => setConnectionType("tcp")}
+            >
+              TCP
+            </button>
+            <button
+              type="button"
+              className={connectionType === "command" ? "tab-button active" : "tab-button"}
+              onClick={() => setConnectionType("command")}
+            >
+              Command
+            </button>
           </div>
         </div>
 
-        <form onSubmit={handleFormSubmit}>
-          <NameField
-            nameValue={nameDerivation.nameValue}
-            isNameManuallyChanged={nameDerivation.isNameManuallyChanged}
-            previewName={nameDerivation.previewName}
-            isEditing={isEditing}
-            onNameChange={nameDerivation.updateName}
-            onReset={nameDerivation.resetToAutoGenerated}
-          />
-
-          {connectionType === "kubernetes" ? (
-            <KubernetesForm
-              selectedContext={selectedContext}
-              selectedNamespace={selectedNamespace}
-              selectedService={selectedService}
-              portsInput={portsInput}
-              contexts={contexts}
-              namespaces={namespaces}
-              services={services}
-              ports={ports}
-              defaultLocalInterface={defaultValues.localInterface}
-              editingConfig={editingConfig}
-              onContextChange={setSelectedContext}
-              onNamespaceChange={setSelectedNamespace}
-              onServiceChange={setSelectedService}
-              onPortsChange={setPortsInput}
+        <FieldErrorsContext.Provider value={fieldErrors}>
+          <form onSubmit={handleFormSubmit} onChange={handleFormChange}>
+            <NameField
+              nameValue={nameDerivation.nameValue}
+              isNameManuallyChanged={nameDerivation.isNameManuallyChanged}
+              previewName={nameDerivation.previewName}
+              isEditing={isEditing}
+              onNameChange={nameDerivation.updateName}
+              onReset={nameDerivation.resetToAutoGenerated}
             />
-          ) : (
-            <SshForm
-              sshHost={sshHost}
-              sshPort={sshPort}
-              defaultLocalInterface={defaultValues.localInterface}
-              onSshHostChange={setSshHost}
-              onSshPortChange={setSshPort}
-            />
-          )}
 
-          <input
-            type="hidden"
-            name="forwardType"
-            value={connectionType === "ssh" ? "Ssh" : "Kubectl"}
-          />
+            {connectionType === "kubernetes" ? (
+              <KubernetesForm
+                selectedContext={selectedContext}
+                selectedNamespace={selectedNamespace}
+                selectedService={selectedService}
+                portsInput={portsInput}
+                contexts={contexts}
+                namespaces={namespaces}
+                services={services}
+                ports={ports}
+                defaultLocalInterface={defaultValues.localInterface}
+                editingConfig={editingConfig}
+                onContextChange={setSelectedContext}
+                onNamespaceChange={setSelectedNamespace}
+                onServiceChange={setSelectedService}
+                onPortsChange={setPortsInput}
+              />
+            ) : connectionType === "tcp" ? (
+              <TcpForm
+                host={sshHost}
+                portsInput={portsInput}
+                defaultLocalInterface={defaultValues.localInterface}
+                onHostChange={setSshHost}
+                onPortsChange={setPortsInput}
+              />
+            ) : connectionType === "command" ? (
+              <CommandForm
+                program={sshHost}
+                portsInput={portsInput}
+                defaultLocalInterface={defaultValues.localInterface}
+                defaultCommand={defaultValues.command}
+                onProgramChange={setSshHost}
+                onPortsChange={setPortsInput}
+              />
+            ) : (
+              <SshForm
+                sshHost={sshHost}
+                sshPort={sshPort}
+                defaultLocalInterface={defaultValues.localInterface}
+                defaultSsh={defaultValues.ssh}
+                onSshHostChange={setSshHost}
+                onSshPortChange={setSshPort}
+              />
+            )}
+
+            <input
+              type="hidden"
+              name="forwardType"
+              value={{ kubernetes: "Kubectl", ssh: "Ssh", tcp: "Tcp", command: "Command" }[connectionType]}
+            />
 
-          <FormActions isEditing={isEditing} onCancel={handleCancel} />
-        </form>
+            <div className="form-group">
+              <label>Tags (Optional):</label>
+              <input type="text" name="tags" defaultValue={defaultValues.tags} placeholder="e.g., db, cache" />
+              <small>Comma-separated. Filter with tag:db in search.</small>
+            </div>
+
+            <div className="form-group">
+              <label>
+                <input type="checkbox" name="autoPort" defaultChecked={defaultValues.autoPort} /> Use
+                a free local port if busy
+              </label>
+              <small>Otherwise starting fails when a local port is already in use.</small>
+            </div>
+
+            <ResolvedValues preview={resolved} />
+
+            <FormActions isEditing={isEditing} onCancel={handleCancel} />
+          </form>
+        </FieldErrorsContext.Provider>
       </div>
     </div>
   )
@@ -0,0 +1,107 @@
+import { CommandSettings } from "../hooks/hooks"
+import { FieldErrorText } from "./FieldErrorText"
+
+type CommandFormProps = {
+  program: string
+  portsInput: string
+  defaultLocalInterface: string
+  defaultCommand?: CommandSettings
+  onProgramChange: (program: string) => void
+  onPortsChange: (ports: string) => void
+}
+
+export let CommandForm = ({
+  program,
+  portsInput,
+  defaultLocalInterface,
+  defaultCommand,
+  onProgramChange,
+  onPortsChange,
+}: CommandFormProps) => {
+  let defaultEnv = Object.entries(defaultCommand?.env ?? {})
+    .map(([key, value]) => `${key}=${value}`)
+    .join("\n")
+
+  return (
+    <>
+      <div className="form-group">
+        <label>Program:</label>
+        <input
+          type="text"
+          name="program"
+          value={program}
+          onChange={(e) => onProgramChange(e.target.value)}
+          placeholder="e.g., cloud-sql-proxy, aws, /usr/local/bin/teleport"
+          required
+        />
+        <small>Looked up on your shell PATH</small>
+        <FieldErrorText field="command" />
+      </div>
+
+      <div className="form-group">
+        <label>Arguments:</label>
+        <textarea
+          name="commandArgs"
+          rows={4}
+          defaultValue={defaultCommand?.args?.join("\n") ?? ""}
+          placeholder={"--port={local_port}\n--address={interface}\nproject:region:instance"}
+        />
+        <small>
+          One per line. {"{local_port}"}, {"{remote_port}"} and {"{interface}"} are filled in
+          from the port mapping.
+        </small>
+      </div>
+
+      <div className="form-group">
+        <label>Environment (Optional):</label>
+        <textarea
+          name="commandEnv"
+          rows={2}
+          defaultValue={defaultEnv}
+          placeholder="AWS_PROFILE=staging"
+        />
+        <small>One KEY=value per line</small>
+      </div>
+
+      <div className="form-group">
+        <label>Ready Pattern (Optional):</label>
+        <input
+          type="text"
+          name="readyPattern"
+          defaultValue={defaultCommand?.ready_pattern ?? ""}
+          placeholder="e.g., ready for new connections"
+        />
+        <small>Regex; the forward counts as started once an output line matches</small>
+        <FieldErrorText field="ready_pattern" />
+      </div>
+
+      <div className="form-group">
+        <label>Local Interface (Optional):</label>
+        <input
+          type="text"
+          name="localInterface"
+          defaultValue={defaultLocalInterface}
+          placeholder="e.g., 127.0.0.2, 0.0.0.0"
+        />
+        <small>
+          Bind to specific interface (default: 127.0.0.1). Will create if doesn't exist.
+        </small>
+        <FieldErrorText field="local_interface" />
+      </div>
+
+      <div className="form-group">
+        <label>Port:</label>
+        <input
+          type="text"
+          name="ports"
+          value={portsInput}
+          onChange={(e) => onPortsChange(e.target.value)}
+          placeholder="e.g., 15432:5432"
+          required
+        />
+        <small>A single local:remote mapping</small>
+        <FieldErrorText field="ports" />
+      </div>
+    </>
+  )
+}
@@ -0,0 +1,89 @@
+import { useEffect, useState } from "react"
+import { diffSnapshot, listSnapshots, SnapshotDiff, SnapshotInfo } from "../hooks/hooks"
+import { ErrorBanner } from "./ErrorBanner"
+
+type ConfigHistoryProps = {
+  onRestore: (id: string) => Promise<void>
+  onClose: () => void
+}
+
+let describeDiff = (diff: SnapshotDiff) => {
+  let parts = [
+    diff.added.length > 0 && `adds ${diff.added.join(", ")}`,
+    diff.removed.length > 0 && `removes ${diff.removed.join(", ")}`,
+    diff.changed.length > 0 && `changes ${diff.changed.join(", ")}`,
+  ].filter(Boolean)
+  return parts.length > 0 ? `Restoring ${parts.join("; ")}.` : "Same as the current configuration."
+}
+
+let ConfigHistory = ({ onRestore, onClose }: ConfigHistoryProps) => {
+  let [snapshots, setSnapshots] = useState<SnapshotInfo[]>([])
+  let [selected, setSelected] = useState<string | null>(null)
+  let [diff, setDiff] = useState<SnapshotDiff | null>(null)
+  let [error, setError] = useState<string | undefined>(undefined)
+
+  useEffect(() => {
+    listSnapshots()
+      .then(setSnapshots)
+      .catch((e) => setError(`Error loading history: ${e}`))
+  }, [])
+
+  let select = async (id: string) => {
+    setSelected(id)
+    setDiff(null)
+    try {
+      setDiff(await diffSnapshot(id))
+    } catch (e) {
+      setError(`Error comparing snapshot: ${e}`)
+    }
+  }
+
+  return (
+    <div className="settings-modal">
+      <div className="service-settings-popup">
+        <div className="dialog-heading">
+          <h3>History</h3>
+          <p>The configuration is saved here on every change</p>
+        </div>
+        <ErrorBanner error={error} onClearError={() => setError(undefined)} />
+        <div className="config-details">
+          {snapshots.length === 0 && <p>No snapshots yet</p>}
+          {snapshots.map((snapshot) => (
+            <p key={snapshot.id}>
+              <label>
+                <input
+                  type="radio"
+                  name="snapshot"
+                  checked={selected === snapshot.id}
+                  onChange={() => select(snapshot.id)}
+                />
+                <strong>{new Date(snapshot.created_at).toLocaleString()}</strong>
+              </label>
+              <span>{snapshot.config_count} port forwards</span>
+            </p>
+          ))}
+        </div>
+        {diff && <p>{describeDiff(diff)}</p>}
+        <div className="service-settings-actions">
+          <button
+            onClick={async () => {
+              if (selected) {
+                await onRestore(selected)
+                onClose()
+              }
+            }}
+            className="edit-button"
+            disabled={!selected}
+          >
+            Restore
+          </button>
+          <button onClick={onClose} className="cancel-button">
+            Cancel
+          </button>
+        </div>
+      </div>
+    </div>
+  )
+}
+
+export default ConfigHistory
@@ -1,7 +1,7 @@
 import { SortableContext, useSortable, verticalListSortingStrategy } from "@dnd-kit/sortable"
 import { CSS } from "@dnd-kit/utilities"
 import ServiceCard from "../ServiceCard"
-import { PortForwardConfig, ServiceStatus } from "../hooks/hooks"
+import { PortForwardConfig, ServiceStatus, sshDirectionFlag } from "../hooks/hooks"
 import { GroupedConfig } from "../utils/groupingUtils"
 
 type ContextAccordionProps = {
@@ -53,6 +53,12 @@ let ContextAccordion = ({
     if (configs.some(config => config.forward_type === "Ssh")) {
       return "SSH"
     }
+    if (configs.some(config => config.forward_type === "Tcp")) {
+      return "TCP"
+    }
+    if (configs.some(config => config.forward_type === "Command")) {
+      return "Commands"
+    }
     return context
   }
 
@@ -108,11 +114,13 @@ let ContextAccordion = ({
             {group.configs.map((config) => {
               let service = services.find((s) => s.name === config.name)
 
-              let displayInfo = config.forward_type === "Ssh"
+              let displayInfo = config.forward_type === "Ssh" || config.forward_type === "Tcp" || config.forward_type === "Command"
                 ? {
                     displayName: config.name,
-                    context: config.service,
-                    namespace: config.forward_type,
+                    context: config.command?.program ?? config.service,
+                    namespace: config.forward_type === "Ssh"
+                      ? `${config.forward_type} ${sshDirectionFlag(config.ssh?.direction)}`
+                      : config.forward_type,
                     ports: `Ports: ${config.ports.join(", ")}`
                   }
                 : {
@@ -134,6 +142,7 @@ let ContextAccordion = ({
                   isRunning={service?.running || false}
                   isLoading={loading === config.name}
                   errors={service?.errors}
+                  traffic={service?.traffic}
                   onStart={() => onStart(config.name)}
                   onStop={() => onStop(config.name)}
                   onSettings={() => onSettings(config.name)}
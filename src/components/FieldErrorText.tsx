@@ -0,0 +1,20 @@
+import { createContext, useContext } from "react"
+import type { ConfigField, FieldError } from "../hooks/hooks"
+
+// Problems of the config in the open form, from the last rejected save
+export let FieldErrorsContext = createContext<FieldError[]>([])
+
+export let FieldErrorText = ({ field }: { field: ConfigField }) => {
+  let errors = useContext(FieldErrorsContext).filter((error) => error.field === field)
+  if (errors.length === 0) return null
+
+  return (
+    <>
+      {errors.map((error) => (
+        <small key={error.message} className="field-error">
+          {error.message}
+        </small>
+      ))}
+    </>
+  )
+}
@@ -0,0 +1,87 @@
+import { useState } from "react"
+import { ImportReport, previewImport } from "../hooks/hooks"
+import { ErrorBanner } from "./ErrorBanner"
+
+type ImportConfigsProps = {
+  onImport: (text?: string) => Promise<void>
+  onClose: () => void
+}
+
+let describeReport = (report: ImportReport) => {
+  let names = report.configs.map((config) => config.name)
+  let parts = [
+    names.length > 0 ? `Adds ${names.join(", ")}.` : "Nothing new to add.",
+    report.duplicates > 0 && `${report.duplicates} already configured.`,
+    report.skipped.length > 0 && `Skipped: ${report.skipped.join("; ")}`,
+  ].filter(Boolean)
+  return parts.join(" ")
+}
+
+let ImportConfigs = ({ onImport, onClose }: ImportConfigsProps) => {
+  let [text, setText] = useState("")
+  let [report, setReport] = useState<ImportReport | null>(null)
+  let [error, setError] = useState<string | undefined>(undefined)
+
+  let preview = async (value: string) => {
+    setReport(null)
+    try {
+      setReport(await previewImport(value || undefined))
+    } catch (e) {
+      setError(`Error reading port forwards: ${e}`)
+    }
+  }
+
+  let openFile = async (file?: File) => {
+    if (!file) return
+    let content = await file.text()
+    setText(content)
+    await preview(content)
+  }
+
+  return (
+    <div className="settings-modal">
+      <div className="service-settings-popup">
+        <div className="dialog-heading">
+          <h3>Import</h3>
+          <p>kubectl port-forward and ssh -L commands, or an exported bundle</p>
+        </div>
+        <ErrorBanner error={error} onClearError={() => setError(undefined)} />
+        <div className="form-group">
+          <textarea
+            rows={6}
+            value={text}
+            onChange={(e) => {
+              setText(e.target.value)
+              setReport(null)
+            }}
+            placeholder="kubectl port-forward -n dev svc/api 8080:80"
+          />
+          <small>Leave empty to look through your shell history</small>
+        </div>
+        <div className="form-group">
+          <input type="file" onChange={(e) => openFile(e.target.files?.[0])} />
+        </div>
+        {report && <p>{describeReport(report)}</p>}
+        <div className="service-settings-actions">
+          <button onClick={() => preview(text)} className="test-button">
+            Preview
+          </button>
+          <button
+            onClick={async () => {
+              await onImport(text || undefined)
+              onClose()
+            }}
+            className="edit-button"
+          >
+            Import
+          </button>
+          <button onClick={onClose} className="cancel-button">
+            Cancel
+          </button>
+        </div>
+      </div>
+    </div>
+  )
+}
+
+export default ImportConfigs
@@ -1,5 +1,6 @@
 import { KubernetesSelect } from "./KubernetesSelect"
 import { PortSuggestions } from "./PortSuggestions"
+import { FieldErrorText } from "./FieldErrorText"
 import { PortForwardConfig } from "../hooks/hooks"
 
 type KubernetesFormProps = {
@@ -95,6 +96,7 @@ export let KubernetesForm = ({
         <small>
           Bind to specific interface (default: 127.0.0.1). Will create if doesn't exist.
         </small>
+        <FieldErrorText field="local_interface" />
       </div>
 
       <div className="form-group">
@@ -109,6 +111,33 @@ export let KubernetesForm = ({
           required
         />
         <small>Comma-separated list of local:remote ports</small>
+        <FieldErrorText field="ports" />
+      </div>
+
+      <div className="form-group">
+        <label>
+          <input
+            type="checkbox"
+            name="native"
+            defaultChecked={editingConfig?.config.forward_type === "Native"}
+          />{" "}
+          Forward without kubectl
+        </label>
+        <small>Runs the port-forward inside EasyKpf through the Kubernetes API.</small>
+      </div>
+
+      <div className="form-group">
+        <label>
+          <input
+            type="checkbox"
+            name="proxy"
+            defaultChecked={editingConfig?.config.proxy ?? false}
+          />{" "}
+          Keep the local port open across kubectl restarts
+        </label>
+        <small>
+          EasyKpf listens on the port itself and restarts kubectl behind it. Ignored without kubectl.
+        </small>
       </div>
     </>
   )
@@ -1,8 +1,9 @@
-import { PortForwardConfig } from "../hooks/hooks"
+import { ConfigField, PortForwardConfig } from "../hooks/hooks"
+import { FieldErrorText } from "./FieldErrorText"
 
 type KubernetesSelectProps = {
   label: string
-  name: string
+  name: ConfigField
   value: string
   onChange: (value: string) => void
   options: string[]
@@ -77,6 +78,7 @@ export let KubernetesSelect = ({
           <div className="loading-progress"></div>
         </div>
       )}
+      <FieldErrorText field={name} />
     </div>
   )
 }
\ No newline at end of file
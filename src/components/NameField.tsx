@@ -1,4 +1,5 @@
 import { useRef } from "react"
+import { FieldErrorText } from "./FieldErrorText"
 
 type NameFieldProps = {
   nameValue: string
@@ -50,6 +51,7 @@ export let NameField = ({
         )}
       </div>
       <small>Leave empty to auto-generate. Updates as you select service/host and ports.</small>
+      <FieldErrorText field="name" />
     </div>
   )
 }
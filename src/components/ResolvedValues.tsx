@@ -0,0 +1,29 @@
+import type { ResolvedPreview } from "../hooks/useFormState"
+
+type ResolvedValuesProps = {
+  preview: ResolvedPreview | null
+}
+
+export let ResolvedValues = ({ preview }: ResolvedValuesProps) => {
+  if (!preview) return null
+
+  let config = preview.config
+  let target = config
+    ? config.forward_type === "Command" && config.command
+      ? [config.command.program, ...(config.command.args ?? [])].join(" ")
+      : [config.context, config.namespace, config.service].filter(Boolean).join(" / ")
+    : ""
+
+  return (
+    <div className="form-group">
+      <label>Resolved Values</label>
+      {config ? (
+        <small>
+          {target} → {config.ports.join(", ")}
+        </small>
+      ) : (
+        <small className="field-error">{preview.error}</small>
+      )}
+    </div>
+  )
+}
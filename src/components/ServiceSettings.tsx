@@ -12,6 +12,7 @@ let ServiceSettings = ({ config, onEdit, onDelete, onClose, configs }: ServiceSe
   if (!config) return null
 
   let index = configs.findIndex((c) => c.name === config.name)
+  let includedFrom = config.source?.kind === "include" ? config.source.path : null
 
   return (
     <div className="settings-modal">
@@ -33,6 +34,11 @@ let ServiceSettings = ({ config, onEdit, onDelete, onClose, configs }: ServiceSe
           <p>
             <strong>Ports</strong><span>{config.ports.join(", ")}</span>
           </p>
+          {includedFrom && (
+            <p>
+              <strong>Included from</strong><span>{includedFrom} (read-only)</span>
+            </p>
+          )}
         </div>
         <div className="service-settings-actions">
           <button
@@ -41,6 +47,7 @@ let ServiceSettings = ({ config, onEdit, onDelete, onClose, configs }: ServiceSe
               onClose()
             }}
             className="edit-button"
+            disabled={includedFrom !== null}
           >
             Edit...
           </button>
@@ -50,6 +57,7 @@ let ServiceSettings = ({ config, onEdit, onDelete, onClose, configs }: ServiceSe
               onClose()
             }}
             className="delete-button"
+            disabled={includedFrom !== null}
           >
             Delete
           </button>
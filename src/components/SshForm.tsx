@@ -1,9 +1,14 @@
+import { useState } from "react"
+import { fetchHostKeys, HostKey, pinHostKeys, SshSettings } from "../hooks/hooks"
+import { readSshSettings } from "../hooks/useFormState"
 import { useSshTesting } from "../hooks/useSshTesting"
+import { FieldErrorText } from "./FieldErrorText"
 
 type SshFormProps = {
   sshHost: string
   sshPort: string
   defaultLocalInterface: string
+  defaultSsh: SshSettings
   onSshHostChange: (host: string) => void
   onSshPortChange: (port: string) => void
 }
@@ -12,10 +17,44 @@ export let SshForm = ({
   sshHost,
   sshPort,
   defaultLocalInterface,
+  defaultSsh,
   onSshHostChange,
   onSshPortChange,
 }: SshFormProps) => {
   let { testStatus, testMessage, testSshConnection } = useSshTesting()
+  let [hostKeys, setHostKeys] = useState<HostKey[] | null>(null)
+  let [hostKeyMessage, setHostKeyMessage] = useState("")
+
+  let verifyHostKey = async (form: HTMLFormElement | null) => {
+    setHostKeys(null)
+    setHostKeyMessage("Fetching host keys...")
+    try {
+      let keys = await fetchHostKeys({
+        name: "",
+        context: sshHost,
+        namespace: "default",
+        service: sshHost,
+        ports: [],
+        forward_type: "Ssh",
+        ssh: form ? readSshSettings(new FormData(form)) : undefined,
+      })
+      setHostKeys(keys)
+      setHostKeyMessage("Compare these fingerprints with the ones from the server's admin before pinning")
+    } catch (error) {
+      setHostKeyMessage(error as string)
+    }
+  }
+
+  let pin = async () => {
+    if (!hostKeys) return
+    try {
+      await pinHostKeys(hostKeys)
+      setHostKeys(null)
+      setHostKeyMessage("Host key pinned")
+    } catch (error) {
+      setHostKeyMessage(error as string)
+    }
+  }
 
   return (
     <>
@@ -37,6 +76,7 @@ export let SshForm = ({
           }
         />
         <small>SSH connection string (user@host or just host)</small>
+        <FieldErrorText field="service" />
       </div>
 
       <div className="form-group">
@@ -57,6 +97,7 @@ export let SshForm = ({
           }
         />
         <small>Port mapping in format local:remote</small>
+        <FieldErrorText field="ports" />
       </div>
 
       <div className="form-group">
@@ -70,6 +111,110 @@ export let SshForm = ({
         <small>
           Bind to specific interface (default: 127.0.0.1). Will create if doesn't exist.
         </small>
+        <FieldErrorText field="local_interface" />
+      </div>
+
+      <div className="form-group">
+        <label>Direction:</label>
+        <select name="sshDirection" defaultValue={defaultSsh.direction ?? "local"}>
+          <option value="local">Local (-L): reach a remote port here</option>
+          <option value="remote">Remote (-R): expose a local port on the SSH host</option>
+          <option value="dynamic">Dynamic (-D): SOCKS proxy</option>
+        </select>
+        <small>For dynamic forwards the port is the local SOCKS port, e.g. 1080</small>
+      </div>
+
+      <div className="form-group">
+        <label>SSH User (Optional):</label>
+        <input type="text" name="sshUser" defaultValue={defaultSsh.user ?? ""} placeholder="e.g., deploy" />
+        <FieldErrorText field="ssh_user" />
+      </div>
+
+      <div className="form-group">
+        <label>SSH Port (Optional):</label>
+        <input
+          type="number"
+          name="sshConnectionPort"
+          min={1}
+          max={65535}
+          defaultValue={defaultSsh.port ?? ""}
+          placeholder="22"
+        />
+        <FieldErrorText field="ssh_port" />
+      </div>
+
+      <div className="form-group">
+        <label>Identity File (Optional):</label>
+        <input
+          type="text"
+          name="identityFile"
+          defaultValue={defaultSsh.identity_file ?? ""}
+          placeholder="e.g., ~/.ssh/id_ed25519"
+        />
+        <FieldErrorText field="identity_file" />
+      </div>
+
+      <div className="form-group">
+        <label>Jump Hosts (Optional):</label>
+        <input
+          type="text"
+          name="jumpHosts"
+          defaultValue={defaultSsh.jump_hosts?.join(", ") ?? ""}
+          placeholder="e.g., bastion, user@gateway:2222"
+        />
+        <small>ProxyJump chain, comma-separated, in connection order</small>
+        <FieldErrorText field="jump_hosts" />
+      </div>
+
+      <div className="form-group">
+        <label>Remote Host (Optional):</label>
+        <input
+          type="text"
+          name="remoteHost"
+          defaultValue={defaultSsh.remote_host ?? ""}
+          placeholder="localhost"
+        />
+        <small>Local: where the port forwards to, as seen from the SSH host. Remote: the address it listens on there</small>
+        <FieldErrorText field="remote_host" />
+      </div>
+
+      <div className="form-group">
+        <label>Host Key Policy:</label>
+        <select name="hostKeyPolicy" defaultValue={defaultSsh.host_key_policy ?? ""}>
+          <option value="">Default (from app settings)</option>
+          <option value="strict">Strict: only pinned keys</option>
+          <option value="accept-new">Accept new: pin on first connect</option>
+          <option value="off">Off: don't check</option>
+        </select>
+      </div>
+
+      <div className="form-group">
+        <label>
+          <input type="checkbox" name="multiplex" defaultChecked={defaultSsh.multiplex} /> Share the
+          connection
+        </label>
+        <small>Forwards to the same user, host and port that share it use one SSH connection.</small>
+      </div>
+
+      <div className="form-group">
+        <button type="button" className="test-button" onClick={(e) => verifyHostKey(e.currentTarget.form)}>
+          Verify Host Key
+        </button>
+        {hostKeyMessage && <small>{hostKeyMessage}</small>}
+        {hostKeys && (
+          <>
+            {hostKeys.map((key) => (
+              <div key={key.key_type}>
+                <code>
+                  {key.key_type} {key.fingerprint}
+                </code>
+              </div>
+            ))}
+            <button type="button" className="edit-button" onClick={pin}>
+              Pin
+            </button>
+          </>
+        )}
       </div>
 
       <div className="form-group">
@@ -77,7 +222,10 @@ export let SshForm = ({
           type="button"
           className={`test-button ${testStatus === "testing" ? "testing" : ""}`}
           disabled={testStatus === "testing"}
-          onClick={() => testSshConnection(sshHost)}
+          onClick={(e) => {
+            let form = e.currentTarget.form
+            testSshConnection(sshHost, form ? readSshSettings(new FormData(form)) : undefined)
+          }}
         >
           {testStatus === "testing" ? "Testing..." : "Test Connection"}
         </button>
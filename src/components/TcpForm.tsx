@@ -0,0 +1,63 @@
+import { FieldErrorText } from "./FieldErrorText"
+
+type TcpFormProps = {
+  host: string
+  portsInput: string
+  defaultLocalInterface: string
+  onHostChange: (host: string) => void
+  onPortsChange: (ports: string) => void
+}
+
+export let TcpForm = ({
+  host,
+  portsInput,
+  defaultLocalInterface,
+  onHostChange,
+  onPortsChange,
+}: TcpFormProps) => {
+  return (
+    <>
+      <div className="form-group">
+        <label>Host:</label>
+        <input
+          type="text"
+          name="tcpHost"
+          value={host}
+          onChange={(e) => onHostChange(e.target.value)}
+          placeholder="e.g., db.vpn.internal or 10.0.0.5"
+          required
+        />
+        <small>Connections are relayed to this host by EasyKpf itself</small>
+        <FieldErrorText field="service" />
+      </div>
+
+      <div className="form-group">
+        <label>Local Interface (Optional):</label>
+        <input
+          type="text"
+          name="localInterface"
+          defaultValue={defaultLocalInterface}
+          placeholder="e.g., 127.0.0.2, 0.0.0.0"
+        />
+        <small>
+          Bind to specific interface (default: 127.0.0.1). Will create if doesn't exist.
+        </small>
+        <FieldErrorText field="local_interface" />
+      </div>
+
+      <div className="form-group">
+        <label>Ports:</label>
+        <input
+          type="text"
+          name="ports"
+          value={portsInput}
+          onChange={(e) => onPortsChange(e.target.value)}
+          placeholder="e.g., 5432, 15432:5432"
+          required
+        />
+        <small>Comma-separated list of local:remote ports</small>
+        <FieldErrorText field="ports" />
+      </div>
+    </>
+  )
+}
@@ -10,13 +10,54 @@ type ServiceErrorEvent = {
   fatal?: boolean
 }
 
+export type TrafficStats = {
+  active_connections: number
+  total_connections: number
+  bytes_in: number
+  bytes_out: number
+  last_activity?: number
+}
+
 export type ServiceStatus = {
   name: string
   running: boolean
   errors?: string[]
+  traffic?: TrafficStats
+}
+
+export type ForwardType = "Kubectl" | "Ssh" | "Native" | "Tcp" | "Command"
+
+export type SshDirection = "local" | "remote" | "dynamic"
+
+export let sshDirectionFlag = (direction?: SshDirection) =>
+  direction === "remote" ? "-R" : direction === "dynamic" ? "-D" : "-L"
+
+export type HostKeyPolicy = "strict" | "accept-new" | "off"
+
+export type HostKey = {
+  host: string
+  key_type: string
+  key: string
+  fingerprint: string
+}
+
+export type SshSettings = {
+  direction?: SshDirection
+  user?: string
+  port?: number
+  identity_file?: string
+  jump_hosts?: string[]
+  remote_host?: string
+  host_key_policy?: HostKeyPolicy
+  multiplex?: boolean
 }
 
-export type ForwardType = "Kubectl" | "Ssh"
+export type CommandSettings = {
+  program: string
+  args?: string[]
+  env?: Record<string, string>
+  ready_pattern?: string
+}
 
 export type PortForwardConfig = {
   name: string
@@ -26,8 +67,73 @@ export type PortForwardConfig = {
   ports: string[]
   local_interface?: string
   forward_type: ForwardType
+  ssh?: SshSettings
+  command?: CommandSettings
+  proxy?: boolean
+  auto_port?: boolean
+  tags?: string[]
+  depends_on?: string[]
+  source?:
+    | { kind: "global" }
+    | { kind: "project"; path: string }
+    | { kind: "include"; path: string }
 }
 
+export type ConfigField =
+  | "name"
+  | "context"
+  | "namespace"
+  | "service"
+  | "ports"
+  | "local_interface"
+  | "ssh_user"
+  | "ssh_port"
+  | "identity_file"
+  | "jump_hosts"
+  | "remote_host"
+  | "command"
+  | "ready_pattern"
+
+export type FieldError = {
+  field: ConfigField
+  message: string
+}
+
+export type ImportReport = {
+  configs: PortForwardConfig[]
+  skipped: string[]
+  duplicates: number
+}
+
+export type SnapshotInfo = {
+  id: string
+  created_at: number
+  config_count: number
+}
+
+export type SnapshotDiff = {
+  added: string[]
+  removed: string[]
+  changed: string[]
+}
+
+// `config` with `${VAR}` placeholders resolved from the config files' variables
+export let resolveConfig = (config: PortForwardConfig) =>
+  invoke<PortForwardConfig>("resolve_port_forward_config", { config })
+
+// What importing would add, without saving
+export let previewImport = (text?: string) =>
+  invoke<ImportReport>("import_port_forwards", { text, dryRun: true })
+
+export let listSnapshots = () => invoke<SnapshotInfo[]>("list_config_snapshots")
+
+export let diffSnapshot = (id: string) => invoke<SnapshotDiff>("diff_config_snapshot", { id })
+
+export let fetchHostKeys = (config: PortForwardConfig) =>
+  invoke<HostKey[]>("fetch_ssh_host_keys", { config })
+
+export let pinHostKeys = (keys: HostKey[]) => invoke<void>("pin_ssh_host_keys", { keys })
+
 export let useConfigs = (
   setMessage: (msg: string) => void,
   setAvailablePorts: (ports: string[]) => void,
@@ -39,12 +145,15 @@ export let useConfigs = (
   let [services, setServices] = useState<ServiceStatus[]>([])
   let [loading, setLoading] = useState<string | null>(null)
   let [formError, setFormError] = useState<string | undefined>(undefined)
+  let [fieldErrors, setFieldErrors] = useState<FieldError[]>([])
 
   let loadConfigs = async () => {
     try {
       let loadedConfigs: PortForwardConfig[] = await invoke("get_port_forward_configs")
       setConfigs(loadedConfigs)
       setServices(loadedConfigs.map((config) => ({ name: config.name, running: false })))
+      let recoveryNotice: string | null = await invoke("take_config_recovery_notice")
+      if (recoveryNotice) setMessage(recoveryNotice)
     } catch (error) {
       console.error("Failed to load configs:", error)
       setMessage(`Error loading configs: ${error}`)
@@ -122,9 +231,26 @@ export let useConfigs = (
       )
     })
 
+    // The config files were edited elsewhere (TUI, editor, another window)
+    let unlistenConfigsPromise = listen("configs-changed", () => {
+      loadConfigs().then(updateServiceStatus)
+    })
+
+    // Connection counters of native, TCP and stable-port forwards
+    let applyTraffic = (traffic: Record<string, TrafficStats>) =>
+      setServices((prev) => prev.map((service) => ({ ...service, traffic: traffic[service.name] })))
+    invoke<Record<string, TrafficStats>>("get_service_traffic")
+      .then(applyTraffic)
+      .catch((error) => console.error("Failed to get service traffic:", error))
+    let unlistenTrafficPromise = listen<Record<string, TrafficStats>>("service-traffic", (event) => {
+      applyTraffic(event.payload)
+    })
+
     return () => {
       clearInterval(verificationInterval)
       unlistenPromise.then((unlisten) => unlisten())
+      unlistenConfigsPromise.then((unlisten) => unlisten())
+      unlistenTrafficPromise.then((unlisten) => unlisten())
     }
   }, [])
 
@@ -159,14 +285,56 @@ export let useConfigs = (
     }
   }
 
+  // Saving rejects invalid configs with the problem of each field
+  let showSaveError = (prefix: string, error: unknown) => {
+    if (Array.isArray(error)) {
+      setFieldErrors(error as FieldError[])
+      setFormError("Fix the marked fields to save")
+    } else {
+      setFieldErrors([])
+      setFormError(`${prefix}: ${error}`)
+    }
+  }
+
+  // Resolves to whether the config was saved, so the form stays open otherwise
   let addConfig = async (config: PortForwardConfig) => {
     setFormError(undefined)
     try {
       await invoke("add_port_forward_config", { config })
+      setFieldErrors([])
       await loadConfigs()
       setMessage(`Added configuration for ${config.name}`)
+      return true
+    } catch (error) {
+      showSaveError("Error adding config", error)
+      return false
+    }
+  }
+
+  // Re-check a form that was rejected, so fixed fields lose their errors as you type
+  let validateConfig = async (config: PortForwardConfig, oldServiceKey?: string) => {
+    try {
+      let errors: FieldError[] = await invoke("validate_port_forward_config", {
+        config,
+        oldServiceKey,
+      })
+      setFieldErrors(errors)
+      if (errors.length === 0) setFormError(undefined)
+    } catch (error) {
+      console.error("Failed to validate config:", error)
+    }
+  }
+
+  // Pasted `kubectl port-forward` / `ssh -L` commands, or shell history when empty
+  let importConfigs = async (text?: string) => {
+    try {
+      let report: ImportReport = await invoke("import_port_forwards", { text, dryRun: false })
+      await loadConfigs()
+      setMessage(
+        `Imported ${report.configs.length} port forwards (${report.duplicates} already configured, ${report.skipped.length} skipped)`,
+      )
     } catch (error) {
-      setFormError(`Error adding config: ${error}`)
+      setMessage(`Error importing port forwards: ${error}`)
     }
   }
 
@@ -180,17 +348,32 @@ export let useConfigs = (
     }
   }
 
+  let restoreSnapshot = async (id: string) => {
+    try {
+      await invoke("restore_config_snapshot", { id })
+      await loadConfigs()
+      await updateServiceStatus()
+      setMessage("Restored configuration snapshot")
+    } catch (error) {
+      setMessage(`Error restoring snapshot: ${error}`)
+    }
+  }
+
   let updateConfig = async (oldServiceKey: string, newConfig: PortForwardConfig) => {
+    setFormError(undefined)
     try {
       await invoke("update_port_forward_config", { 
         oldServiceKey: oldServiceKey, 
         newConfig: newConfig 
       })
+      setFieldErrors([])
       await loadConfigs()
       await updateServiceStatus()
       setMessage(`Updated configuration for ${newConfig.name}`)
+      return true
     } catch (error) {
-      setMessage(`Error updating config: ${error}`)
+      showSaveError("Error updating config", error)
+      return false
     }
   }
 
@@ -259,7 +442,7 @@ export let useConfigs = (
     if (!context || !namespace) return
     setFormError(undefined)
     try {
-      let services: string[] = await invoke("get_services", { context, namespace })
+      let services: string[] = await invoke("get_forward_targets", { context, namespace })
       setAvailableServices(services)
       setMessage("")
     } catch (error) {
@@ -326,6 +509,7 @@ export let useConfigs = (
 
   let clearFormError = () => {
     setFormError(undefined)
+    setFieldErrors([])
   }
 
   let reconnectAll = async () => {
@@ -337,12 +521,16 @@ export let useConfigs = (
     services,
     loading,
     formError,
+    fieldErrors,
     loadConfigs,
     updateServiceStatus,
     startPortForward,
     addConfig,
+    importConfigs,
     removeConfig,
+    restoreSnapshot,
     updateConfig,
+    validateConfig,
     reorderConfig,
     reorderGroup,
     loadContexts,
@@ -8,9 +8,24 @@ type UseConnectionFormProps = {
   } | null
 }
 
+export type ConnectionType = "kubernetes" | "ssh" | "tcp" | "command"
+
+let connectionTypeOf = (config?: PortForwardConfig): ConnectionType => {
+  switch (config?.forward_type) {
+    case "Ssh":
+      return "ssh"
+    case "Tcp":
+      return "tcp"
+    case "Command":
+      return "command"
+    default:
+      return "kubernetes"
+  }
+}
+
 export let useConnectionForm = ({ editingConfig }: UseConnectionFormProps) => {
-  let [connectionType, setConnectionType] = useState<"kubernetes" | "ssh">(
-    editingConfig?.config.forward_type === "Ssh" ? "ssh" : "kubernetes"
+  let [connectionType, setConnectionType] = useState<ConnectionType>(
+    connectionTypeOf(editingConfig?.config)
   )
   let [sshHost, setSshHost] = useState("")
   let [sshPort, setSshPort] = useState("")
@@ -22,6 +37,14 @@ export let useConnectionForm = ({ editingConfig }: UseConnectionFormProps) => {
       if (editingConfig.config.forward_type === "Ssh") {
         setSshHost(editingConfig.config.context)
         setSshPort(editingConfig.config.ports[0] || "")
+      } else if (editingConfig.config.forward_type === "Tcp") {
+        // The relay's target host shares the SSH host field's state
+        setSshHost(editingConfig.config.service)
+        setPortsInput(editingConfig.config.ports.join(", "))
+      } else if (editingConfig.config.forward_type === "Command") {
+        // So does the command's program, which names the forward
+        setSshHost(editingConfig.config.command?.program || "")
+        setPortsInput(editingConfig.config.ports.join(", "))
       } else {
         setPortsInput(editingConfig.config.ports.join(", "))
       }
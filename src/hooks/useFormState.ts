@@ -1,24 +1,90 @@
-import { PortForwardConfig } from "./hooks"
+import { useRef, useState } from "react"
+import {
+  CommandSettings,
+  ForwardType,
+  HostKeyPolicy,
+  PortForwardConfig,
+  SshDirection,
+  SshSettings,
+  resolveConfig,
+} from "./hooks"
+
+// A config using `${VAR}` placeholders, as it will be started
+export type ResolvedPreview = { config?: PortForwardConfig; error?: string }
+
+let usesPlaceholders = (config: PortForwardConfig) => JSON.stringify(config).includes("${")
 
 export let deriveConfigName = (
-  forwardType: "Kubectl" | "Ssh",
+  forwardType: ForwardType,
   selectedService: string,
   sshHost: string,
   ports: string[]
 ): string => {
-  if (forwardType === "Ssh") {
+  if (forwardType === "Ssh" || forwardType === "Tcp") {
     let host = sshHost.split("@").pop() || sshHost
     let port = ports[0]?.split(":")[0] || "unknown"
     return `${host}-${port}`
+  } else if (forwardType === "Command") {
+    let program = sshHost.split("/").pop() || sshHost
+    let port = ports[0]?.split(":")[0] || "unknown"
+    return `${program}-${port}`
   } else {
     let port = ports[0]?.split(":")[0] || "unknown"
     return `${selectedService}-${port}`
   }
 }
 
+export let readSshSettings = (formData: FormData): SshSettings | undefined => {
+  let text = (field: string) => (formData.get(field) as string | null)?.trim() || undefined
+  let port = text("sshConnectionPort")
+  let direction = text("sshDirection") as SshDirection | undefined
+  let jumpHosts = (text("jumpHosts") || "")
+    .split(",")
+    .map((h) => h.trim())
+    .filter((h) => h.length > 0)
+
+  let ssh: SshSettings = {
+    direction: direction === "local" ? undefined : direction,
+    user: text("sshUser"),
+    port: port ? Number(port) : undefined,
+    identity_file: text("identityFile"),
+    jump_hosts: jumpHosts.length > 0 ? jumpHosts : undefined,
+    remote_host: text("remoteHost"),
+    host_key_policy: text("hostKeyPolicy") as HostKeyPolicy | undefined,
+    multiplex: formData.get("multiplex") === "on" || undefined,
+  }
+  return Object.values(ssh).some((v) => v !== undefined) ? ssh : undefined
+}
+
+// Args and env are one per line, so args may contain spaces or JSON
+export let readCommandSettings = (formData: FormData): CommandSettings => {
+  let lines = (field: string) =>
+    ((formData.get(field) as string | null) || "")
+      .split("\n")
+      .map((line) => line.trim())
+      .filter((line) => line.length > 0)
+  let env = Object.fromEntries(
+    lines("commandEnv").map((line) => {
+      let [key, ...value] = line.split("=")
+      return [key.trim(), value.join("=")]
+    })
+  )
+  let args = lines("commandArgs")
+  let readyPattern = (formData.get("readyPattern") as string | null)?.trim()
+
+  return {
+    program: (formData.get("program") as string).trim(),
+    args: args.length > 0 ? args : undefined,
+    env: Object.keys(env).length > 0 ? env : undefined,
+    ready_pattern: readyPattern || undefined,
+  }
+}
+
 type FormStateProps = {
-  onAdd: (config: PortForwardConfig) => void
-  onUpdate?: (oldName: string, newConfig: PortForwardConfig) => void
+  onAdd: (config: PortForwardConfig) => Promise<boolean>
+  onUpdate?: (oldName: string, newConfig: PortForwardConfig) => Promise<boolean>
+  onValidate?: (config: PortForwardConfig, oldName?: string) => void
+  hasFieldErrors?: boolean
   onClose: () => void
   editingConfig?: {
     config: PortForwardConfig
@@ -26,12 +92,47 @@ type FormStateProps = {
   } | null
 }
 
-export let useFormState = ({ onAdd, onUpdate, onClose, editingConfig }: FormStateProps) => {
-  let handleSubmit = (selectedContext: string, selectedNamespace: string, selectedService: string) => (e: React.FormEvent) => {
-    e.preventDefault()
-    let formData = new FormData(e.target as HTMLFormElement)
-    let forwardType = formData.get("forwardType") as "Kubectl" | "Ssh"
+export let useFormState = ({
+  onAdd,
+  onUpdate,
+  onValidate,
+  hasFieldErrors,
+  onClose,
+  editingConfig,
+}: FormStateProps) => {
+  let [resolved, setResolved] = useState<ResolvedPreview | null>(null)
+  let latestPreview = useRef(0)
+
+  let previewConfig = async (config: PortForwardConfig) => {
+    let request = ++latestPreview.current
+    if (!usesPlaceholders(config)) {
+      setResolved(null)
+      return
+    }
+    let preview: ResolvedPreview
+    try {
+      preview = { config: await resolveConfig(config) }
+    } catch (error) {
+      preview = { error: `${error}` }
+    }
+    // Only the newest edit is shown
+    if (request === latestPreview.current) setResolved(preview)
+  }
+
+  let buildConfig = (
+    form: HTMLFormElement,
+    selectedContext: string,
+    selectedNamespace: string,
+    selectedService: string
+  ): PortForwardConfig => {
+    let formData = new FormData(form)
+    let forwardType = formData.get("forwardType") as ForwardType
     let providedName = formData.get("name") as string
+    let autoPort = formData.get("autoPort") === "on"
+    let tags = (formData.get("tags") as string)
+      .split(",")
+      .map((t) => t.trim())
+      .filter((t) => t.length > 0)
 
     let config: PortForwardConfig
 
@@ -51,6 +152,51 @@ export let useFormState = ({ onAdd, onUpdate, onClose, editingConfig }: FormStat
         ports: ports,
         local_interface: localInterface || undefined,
         forward_type: "Ssh",
+        ssh: readSshSettings(formData),
+        auto_port: autoPort,
+        tags: tags.length > 0 ? tags : undefined,
+        depends_on: editingConfig?.config.depends_on,
+      }
+    } else if (forwardType === "Tcp") {
+      let host = (formData.get("tcpHost") as string).trim()
+      let ports = (formData.get("ports") as string)
+        .split(",")
+        .map((p) => p.trim())
+        .filter((p) => p.length > 0)
+      let localInterface = formData.get("localInterface") as string
+
+      config = {
+        name: providedName || deriveConfigName(forwardType, "", host, ports),
+        context: "",
+        namespace: "default",
+        service: host,
+        ports: ports,
+        local_interface: localInterface || undefined,
+        forward_type: "Tcp",
+        auto_port: autoPort,
+        tags: tags.length > 0 ? tags : undefined,
+        depends_on: editingConfig?.config.depends_on,
+      }
+    } else if (forwardType === "Command") {
+      let command = readCommandSettings(formData)
+      let ports = (formData.get("ports") as string)
+        .split(",")
+        .map((p) => p.trim())
+        .filter((p) => p.length > 0)
+      let localInterface = formData.get("localInterface") as string
+
+      config = {
+        name: providedName || deriveConfigName(forwardType, "", command.program, ports),
+        context: "",
+        namespace: "default",
+        service: "",
+        ports: ports,
+        local_interface: localInterface || undefined,
+        forward_type: "Command",
+        command,
+        auto_port: autoPort,
+        tags: tags.length > 0 ? tags : undefined,
+        depends_on: editingConfig?.config.depends_on,
       }
     } else {
       let portsString = formData.get("ports") as string
@@ -69,33 +215,59 @@ export let useFormState = ({ onAdd, onUpdate, onClose, editingConfig }: FormStat
         service: selectedService,
         ports: ports,
         local_interface: localInterface || undefined,
-        forward_type: "Kubectl",
+        forward_type: formData.get("native") === "on" ? "Native" : "Kubectl",
+        proxy: formData.get("proxy") === "on" || undefined,
+        auto_port: autoPort,
+        tags: tags.length > 0 ? tags : undefined,
+        depends_on: editingConfig?.config.depends_on,
       }
     }
 
-    if (editingConfig && onUpdate) {
-      onUpdate(editingConfig.config.name, config)
-    } else {
-      onAdd(config)
-    }
-    onClose()
+    return config
   }
 
+  let handleSubmit =
+    (selectedContext: string, selectedNamespace: string, selectedService: string) =>
+    async (e: React.FormEvent<HTMLFormElement>) => {
+      e.preventDefault()
+      let config = buildConfig(e.currentTarget, selectedContext, selectedNamespace, selectedService)
+      let saved =
+        editingConfig && onUpdate
+          ? await onUpdate(editingConfig.config.name, config)
+          : await onAdd(config)
+      // A rejected config keeps the form open with its field errors
+      if (saved) onClose()
+    }
+
+  let handleChange =
+    (selectedContext: string, selectedNamespace: string, selectedService: string) =>
+    (e: React.FormEvent<HTMLFormElement>) => {
+      let config = buildConfig(e.currentTarget, selectedContext, selectedNamespace, selectedService)
+      previewConfig(config)
+      if (hasFieldErrors && onValidate) onValidate(config, editingConfig?.config.name)
+    }
+
   let handleCancel = () => {
     onClose()
   }
 
   return {
     handleSubmit,
+    handleChange,
     handleCancel,
+    resolved,
     isEditing: !!editingConfig,
     defaultValues: {
       name: editingConfig?.config.name || "",
       ports: editingConfig?.config.ports.join(", ") || "",
       localInterface: editingConfig?.config.local_interface || "",
       forwardType: editingConfig?.config.forward_type || "Kubectl",
+      autoPort: editingConfig?.config.auto_port ?? false,
+      tags: editingConfig?.config.tags?.join(", ") || "",
       sshHost: editingConfig?.config.forward_type === "Ssh" ? editingConfig?.config.context : "",
       sshPort: editingConfig?.config.forward_type === "Ssh" ? editingConfig?.config.ports[0] : "",
+      ssh: editingConfig?.config.ssh ?? {},
+      command: editingConfig?.config.command,
     },
   }
 }
\ No newline at end of file
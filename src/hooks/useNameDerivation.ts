@@ -1,8 +1,9 @@
 import { useState, useEffect } from "react"
 import { deriveConfigName } from "./useFormState"
+import { ConnectionType } from "./useConnectionForm"
 
 type UseNameDerivationProps = {
-  connectionType: "kubernetes" | "ssh"
+  connectionType: ConnectionType
   selectedContext: string
   selectedNamespace: string
   selectedService: string
@@ -28,6 +29,10 @@ export let useNameDerivation = ({
   let previewName = (() => {
     if (connectionType === "ssh" && sshHost && sshPort) {
       return deriveConfigName("Ssh", "", sshHost, [sshPort])
+    } else if (connectionType === "tcp" && sshHost && portsInput) {
+      return deriveConfigName("Tcp", "", sshHost, portsInput.split(",").map(p => p.trim()))
+    } else if (connectionType === "command" && sshHost && portsInput) {
+      return deriveConfigName("Command", "", sshHost, portsInput.split(",").map(p => p.trim()))
     } else if (connectionType === "kubernetes" && selectedService && portsInput) {
       let portsArray = portsInput.split(",").map(p => p.trim()).filter(p => p.length > 0)
       if (portsArray.length > 0) {
@@ -47,6 +52,18 @@ export let useNameDerivation = ({
         } else if (sshHost) {
           derivedName = sshHost.split("@").pop() || sshHost
         }
+      } else if (connectionType === "tcp") {
+        if (sshHost && portsInput) {
+          derivedName = deriveConfigName("Tcp", "", sshHost, portsInput.split(",").map(p => p.trim()))
+        } else if (sshHost) {
+          derivedName = sshHost
+        }
+      } else if (connectionType === "command") {
+        if (sshHost && portsInput) {
+          derivedName = deriveConfigName("Command", "", sshHost, portsInput.split(",").map(p => p.trim()))
+        } else if (sshHost) {
+          derivedName = sshHost.split("/").pop() || sshHost
+        }
       } else if (connectionType === "kubernetes") {
         if (selectedService && portsInput) {
           let portsArray = portsInput.split(",").map(p => p.trim()).filter(p => p.length > 0)
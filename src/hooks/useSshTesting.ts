@@ -1,11 +1,12 @@
 import { useState } from "react"
 import { invoke } from "@tauri-apps/api/core"
+import { SshSettings } from "./hooks"
 
 export let useSshTesting = () => {
   let [testStatus, setTestStatus] = useState<"idle" | "testing" | "success" | "error">("idle")
   let [testMessage, setTestMessage] = useState("")
 
-  let testSshConnection = async (sshHost: string) => {
+  let testSshConnection = async (sshHost: string, ssh?: SshSettings) => {
     if (!sshHost) {
       setTestStatus("error")
       setTestMessage("Please enter SSH host")
@@ -16,7 +17,7 @@ export let useSshTesting = () => {
     setTestMessage("Testing SSH connection...")
 
     try {
-      let result = await invoke<string>("test_ssh_connection", { sshHost })
+      let result = await invoke<string>("test_ssh_connection", { sshHost, ssh: ssh ?? null })
       setTestStatus("success")
       setTestMessage(result)
     } catch (error) {
@@ -0,0 +1,13 @@
+const UNITS = ["KiB", "MiB", "GiB", "TiB"]
+
+// e.g. "512 B", "1.2 KiB", "3.4 MiB"
+export let formatBytes = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} B`
+  let value = bytes / 1024
+  let unit = 0
+  while (value >= 1024 && unit < UNITS.length - 1) {
+    value /= 1024
+    unit += 1
+  }
+  return `${value.toFixed(1)} ${UNITS[unit]}`
+}
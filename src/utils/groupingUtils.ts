@@ -5,8 +5,18 @@ export type GroupedConfig = {
   configs: PortForwardConfig[]
 }
 
-export let getConfigGroupKey = (config: PortForwardConfig) =>
-  config.forward_type === "Ssh" ? "SSH" : config.context
+export let getConfigGroupKey = (config: PortForwardConfig) => {
+  switch (config.forward_type) {
+    case "Ssh":
+      return "SSH"
+    case "Tcp":
+      return "TCP"
+    case "Command":
+      return "Commands"
+    default:
+      return config.context
+  }
+}
 
 export let groupConfigsByContext = (configs: PortForwardConfig[]): GroupedConfig[] => {
   let grouped = configs.reduce((acc, config) => {
@@ -7,7 +7,7 @@ export let loadKubernetesNamespaces = (context: string): Promise<string[]> =>
   invoke("get_namespaces", { context })
 
 export let loadKubernetesServices = (context: string, namespace: string): Promise<string[]> => 
-  invoke("get_services", { context, namespace })
+  invoke("get_forward_targets", { context, namespace })
 
 export let loadKubernetesPorts = (context: string, namespace: string, service: string): Promise<string[]> => 
   invoke("get_service_ports", { context, namespace, service })
\ No newline at end of file